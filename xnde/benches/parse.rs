@@ -0,0 +1,110 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! Parser benchmarks
+//!
+//! Parse synthetic, in-memory libraries of a thousand & a hundred thousand tracks, reporting
+//! throughput in tracks per second. Also parse a library on disk through data file buffers of
+//! various sizes. Run with `cargo bench`.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use xnde::{read_indicies, BufSeeker, ParseOptions, Tracks};
+
+use std::{fs::File, io::Cursor};
+
+/// Generate a library of `n` tracks; return the index & data files
+fn library(n: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut table = Table::new();
+    add_meta(&mut table);
+    for i in 0..n {
+        table.add_record(&[
+            string(
+                FILENAME_COL,
+                FILENAME,
+                &format!("/music/artist{}/track{}.mp3", i % 100, i),
+            ),
+            string(ARTIST_COL, STRING, &format!("Artist {}", i % 100)),
+            string(TITLE_COL, STRING, &format!("Track {}", i)),
+            integer(LENGTH_COL, LENGTH, 180 + (i % 120) as i32),
+            integer(YEAR_COL, INTEGER, 1960 + (i % 60) as i32),
+            int64(FILESIZE_COL, 3_000_000 + i as i64),
+        ]);
+    }
+    (table.index(), table.buf)
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for n in [1_000usize, 100_000] {
+        let (idx, dat) = library(n);
+        group.throughput(Throughput::Elements(n as u64));
+        group.sample_size(if n > 10_000 { 10 } else { 100 });
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, _| {
+            b.iter(|| {
+                let opts = ParseOptions::default();
+                let idxes = read_indicies(&mut Cursor::new(&idx), &opts).unwrap();
+                let primary = idxes.into_iter().next().unwrap();
+                Tracks::new(Cursor::new(&dat), primary, &opts)
+                    .unwrap()
+                    .map(|t| t.unwrap())
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Parse a library on disk, reading the data file directly & through buffers of various sizes
+fn buffer(c: &mut Criterion) {
+    let n = 10_000usize;
+    let (idx, dat) = library(n);
+    let path = std::env::temp_dir().join(format!("xnde-bench-{}.dat", std::process::id()));
+    std::fs::write(&path, &dat).unwrap();
+
+    let mut group = c.benchmark_group("buffer");
+    group.throughput(Throughput::Elements(n as u64));
+    group.sample_size(20);
+    for size in [0usize, 8 * 1024, 64 * 1024, 1024 * 1024] {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| {
+                let opts = ParseOptions::default();
+                let idxes = read_indicies(&mut Cursor::new(&idx), &opts).unwrap();
+                let primary = idxes.into_iter().next().unwrap();
+                let fd = File::open(&path).unwrap();
+                if size == 0 {
+                    Tracks::new(fd, primary, &opts)
+                        .unwrap()
+                        .map(|t| t.unwrap())
+                        .count()
+                } else {
+                    Tracks::new(BufSeeker::with_capacity(size, fd), primary, &opts)
+                        .unwrap()
+                        .map(|t| t.unwrap())
+                        .count()
+                }
+            })
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(benches, parse, buffer);
+criterion_main!(benches);
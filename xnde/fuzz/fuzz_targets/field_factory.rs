@@ -0,0 +1,37 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! Feed arbitrary bytes to the field parser
+//!
+//! The first byte selects the field type; the remainder is taken to be everything following the
+//! field's ID & type on disk (size, next & previous offsets, then the field-specific data).
+//! Parsing may fail, but must never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xnde::{
+    fields::{field_factory, FieldType},
+    ParseOptions,
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((ftype, mut rest)) = data.split_first() {
+        if let Ok(ftype) = FieldType::from(*ftype) {
+            let _ = field_factory(&mut rest, 0, ftype, 0, &ParseOptions::default());
+        }
+    }
+});
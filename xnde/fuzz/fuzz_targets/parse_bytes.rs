@@ -0,0 +1,37 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! Feed an arbitrary index & data file pair to the full parser
+//!
+//! The first four bytes give the length of the index file (little-endian); it follows, and the
+//! data file is whatever remains. This reaches everything the field & index targets don't:
+//! redirects, the chaining of fields into records, the column definitions & so on. Parsing may
+//! fail, but must never panic, hang or exhaust memory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xnde::{parse_bytes, ParseOptions};
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 4 {
+        return;
+    }
+    let (len, rest) = data.split_at(4);
+    let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    let (idx, dat) = rest.split_at(std::cmp::min(len, rest.len()));
+    let _ = parse_bytes(idx, dat, &ParseOptions::default());
+});
@@ -0,0 +1,443 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! Helpers for assembling NDE fields, records & tables byte by byte, for tests & benchmarks
+//!
+//! Rather than hand-writing byte arrays, tests can describe a record as a list of (column ID,
+//! [`FieldValue`]) pairs & have it laid out here; where the precise on-disk form matters (UTF-8
+//! rather than UTF-16 text, say), [`Field`]s can be built directly. Everything is written
+//! little-endian, with each field header pointing at its neighbors:
+//!
+//! ```ignore
+//!     +----+------+----------+------+------+------+
+//!     | ID | type | max_size | next | prev | data |
+//!     +----+------+----------+------+------+------+
+//!       u8    u8      u32       u32    u32
+//! ```
+//!
+//! Note that the offset of the *next* field precedes that of the previous one.
+//!
+//! This module is compiled into the crate's own unit tests; integration tests & benchmarks get it
+//! via the `test-util` feature.
+//!
+//! [`FieldValue`]: ../fields/enum.FieldValue.html
+//! [`Field`]: type.Field.html
+
+use crate::fields::{FieldType, FieldValue, FIELD_HEADER_SIZE};
+
+/// A field as laid out on disk, less its header: its ID, type & field-specific data
+pub type Field = (u8, FieldType, Vec<u8>);
+
+/// Encode `text` as NDE does: a u16 byte count, followed by UTF-16LE text with a BOM
+fn string_data(text: &str) -> Vec<u8> {
+    let mut text16: Vec<u8> = vec![0xff, 0xfe];
+    for x in text.encode_utf16() {
+        text16.extend_from_slice(&x.to_le_bytes());
+    }
+    let mut data: Vec<u8> = Vec::with_capacity(text16.len() + 2);
+    data.extend_from_slice(&(text16.len() as u16).to_le_bytes());
+    data.extend_from_slice(&text16);
+    data
+}
+
+/// Return the type & on-disk data for `value`
+///
+/// When parsed, column & index fields give their ID as the first member of their value; since the
+/// ID is given separately here, the builder instead takes it to be the column's type (for
+/// `FieldValue::Column`) or the index's position (for `FieldValue::Index`). Index fields are
+/// written with an empty name & bitmaps as BINARY fields. Values with no on-disk representation
+/// (`Unknown`, `Boolean` & `Float`, which we don't parse) panic.
+pub fn field_data(value: &FieldValue) -> (FieldType, Vec<u8>) {
+    match value {
+        FieldValue::Column((col_type, name)) => {
+            let mut data = vec![*col_type as u8, 0, name.len() as u8];
+            data.extend_from_slice(name.as_bytes());
+            (FieldType::Column, data)
+        }
+        FieldValue::Index((pos, ftype)) => {
+            let mut data: Vec<u8> = Vec::new();
+            data.extend_from_slice(&pos.to_le_bytes());
+            data.extend_from_slice(&ftype.to_le_bytes());
+            data.push(0);
+            (FieldType::Index, data)
+        }
+        FieldValue::String(text) => (FieldType::String, string_data(text)),
+        FieldValue::Integer(x) => (FieldType::Integer, x.to_le_bytes().to_vec()),
+        FieldValue::Datetime(x) => (FieldType::Datetime, x.to_le_bytes().to_vec()),
+        FieldValue::Length(x) => (FieldType::Length, x.to_le_bytes().to_vec()),
+        FieldValue::Filename(path) => (FieldType::Filename, string_data(&path.to_string_lossy())),
+        FieldValue::Int64(x) => (FieldType::Int64, x.to_le_bytes().to_vec()),
+        FieldValue::Private { owner, data } => {
+            let mut buf = owner.to_vec();
+            buf.extend_from_slice(data);
+            (FieldType::Private, buf)
+        }
+        FieldValue::Bitmap(payload) => {
+            let mut data = (payload.len() as u16).to_le_bytes().to_vec();
+            data.extend_from_slice(payload);
+            (FieldType::Binary, data)
+        }
+        _ => panic!("no on-disk representation for {:?}", value),
+    }
+}
+
+/// Build the [`Field`] with ID `id` holding `value` (cf. [`field_data`])
+///
+/// [`Field`]: type.Field.html
+/// [`field_data`]: fn.field_data.html
+pub fn field(id: u8, value: &FieldValue) -> Field {
+    let (ftype, data) = field_data(value);
+    (id, ftype, data)
+}
+
+/// A column definition, with ID `id`, naming a column of type `col_type`
+pub fn column(id: u8, col_type: FieldType, name: &str) -> Field {
+    let mut data = vec![col_type as u8, 0, name.len() as u8];
+    data.extend_from_slice(name.as_bytes());
+    (id, FieldType::Column, data)
+}
+
+/// An index definition, with ID `id`; unlike [`field_data`], this takes the index's name
+///
+/// [`field_data`]: fn.field_data.html
+pub fn index(id: u8, pos: u32, ftype: i32, name: &str) -> Field {
+    let mut data: Vec<u8> = Vec::new();
+    data.extend_from_slice(&pos.to_le_bytes());
+    data.extend_from_slice(&ftype.to_le_bytes());
+    data.push(name.len() as u8);
+    data.extend_from_slice(name.as_bytes());
+    (id, FieldType::Index, data)
+}
+
+/// A STRING (or FILENAME) field holding `text` as UTF-8, with no BOM
+pub fn string(id: u8, ftype: FieldType, text: &str) -> Field {
+    let mut data: Vec<u8> = Vec::new();
+    data.extend_from_slice(&(text.len() as u16).to_le_bytes());
+    data.extend_from_slice(text.as_bytes());
+    (id, ftype, data)
+}
+
+/// A STRING field holding `text` as UTF-16LE, with a BOM
+pub fn utf16_string(id: u8, text: &str) -> Field {
+    (id, FieldType::String, string_data(text))
+}
+
+/// An INTEGER (or LENGTH, or DATETIME) field
+pub fn integer(id: u8, ftype: FieldType, x: i32) -> Field {
+    (id, ftype, x.to_le_bytes().to_vec())
+}
+
+/// An INT64 field
+pub fn int64(id: u8, x: i64) -> Field {
+    (id, FieldType::Int64, x.to_le_bytes().to_vec())
+}
+
+/// Assemble the body of a field (its header, less ID & type, followed by `data`), as the field
+/// constructors (`StringField::new`, e.g.) expect to read it
+pub fn body(data: &[u8], next: u32, prev: u32) -> Vec<u8> {
+    let mut buf: Vec<u8> = Vec::with_capacity(FIELD_HEADER_SIZE - 2 + data.len());
+    buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&next.to_le_bytes());
+    buf.extend_from_slice(&prev.to_le_bytes());
+    buf.extend_from_slice(data);
+    buf
+}
+
+/// Assemble the body of a field holding `value` (cf. [`body`])
+///
+/// [`body`]: fn.body.html
+pub fn field_body(value: &FieldValue, next: u32, prev: u32) -> Vec<u8> {
+    body(&field_data(value).1, next, prev)
+}
+
+/// Lay out `fields` as a record beginning at offset `at` in the data file
+pub fn raw_record(at: u32, fields: &[Field]) -> Vec<u8> {
+    let mut buf: Vec<u8> = Vec::new();
+    let mut prev: u32 = 0;
+    for (i, (id, ftype, data)) in fields.iter().enumerate() {
+        let here = at + buf.len() as u32;
+        let next = if i + 1 < fields.len() {
+            here + (FIELD_HEADER_SIZE + data.len()) as u32
+        } else {
+            0
+        };
+        buf.push(*id);
+        buf.push(*ftype as u8);
+        buf.extend_from_slice(&body(data, next, prev));
+        prev = here;
+    }
+    buf
+}
+
+/// Lay out `values` as a record beginning at offset `at` in the data file
+pub fn record(at: u32, values: &[(u8, FieldValue)]) -> Vec<u8> {
+    raw_record(
+        at,
+        &values
+            .iter()
+            .map(|(id, value)| field(*id, value))
+            .collect::<Vec<Field>>(),
+    )
+}
+
+/// Lay out `records` as an NDE table, returning the index file (with a primary index only) & the
+/// data file
+pub fn table(records: &[Vec<(u8, FieldValue)>]) -> (Vec<u8>, Vec<u8>) {
+    let mut dat: Vec<u8> = b"NDETABLE".to_vec();
+    let mut idx: Vec<u8> = b"NDEINDEX".to_vec();
+    idx.extend_from_slice(&(records.len() as u32).to_le_bytes());
+    idx.extend_from_slice(&255i32.to_le_bytes());
+    for (i, values) in records.iter().enumerate() {
+        let at = dat.len() as u32;
+        dat.extend_from_slice(&record(at, values));
+        idx.extend_from_slice(&at.to_le_bytes());
+        idx.extend_from_slice(&(i as i32).to_le_bytes());
+    }
+    (idx, dat)
+}
+
+/// Accumulates an NDE data file one record at a time
+pub struct Table {
+    pub buf: Vec<u8>,
+    /// Offset of each record, in the order in which they were added
+    pub offsets: Vec<u32>,
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+impl Table {
+    pub fn new() -> Table {
+        Table {
+            buf: b"NDETABLE".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+    /// Append a record made up of `fields`
+    pub fn add_record(&mut self, fields: &[Field]) {
+        let at = self.buf.len() as u32;
+        self.offsets.push(at);
+        self.buf.extend_from_slice(&raw_record(at, fields));
+    }
+    /// Produce an index file with a primary index (ID 255) listing the records in the order in
+    /// which they were added, and a second (ID 0) listing them in reverse
+    pub fn index(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = b"NDEINDEX".to_vec();
+        buf.extend_from_slice(&(self.offsets.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&255i32.to_le_bytes());
+        for off in &self.offsets {
+            buf.extend_from_slice(&off.to_le_bytes());
+            buf.extend_from_slice(&0i32.to_le_bytes());
+        }
+        buf.extend_from_slice(&0i32.to_le_bytes());
+        for off in self.offsets.iter().rev() {
+            buf.extend_from_slice(&off.to_le_bytes());
+            buf.extend_from_slice(&0i32.to_le_bytes());
+        }
+        buf
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+
+    /// Every value we can write should parse back to itself
+    #[test]
+    fn fields() -> Result<(), String> {
+        use super::*;
+        use crate::fields::{field_factory, ColumnField, IndexField};
+
+        let values = vec![
+            FieldValue::String(String::from("Björk")),
+            FieldValue::Integer(1997),
+            FieldValue::Datetime(1680373805),
+            FieldValue::Length(305),
+            FieldValue::Filename(std::path::PathBuf::from("C:\\music\\joga.mp3")),
+            FieldValue::Int64(4882432),
+            FieldValue::Private {
+                owner: [7; 16],
+                data: vec![1, 2, 3],
+            },
+            FieldValue::Bitmap(b"\x89PNG\r\n\x1a\n".to_vec()),
+        ];
+        for value in values {
+            let (ftype, _) = field_data(&value);
+            let body = field_body(&value, 0x40, 0x20);
+            let field = field_factory(
+                &mut body.as_slice(),
+                3,
+                ftype,
+                0,
+                &crate::ParseOptions::default(),
+            )
+            .map_err(|err| format!("{}", err))?;
+            assert_eq!(field.id(), 3);
+            assert_eq!(field.next_field_pos(), 0x40);
+            assert_eq!(field.prev_field_pos(), 0x20);
+            assert_eq!(format!("{:?}", field.value()), format!("{:?}", value));
+        }
+
+        let value = FieldValue::Column((FieldType::Filename as i32, String::from("filename")));
+        let col = ColumnField::new(
+            &mut field_body(&value, 0, 0).as_slice(),
+            3,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(col.col_type(), FieldType::Filename);
+        assert_eq!(col.name(), "filename");
+
+        let value = FieldValue::Index((4, 12));
+        let index = IndexField::new(
+            &mut field_body(&value, 0, 0).as_slice(),
+            1,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(index.pos(), 4);
+        assert_eq!(index.ftype(), 12);
+        Ok(())
+    }
+
+    /// Records & tables should be laid out with each field pointing at its neighbors
+    #[test]
+    fn tables() -> Result<(), String> {
+        use super::*;
+        let (idx, dat) = table(&[
+            vec![
+                (
+                    0,
+                    FieldValue::Column((FieldType::Filename as i32, String::from("filename"))),
+                ),
+                (
+                    1,
+                    FieldValue::Column((FieldType::String as i32, String::from("artist"))),
+                ),
+            ],
+            vec![
+                (0, FieldValue::Filename(std::path::PathBuf::from("a.mp3"))),
+                (1, FieldValue::String(String::from("ABBA"))),
+            ],
+        ]);
+        let mut idxes = crate::read_indicies(
+            &mut std::io::Cursor::new(idx),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(idxes.len(), 1);
+        assert_eq!(idxes[0].len(), 2);
+        assert_eq!(idxes[0].off(0), 8);
+
+        let mut tracks = crate::Tracks::new(
+            std::io::Cursor::new(dat),
+            idxes.remove(0),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let (i, trk) = tracks
+            .next()
+            .ok_or_else(|| String::from("no tracks"))?
+            .map_err(|err| format!("{}", err))?;
+        assert_eq!(i, 1);
+        assert_eq!(trk.filename(), std::path::Path::new("a.mp3"));
+        assert_eq!(trk.artist(), Some("ABBA"));
+        assert!(tracks.next().is_none());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod round_trip_props {
+
+    use super::*;
+    use crate::fields::{field_factory, ColumnField};
+    use proptest::prelude::*;
+
+    /// Integers with the edge cases over-represented
+    fn int32() -> impl Strategy<Value = i32> {
+        prop_oneof![
+            Just(0),
+            Just(-1),
+            Just(i32::MIN),
+            Just(i32::MAX),
+            any::<i32>()
+        ]
+    }
+
+    /// Any value the field constructors can parse back; bitmaps are only recognized as such by
+    /// their signature, so each payload is given a PNG signature
+    fn field_value() -> impl Strategy<Value = FieldValue> {
+        const PNG: &[u8] = b"\x89PNG\r\n\x1a\n";
+        prop_oneof![
+            ".{0,128}".prop_map(FieldValue::String),
+            int32().prop_map(FieldValue::Integer),
+            int32().prop_map(FieldValue::Datetime),
+            int32().prop_map(FieldValue::Length),
+            ".{0,128}".prop_map(|x| FieldValue::Filename(std::path::PathBuf::from(x))),
+            prop_oneof![Just(0), Just(i64::MIN), Just(i64::MAX), any::<i64>()]
+                .prop_map(FieldValue::Int64),
+            (
+                any::<[u8; 16]>(),
+                prop::collection::vec(any::<u8>(), 0..256)
+            )
+                .prop_map(|(owner, data)| FieldValue::Private {
+                    owner: owner,
+                    data: data,
+                }),
+            prop_oneof![
+                Just(u16::MAX as usize - PNG.len()),
+                0..(u16::MAX as usize - PNG.len())
+            ]
+            .prop_flat_map(|n| prop::collection::vec(any::<u8>(), n))
+            .prop_map(|tail| {
+                let mut payload = PNG.to_vec();
+                payload.extend(tail);
+                FieldValue::Bitmap(payload)
+            }),
+        ]
+    }
+
+    proptest! {
+        /// Writing a field & parsing it back should give the same value, header & size
+        #[test]
+        fn fields(value in field_value(), id in any::<u8>(), next in any::<u32>(),
+                  prev in any::<u32>()) {
+            let (ftype, data) = field_data(&value);
+            let body = field_body(&value, next, prev);
+            prop_assert_eq!(body.len(), FIELD_HEADER_SIZE - 2 + data.len());
+            let field = field_factory(&mut body.as_slice(), id as i32, ftype, 0, &crate::ParseOptions::default())
+                .map_err(|err| TestCaseError::fail(format!("{}", err)))?;
+            prop_assert_eq!(field.id(), id as i32);
+            prop_assert_eq!(field.next_field_pos(), next as u64);
+            prop_assert_eq!(field.prev_field_pos(), prev as u64);
+            prop_assert_eq!(field.serialized_len(), FIELD_HEADER_SIZE + data.len());
+            prop_assert_eq!(format!("{:?}", field.value()), format!("{:?}", value));
+        }
+
+        /// Likewise column definitions
+        #[test]
+        fn columns(name in "[a-z_]{0,64}", col_type in 0u8..16) {
+            let value = FieldValue::Column((col_type as i32, name.clone()));
+            let col = ColumnField::new(&mut field_body(&value, 0, 0).as_slice(), 0, &crate::ParseOptions::default())
+                .map_err(|err| TestCaseError::fail(format!("{}", err)))?;
+            prop_assert_eq!(col.col_type() as u8, col_type);
+            prop_assert_eq!(col.name(), name);
+        }
+    }
+}
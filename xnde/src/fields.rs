@@ -117,8 +117,15 @@
 //!
 //! `cb` is a sixteen-bit, little-endian unsigned integer containing the number of bytes in the
 //! filename or string. The text _may_ be UTF-16 encoded; in that case we expect a BOM. Else the
-//! reference implementation simply copies the bytes; this implementation assumes UTF-8. Note that
-//! the string is not null-terminated.
+//! reference implementation simply copies the bytes; this implementation assumes UTF-8 (unless
+//! asked to guess at UTF-16LE via [`ParseOptions::guess_utf16`]), failing on invalid text unless
+//! told otherwise via [`ParseOptions::string_fallback`]. Those whose text is known to be UTF-8 may
+//! disable the BOM check altogether via [`ParseOptions::strict_utf8`]. Note that the string is not
+//! null-terminated.
+//!
+//! [`ParseOptions::guess_utf16`]: ../struct.ParseOptions.html#method.guess_utf16
+//! [`ParseOptions::strict_utf8`]: ../struct.ParseOptions.html#method.strict_utf8
+//! [`ParseOptions::string_fallback`]: ../struct.ParseOptions.html#method.string_fallback
 //!
 //! ### Index
 //!
@@ -133,6 +140,17 @@
 //! 32-bit, LE unsigned int describing the length of the `name` field, which is the ASCII text
 //! of the filter name.
 //!
+//! ### Private
+//!
+//! ```ignore
+//!     +-------------+------+
+//!     | owner: GUID | data |
+//!     +-------------+------+
+//! ```
+//!
+//! A blob private to some plugin; the owning plugin is identified by the leading sixteen-byte
+//! GUID, and the rest of the field (i.e. `max_size - 16` bytes) is opaque.
+//!
 //! ### Int64
 //!
 //! ```ignore
@@ -155,11 +173,21 @@
 //! cases, but it _is_ a signed integer (i.e. not a simple Unix-style "seconds-since-epoch" value
 //! for time, or seconds for length).
 //!
+//! Lengths are usually in seconds, but some versions of Winamp (or some of its plugins) write
+//! milliseconds instead, and Winamp uses -1 for "unknown". Since no track is a day long, a length
+//! greater than 86400 is taken to be in milliseconds, unless told otherwise (cf.
+//! [`ParseOptions::length_units`]).
+//!
+//! [`ParseOptions::length_units`]: ../struct.ParseOptions.html#method.length_units
+//!
 
 use parse_display::Display;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::ParseOptions;
+
 use std::io::Read;
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -174,13 +202,25 @@ pub enum Cause {
     /// Unknown field type
     #[display("Uknown field type {}")]
     BadFieldType(u8),
+    /// Failed to parse a field-- cf. source.
+    #[display("Failed to parse a field of type {field_type} at offset {offset:#x}-- cf. source.")]
+    FieldParse { offset: u64, field_type: FieldType },
+    /// A field claimed a size too small to hold its contents
+    #[display("A field claimed a size of {size} bytes, which is too small for its type.")]
+    FieldTooSmall { size: usize },
+    /// A field claimed a size larger than the configured maximum
+    #[display("A field claimed a size of {size} bytes, which exceeds the maximum.")]
+    FieldTooLarge { size: usize },
+    /// Windows-1252 decoding was requested, but we were built without encoding support
+    #[display(
+        "Can't decode strings as Windows-1252; xnde was built without the `encoding' feature"
+    )]
+    NoCp1252,
 }
 
-#[derive(Debug, Display)]
-#[display("{cause} Source (if any): {source} Stack trace (if any): {trace}")]
+#[derive(Debug)]
 pub struct Error {
     /// Enumerated status code
-    #[display("XNDE error {}.")]
     cause: Cause,
     // This is an Option that may contain a Box containing something that implements
     // std::error::Error.  It is still unclear to me how this satisfies the lifetime bound in
@@ -188,13 +228,9 @@ pub struct Error {
     // lifetime. There is a discussion of this at
     // <https://users.rust-lang.org/t/what-does-it-mean-to-return-dyn-error-static/37619/6>,
     // but at the time of this writing, i cannot follow it.
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("fields error caused by {:#?}.")]
-    source: Option<Box<dyn std::error::Error>>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
     /// Optional backtrace
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("backtrace: {:#?}.")]
-    trace: Option<backtrace::Backtrace>,
+    trace: Option<crate::Backtrace>,
 }
 
 impl Error {
@@ -203,9 +239,27 @@ impl Error {
         Error {
             cause: cause,
             source: None,
-            trace: Some(backtrace::Backtrace::new()),
+            trace: crate::capture_backtrace(),
         }
     }
+    /// Wrap this error in one noting the offset & type of the field that was being parsed when it
+    /// occurred
+    pub fn at(self, offset: u64, field_type: FieldType) -> Error {
+        Error {
+            cause: Cause::FieldParse {
+                offset: offset,
+                field_type: field_type,
+            },
+            source: Some(Box::new(self)),
+            trace: crate::capture_backtrace(),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        crate::fmt_error(f, &self.cause, self, self.trace.as_ref())
+    }
 }
 
 impl std::error::Error for Error {
@@ -223,44 +277,239 @@ impl std::error::Error for Error {
     }
 }
 
-impl std::convert::From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
+error_from!(std::io::Error);
+error_from!(std::string::FromUtf8Error);
+error_from!(std::string::FromUtf16Error);
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                        field size limit                                        //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Default ceiling on the number of bytes we'll allocate to read any one field
+pub const DEFAULT_MAX_FIELD_SIZE: usize = 8 * 1024 * 1024;
+
+/// Guess whether `buf` is UTF-16LE-encoded text, despite the lack of a BOM: it must be of even
+/// length, and more than 30% of the bytes at odd positions (i.e. the high bytes) must be zero, as
+/// they will be for text drawn mostly from Latin-1
+fn looks_like_utf16le(buf: &[u8]) -> bool {
+    if buf.is_empty() || !buf.len().is_multiple_of(2) {
+        return false;
+    }
+    let nzero = buf.iter().skip(1).step_by(2).filter(|x| **x == 0).count();
+    nzero * 10 > (buf.len() / 2) * 3
+}
+
+/// Read `cb` bytes from `rdr`, refusing to allocate more than the maximum field size given in
+/// `opts`
+fn read_bytes<R: Read>(rdr: &mut R, cb: usize, opts: &ParseOptions) -> Result<Vec<u8>> {
+    if cb > opts.max_field_size {
+        return Err(Error::new(Cause::FieldTooLarge { size: cb }));
+    }
+    let mut buf: Vec<u8> = vec![0; cb];
+    rdr.read_exact(buf.as_mut_slice())?;
+    Ok(buf)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                           byte order                                           //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The order in which the bytes making up an NDE table's integers are serialized
+///
+/// The NDE was developed against Win32 on x86, so tables are almost always little-endian; but the
+/// format isn't inherently so, and files written by the PowerPC-era MacOS port may be big-endian.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ByteOrder {
+    #[default]
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    pub fn u16(self, buf: [u8; 2]) -> u16 {
+        match self {
+            ByteOrder::Little => u16::from_le_bytes(buf),
+            ByteOrder::Big => u16::from_be_bytes(buf),
+        }
+    }
+    pub fn u32(self, buf: [u8; 4]) -> u32 {
+        match self {
+            ByteOrder::Little => u32::from_le_bytes(buf),
+            ByteOrder::Big => u32::from_be_bytes(buf),
+        }
+    }
+    pub fn i32(self, buf: [u8; 4]) -> i32 {
+        match self {
+            ByteOrder::Little => i32::from_le_bytes(buf),
+            ByteOrder::Big => i32::from_be_bytes(buf),
+        }
+    }
+    pub fn i64(self, buf: [u8; 8]) -> i64 {
+        match self {
+            ByteOrder::Little => i64::from_le_bytes(buf),
+            ByteOrder::Big => i64::from_be_bytes(buf),
         }
     }
 }
 
-impl std::convert::From<std::string::FromUtf8Error> for Error {
-    fn from(err: std::string::FromUtf8Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
+#[cfg(test)]
+mod byte_order_tests {
+
+    /// Trivial test case
+    #[test]
+    fn smoke() {
+        use super::*;
+        assert_eq!(ByteOrder::Little.u32([0x01, 0x02, 0x00, 0x00]), 0x0201);
+        assert_eq!(ByteOrder::Big.u32([0x00, 0x00, 0x02, 0x01]), 0x0201);
+        assert_eq!(ByteOrder::Big.u16([0x01, 0x02]), 0x0102);
+        assert_eq!(ByteOrder::Big.i32([0xff, 0xff, 0xff, 0xfe]), -2);
+        assert_eq!(
+            ByteOrder::Little.i64([0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+            -2
+        );
     }
 }
 
-impl std::convert::From<std::string::FromUtf16Error> for Error {
-    fn from(err: std::string::FromUtf16Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                          length units                                          //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The units in which the values of LENGTH fields are expressed
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+#[display(style = "lowercase")]
+pub enum LengthUnits {
+    Seconds,
+    Millis,
+    /// Seconds, unless the value is too large to be plausible (more than a day), in which case
+    /// milliseconds
+    Auto,
+}
+
+/// The longest plausible track length, in seconds
+const MAX_PLAUSIBLE_LENGTH: i32 = 86400;
+
+impl LengthUnits {
+    /// Interpret the raw value of a LENGTH field as a number of seconds; negative values (Winamp
+    /// uses -1 for "unknown") are returned as-is
+    pub fn seconds(self, raw: i32) -> f64 {
+        if raw < 0 {
+            return raw as f64;
+        }
+        match self {
+            LengthUnits::Seconds => raw as f64,
+            LengthUnits::Millis => raw as f64 / 1000.0,
+            LengthUnits::Auto => {
+                if raw > MAX_PLAUSIBLE_LENGTH {
+                    raw as f64 / 1000.0
+                } else {
+                    raw as f64
+                }
+            }
         }
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+#[cfg(test)]
+mod length_units_tests {
+
+    /// Trivial test case
+    #[test]
+    fn smoke() {
+        use super::*;
+        assert_eq!(LengthUnits::Seconds.seconds(185), 185.0);
+        assert_eq!(LengthUnits::Millis.seconds(185250), 185.25);
+        assert_eq!(LengthUnits::Auto.seconds(185), 185.0);
+        assert_eq!(LengthUnits::Auto.seconds(185250), 185.25);
+        assert_eq!(LengthUnits::Millis.seconds(-1), -1.0);
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                         string fallback                                        //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// What to do with a string that has no BOM & isn't valid UTF-8
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+#[display(style = "lowercase")]
+pub enum StringFallback {
+    /// Fail to parse the field
+    Error,
+    /// Decode it as Windows-1252, as legacy libraries commonly contain (requires the `encoding`
+    /// feature)
+    Cp1252,
+    /// Replace invalid sequences with U+FFFD
+    Lossy,
+}
+
+#[cfg(feature = "encoding")]
+fn decode_cp1252(buf: &[u8]) -> Result<String> {
+    let (text, _) = encoding_rs::WINDOWS_1252.decode_without_bom_handling(buf);
+    Ok(text.into_owned())
+}
+
+#[cfg(not(feature = "encoding"))]
+fn decode_cp1252(_buf: &[u8]) -> Result<String> {
+    Err(Error::new(Cause::NoCp1252))
+}
+
+/// Decode `buf` as UTF-8, handling invalid text as `fallback` directs
+fn decode_utf8(buf: Vec<u8>, fallback: StringFallback) -> Result<String> {
+    match String::from_utf8(buf) {
+        Ok(text) => Ok(text),
+        Err(err) => match fallback {
+            StringFallback::Error => Err(Error::from(err)),
+            StringFallback::Cp1252 => decode_cp1252(err.as_bytes()),
+            StringFallback::Lossy => Ok(String::from_utf8_lossy(err.as_bytes()).into_owned()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod string_fallback_tests {
+
+    /// Invalid UTF-8 should be an error, or decoded as directed
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        // "Bjork's" with a Windows-1252 o-umlaut & right single quote
+        let buf: Vec<u8> = vec![b'B', b'j', 0xf6, b'r', b'k', 0x92, b's'];
+        assert!(decode_utf8(buf.clone(), StringFallback::Error).is_err());
+        assert_eq!(
+            decode_utf8(buf.clone(), StringFallback::Lossy).map_err(|err| format!("{}", err))?,
+            "Bj\u{fffd}rk\u{fffd}s"
+        );
+        assert_eq!(
+            decode_utf8(Vec::from("Björk"), StringFallback::Lossy)
+                .map_err(|err| format!("{}", err))?,
+            "Björk"
+        );
+        #[cfg(feature = "encoding")]
+        assert_eq!(
+            decode_utf8(buf, StringFallback::Cp1252).map_err(|err| format!("{}", err))?,
+            "Bj\u{f6}rk\u{2019}s"
+        );
+        #[cfg(not(feature = "encoding"))]
+        assert!(decode_utf8(buf, StringFallback::Cp1252).is_err());
+        Ok(())
+    }
+}
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 //                                     Basic NDE Field Types                                      //
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// NDE field types, maintaining the associated C numeric constants
-#[derive(Debug, Deserialize, Display, PartialEq, Serialize)]
+///
+/// These serialize by name, in upper case, just as they display (`"COLUMN"`, `"INT64"`, &c).
+///
+/// This enum is non-exhaustive, should some variant of NDE turn out to define more; the `is_*`
+/// predicates below classify field types without matching on every one.
+#[derive(Clone, Copy, Debug, Display, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "UPPERCASE"))]
+#[non_exhaustive]
 pub enum FieldType {
     #[display("COLUMN")]
     Column = 0,
@@ -280,9 +529,9 @@ pub enum FieldType {
     Guid = 7,
     #[display("PRIVATE")]
     Private = 8,
-    // TODO(sp1ff): how to handle this?
-    // #[display("BITMAP")]
-    // Bitmap = 6,
+    // FIELD_BITMAP shares FIELD_BINARY's discriminant, so the type byte alone can't tell them
+    // apart; rather than a variant of its own, a BINARY field is taken to hold a bitmap when its
+    // payload begins with a known image signature (see `image_format`).
     #[display("FLOAT")]
     Float = 9,
     #[display("DATETIME")]
@@ -322,9 +571,83 @@ impl FieldType {
             _ => Err(Error::new(Cause::BadFieldType(i))),
         }
     }
+    /// True for the types describing the table itself (its columns & indicies, & the redirectors
+    /// linking its fields), rather than holding a track's data
+    pub fn is_meta(&self) -> bool {
+        matches!(
+            self,
+            FieldType::Column | FieldType::Index | FieldType::Redirector
+        )
+    }
+    /// True for the types holding integers (of whatever width or interpretation)
+    pub fn is_integral(&self) -> bool {
+        matches!(
+            self,
+            FieldType::Integer | FieldType::Datetime | FieldType::Length | FieldType::Int64
+        )
+    }
+    /// True for the types holding text
+    pub fn is_text(&self) -> bool {
+        matches!(self, FieldType::String | FieldType::Filename)
+    }
+    /// True for the types holding arbitrary bytes (bitmaps included)
+    pub fn is_binary(&self) -> bool {
+        matches!(self, FieldType::Binary | FieldType::Binary32)
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[cfg(test)]
+mod field_type_tests {
+
+    /// Field types should serialize by name, just as they display
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() -> Result<(), String> {
+        use super::*;
+        for i in 0..16 {
+            let ftype = FieldType::from(i).map_err(|err| format!("{}", err))?;
+            let json = serde_json::to_string(&ftype).map_err(|err| format!("{}", err))?;
+            assert_eq!(json, format!("\"{}\"", ftype));
+            let back: FieldType = serde_json::from_str(&json).map_err(|err| format!("{}", err))?;
+            assert_eq!(back, ftype);
+        }
+        assert_eq!(
+            serde_json::to_string(&FieldType::Binary32).map_err(|err| format!("{}", err))?,
+            r#""BINARY32""#
+        );
+        Ok(())
+    }
+
+    /// Every field type should fall into at most one class
+    #[test]
+    fn classes() -> Result<(), String> {
+        use super::*;
+        for i in 0..16 {
+            let ftype = FieldType::from(i).map_err(|err| format!("{}", err))?;
+            let classes = [
+                ftype.is_meta(),
+                ftype.is_integral(),
+                ftype.is_text(),
+                ftype.is_binary(),
+            ];
+            assert!(classes.iter().filter(|x| **x).count() <= 1);
+        }
+        assert!(FieldType::Redirector.is_meta());
+        assert!(FieldType::Length.is_integral());
+        assert!(FieldType::Filename.is_text());
+        assert!(FieldType::Binary32.is_binary());
+        assert!(!FieldType::Guid.is_binary());
+        Ok(())
+    }
+}
+
+/// The value of an NDE field, in a form independent of its on-disk representation
+///
+/// This enum is non-exhaustive, as support for more field types may be added; the `as_*`
+/// accessors below extract values without matching on every variant.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[non_exhaustive]
 pub enum FieldValue {
     Unknown,
     Column((i32, String)),
@@ -337,22 +660,167 @@ pub enum FieldValue {
     Length(i32),
     Filename(std::path::PathBuf),
     Int64(i64),
+    Private {
+        owner: [u8; 16],
+        data: Vec<u8>,
+    },
+    /// A BINARY field whose payload carries an image signature (see [`image_format`])
+    ///
+    /// [`image_format`]: fn.image_format.html
+    Bitmap(Vec<u8>),
+}
+
+/// Convenience accessors, after the fashion of `serde_json::Value`'s; each returns `None` if the
+/// value isn't of a compatible sort
+impl FieldValue {
+    /// Return any integral value (`Integer`, `Int64`, `Datetime` or `Length`) as an i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            FieldValue::Integer(x) | FieldValue::Datetime(x) | FieldValue::Length(x) => {
+                Some(*x as i64)
+            }
+            FieldValue::Int64(x) => Some(*x),
+            _ => None,
+        }
+    }
+    /// Return a `Float` as such, or any integral value converted to an f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FieldValue::Float(x) => Some(*x),
+            _ => self.as_i64().map(|x| x as f64),
+        }
+    }
+    /// Return the text of a `String`
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FieldValue::String(x) => Some(x),
+            _ => None,
+        }
+    }
+    /// Return the path of a `Filename`
+    pub fn as_path(&self) -> Option<&std::path::Path> {
+        match self {
+            FieldValue::Filename(x) => Some(x),
+            _ => None,
+        }
+    }
+    /// Return a `Boolean`
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            FieldValue::Boolean(x) => Some(*x),
+            _ => None,
+        }
+    }
+    /// Return the payload of a `Bitmap`, or the data (less the owner) of a `Private` value
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            FieldValue::Bitmap(x) | FieldValue::Private { data: x, .. } => Some(x),
+            _ => None,
+        }
+    }
+    /// True if this is a value of a type xnde doesn't parse
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, FieldValue::Unknown)
+    }
+}
+
+#[cfg(test)]
+mod field_value_tests {
+
+    /// Accessors should coerce between compatible sorts of value, & only those
+    #[test]
+    fn accessors() {
+        use super::*;
+        assert_eq!(FieldValue::Integer(7).as_i64(), Some(7));
+        assert_eq!(FieldValue::Datetime(-1).as_i64(), Some(-1));
+        assert_eq!(FieldValue::Length(305).as_i64(), Some(305));
+        assert_eq!(FieldValue::Int64(1 << 40).as_i64(), Some(1 << 40));
+        assert_eq!(FieldValue::String(String::from("7")).as_i64(), None);
+        assert_eq!(FieldValue::Float(0.5).as_f64(), Some(0.5));
+        assert_eq!(FieldValue::Integer(2).as_f64(), Some(2.0));
+        assert_eq!(FieldValue::Boolean(true).as_f64(), None);
+        assert_eq!(
+            FieldValue::String(String::from("ABBA")).as_str(),
+            Some("ABBA")
+        );
+        assert_eq!(
+            FieldValue::Filename(std::path::PathBuf::from("a.mp3")).as_str(),
+            None
+        );
+        assert_eq!(
+            FieldValue::Filename(std::path::PathBuf::from("a.mp3")).as_path(),
+            Some(std::path::Path::new("a.mp3"))
+        );
+        assert_eq!(FieldValue::String(String::from("a.mp3")).as_path(), None);
+        assert_eq!(FieldValue::Boolean(false).as_bool(), Some(false));
+        assert_eq!(FieldValue::Integer(0).as_bool(), None);
+        assert_eq!(FieldValue::Unknown.as_i64(), None);
+        assert!(FieldValue::Unknown.is_unknown());
+        assert!(!FieldValue::Integer(0).is_unknown());
+        assert_eq!(
+            FieldValue::Bitmap(vec![1, 2]).as_bytes(),
+            Some(&[1u8, 2][..])
+        );
+        assert_eq!(
+            FieldValue::Private {
+                owner: [0; 16],
+                data: vec![3]
+            }
+            .as_bytes(),
+            Some(&[3u8][..])
+        );
+        assert_eq!(FieldValue::Integer(0).as_bytes(), None);
+    }
+}
+
+impl std::fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FieldValue::Unknown => write!(f, "<unknown>"),
+            FieldValue::Column((id, name)) => write!(f, "column {}: {}", id, name),
+            FieldValue::Index((id, ftype)) => write!(f, "index {}, type {}", id, ftype),
+            FieldValue::String(x) => write!(f, "{}", x),
+            FieldValue::Integer(x) => write!(f, "{}", x),
+            FieldValue::Boolean(x) => write!(f, "{}", x),
+            FieldValue::Float(x) => write!(f, "{}", x),
+            FieldValue::Datetime(x) => write!(f, "{}", x),
+            FieldValue::Length(x) => write!(f, "{}", x),
+            FieldValue::Filename(x) => write!(f, "{}", x.display()),
+            FieldValue::Int64(x) => write!(f, "{}", x),
+            FieldValue::Private { owner, data } => {
+                write!(f, "private data owned by ")?;
+                for b in owner {
+                    write!(f, "{:02x}", b)?;
+                }
+                write!(f, ": {} bytes", data.len())
+            }
+            FieldValue::Bitmap(data) => write!(f, "bitmap: {} bytes", data.len()),
+        }
+    }
 }
 
 /// Common NDE Field behavior
 // This annotation is from the `tyeptag' crate; it marks the Trait NdeField as having only
 // implementors who themselves implement Deserialize & Serialize. It also allows the serde
 // library to operate on things of type `&dyn NdeField' (I believe).
-#[typetag::serde(tag = "type")]
+#[cfg_attr(feature = "serde", typetag::serde(tag = "type"))]
 pub trait NdeField: std::fmt::Display {
     fn id(&self) -> i32;
     fn type_id(&self) -> Option<FieldType>;
     fn prev_field_pos(&self) -> u64;
     fn next_field_pos(&self) -> u64;
     fn value(&self) -> FieldValue;
+    /// The number of bytes this field occupies on disk: its header plus the size given therein
+    /// (not counting any redirectors that led to it)
+    fn serialized_len(&self) -> usize;
 }
 
-#[derive(Debug, Deserialize, Display, Serialize)]
+/// Size of a field header: ID & type (one byte each), then the size of the field's data, & the
+/// offsets of the next & previous fields (four bytes each)
+pub const FIELD_HEADER_SIZE: usize = 14;
+
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 /// Common NDE Field attributes: id, next-field, prev-field
 #[display(
     "ID {id}, size: {max_size_on_disk}, prev: {prev_field_pos:#06x}, next: {next_field_pos:#06x}"
@@ -371,14 +839,14 @@ pub struct NdeFieldBase {
 impl NdeFieldBase {
     /// Read from disk-- the caller is assumed to already have the id, since it would have been
     /// parsed as a result of following redirects
-    fn new<R: Read>(rdr: &mut R, id: i32) -> Result<NdeFieldBase> {
+    fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<NdeFieldBase> {
         let mut buf: [u8; 4] = [0; 4];
         rdr.read_exact(&mut buf)?;
-        let max_size_on_disk = u32::from_le_bytes(buf) as usize;
+        let max_size_on_disk = opts.byte_order.u32(buf) as usize;
         rdr.read_exact(&mut buf)?;
-        let next_field_pos = u32::from_le_bytes(buf) as u64;
+        let next_field_pos = opts.byte_order.u32(buf) as u64;
         rdr.read_exact(&mut buf)?;
-        let prev_field_pos = u32::from_le_bytes(buf) as u64;
+        let prev_field_pos = opts.byte_order.u32(buf) as u64;
         Ok(NdeFieldBase {
             id: id,
             max_size_on_disk: max_size_on_disk,
@@ -393,6 +861,9 @@ impl NdeFieldBase {
     fn max_size_on_disk(&self) -> usize {
         self.max_size_on_disk
     }
+    fn serialized_len(&self) -> usize {
+        FIELD_HEADER_SIZE.saturating_add(self.max_size_on_disk)
+    }
     #[allow(dead_code)]
     fn next(&self) -> u64 {
         self.next_field_pos
@@ -406,10 +877,8 @@ mod nde_field_base_tests {
     #[test]
     fn smoke() -> std::result::Result<(), String> {
         use super::*;
-        let bytes: [u8; 12] = [
-            0x10, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-        ];
-        let b = match NdeFieldBase::new(&mut bytes.as_ref(), 11) {
+        let bytes = crate::builder::body(&[0x00; 16], 0x14, 0);
+        let b = match NdeFieldBase::new(&mut bytes.as_slice(), 11, &ParseOptions::default()) {
             Ok(x) => x,
             Err(e) => {
                 return Err(format!("{}", e));
@@ -425,31 +894,129 @@ mod nde_field_base_tests {
 //                                    concrete NDE field types                                    //
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Serialize raw bytes as a string of hex digits, rather than an array of integers
+#[cfg(feature = "serde")]
+fn serialize_hex<S: serde::Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    let text: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    serializer.serialize_str(&text)
+}
+
+/// Deserialize raw bytes from a string of hex digits
+#[cfg(feature = "serde")]
+fn deserialize_hex<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<u8>, D::Error> {
+    use serde::de::Error;
+    let text = String::deserialize(deserializer)?;
+    if !text.is_ascii() || text.len() % 2 != 0 {
+        return Err(D::Error::custom(format!("{} is not a hex string", text)));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(D::Error::custom))
+        .collect()
+}
+
+/// Identify the image format of a binary payload by its signature, returning the customary file
+/// extension for that format, or `None` if `payload` doesn't begin with a signature we know
+///
+/// NDE's FIELD_BITMAP & FIELD_BINARY share a type (6), so this is what distinguishes the two: a
+/// BINARY field is a bitmap if & only if its payload is recognized here.
+pub fn image_format(payload: &[u8]) -> Option<&'static str> {
+    if payload.starts_with(b"BM") {
+        Some("bmp")
+    } else if payload.starts_with(b"GIF87a") || payload.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if payload.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("jpg")
+    } else if payload.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else {
+        None
+    }
+}
+
 /// An NDE Field which we do not know how to parse
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 // TODO(sp1ff): format the raw bytes more nicely (without newlines)
 #[display("Unk {field_type}: {base} data: {bytes:#?}")]
 pub struct UnsupportedNdeField {
     base: NdeFieldBase,
     field_type: FieldType,
+    /// The raw field data, serialized as hex so as to be (somewhat) legible
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")
+    )]
     bytes: Vec<u8>,
+    /// The byte order of the table whence this field came, needed to read binary payload lengths
+    #[cfg_attr(feature = "serde", serde(skip))]
+    byte_order: ByteOrder,
 }
 
 impl UnsupportedNdeField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32, ft: FieldType) -> Result<UnsupportedNdeField> {
-        let base = NdeFieldBase::new(rdr, id)?;
-        let mut buf: Vec<u8> = Vec::with_capacity(base.max_size_on_disk());
-        buf.resize(base.max_size_on_disk(), 0);
-        rdr.read_exact(buf.as_mut_slice())?;
+    pub fn new<R: Read>(
+        rdr: &mut R,
+        id: i32,
+        ft: FieldType,
+        opts: &ParseOptions,
+    ) -> Result<UnsupportedNdeField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
+        let buf = read_bytes(rdr, base.max_size_on_disk(), opts)?;
         Ok(UnsupportedNdeField {
             base: base,
             field_type: ft,
             bytes: buf,
+            byte_order: opts.byte_order,
         })
     }
+    /// The type of this field, as given in its header
+    pub fn field_type(&self) -> FieldType {
+        self.field_type
+    }
+    /// This field's raw data
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+    /// If this is a BINARY or BINARY32 field, return its payload (i.e. its data, less the
+    /// leading 16- or 32-bit length); the payload is truncated to the data actually present
+    pub fn binary_payload(&self) -> Option<&[u8]> {
+        let (prefix, len): (usize, usize) = match self.field_type {
+            FieldType::Binary if self.bytes.len() >= 2 => (
+                2,
+                self.byte_order.u16([self.bytes[0], self.bytes[1]]) as usize,
+            ),
+            FieldType::Binary32 if self.bytes.len() >= 4 => (
+                4,
+                self.byte_order
+                    .u32([self.bytes[0], self.bytes[1], self.bytes[2], self.bytes[3]])
+                    as usize,
+            ),
+            _ => return None,
+        };
+        let end = std::cmp::min(self.bytes.len(), prefix.saturating_add(len));
+        Some(&self.bytes[prefix..end])
+    }
+    /// True if this is a BINARY field holding a bitmap (i.e. its payload is a recognized image)
+    pub fn is_bitmap(&self) -> bool {
+        self.field_type == FieldType::Binary
+            && self.binary_payload().and_then(image_format).is_some()
+    }
+    /// Replace this field's data with a reference to the file to which it's been written
+    pub fn into_extracted(self, file: std::path::PathBuf) -> ExtractedBinaryField {
+        ExtractedBinaryField {
+            base: self.base,
+            field_type: self.field_type,
+            file: file,
+        }
+    }
 }
 
-#[typetag::serde]
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for UnsupportedNdeField {
     fn id(&self) -> i32 {
         self.base.id
@@ -463,13 +1030,60 @@ impl NdeField for UnsupportedNdeField {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
+    fn value(&self) -> FieldValue {
+        match self.binary_payload() {
+            Some(payload) if self.is_bitmap() => FieldValue::Bitmap(payload.to_vec()),
+            _ => FieldValue::Unknown,
+        }
+    }
+}
+
+/// A BINARY or BINARY32 field whose payload has been written out to a file of its own, leaving
+/// only a reference to that file
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[display("{field_type}: {base} file: {file:?}")]
+pub struct ExtractedBinaryField {
+    base: NdeFieldBase,
+    field_type: FieldType,
+    file: std::path::PathBuf,
+}
+
+impl ExtractedBinaryField {
+    /// The file to which this field's payload was written
+    pub fn file(&self) -> &std::path::Path {
+        &self.file
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl NdeField for ExtractedBinaryField {
+    fn id(&self) -> i32 {
+        self.base.id
+    }
+    fn type_id(&self) -> Option<FieldType> {
+        Some(self.field_type)
+    }
+    fn prev_field_pos(&self) -> u64 {
+        self.base.prev_field_pos
+    }
+    fn next_field_pos(&self) -> u64 {
+        self.base.next_field_pos
+    }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Unknown
     }
 }
 
 /// NDE FIELD_COLUMN
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("Column: {base}, {col_type}, {name}")]
 pub struct ColumnField {
     base: NdeFieldBase,
@@ -479,8 +1093,8 @@ pub struct ColumnField {
 }
 
 impl ColumnField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<ColumnField> {
-        let base = NdeFieldBase::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<ColumnField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
         let mut buf: [u8; 3] = [0; 3];
         rdr.read_exact(&mut buf)?;
 
@@ -488,9 +1102,7 @@ impl ColumnField {
         let index_unique = buf[1] != 0;
         let cb = buf[2] as usize;
 
-        let mut buf: Vec<u8> = Vec::with_capacity(cb);
-        buf.resize(cb, 0);
-        rdr.read_exact(buf.as_mut_slice())?;
+        let buf = read_bytes(rdr, cb, opts)?;
 
         let name = String::from_utf8(buf)?;
 
@@ -504,15 +1116,47 @@ impl ColumnField {
     pub fn name(&self) -> String {
         self.name.clone()
     }
+    /// The type declared for this column
+    pub fn col_type(&self) -> FieldType {
+        self.col_type
+    }
+    /// True if this column's values are flagged as unique within its index
+    pub fn is_unique(&self) -> bool {
+        self.index_unique
+    }
+    /// This column's ID, by which fields in subsequent records refer to it
+    pub fn id(&self) -> i32 {
+        self.base.id
+    }
 }
 
-#[typetag::serde]
+#[cfg(test)]
+mod column_field_tests {
+
+    /// Column fields should expose their ID, type, uniqueness & name
+    #[test]
+    fn accessors() -> Result<(), String> {
+        use super::*;
+        let (_, _, mut data) = crate::builder::column(2, FieldType::String, "title");
+        data[1] = 0x01; // unique
+        let bytes = crate::builder::body(&data, 0, 0);
+        let col = ColumnField::new(&mut bytes.as_slice(), 2, &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        assert_eq!(col.id(), 2);
+        assert_eq!(col.col_type(), FieldType::String);
+        assert!(col.is_unique());
+        assert_eq!(col.name(), "title");
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for ColumnField {
     fn id(&self) -> i32 {
         self.base.id
     }
     fn type_id(&self) -> Option<FieldType> {
-        Some(FieldType::Filename)
+        Some(FieldType::Column)
     }
     fn prev_field_pos(&self) -> u64 {
         self.base.prev_field_pos
@@ -520,13 +1164,17 @@ impl NdeField for ColumnField {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Column((self.id(), self.name.clone()))
     }
 }
 
 /// NDE FIELD_DATETIME
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("{base} {data}")]
 pub struct DatetimeField {
     base: NdeFieldBase,
@@ -534,11 +1182,11 @@ pub struct DatetimeField {
 }
 
 impl DatetimeField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<DatetimeField> {
-        let base = NdeFieldBase::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<DatetimeField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
         let mut buf: [u8; 4] = [0; 4];
         rdr.read_exact(&mut buf)?;
-        let data = i32::from_le_bytes(buf);
+        let data = opts.byte_order.i32(buf);
         Ok(DatetimeField {
             base: base,
             data: data,
@@ -546,7 +1194,7 @@ impl DatetimeField {
     }
 }
 
-#[typetag::serde]
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for DatetimeField {
     fn id(&self) -> i32 {
         self.base.id
@@ -560,13 +1208,17 @@ impl NdeField for DatetimeField {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Datetime(self.data)
     }
 }
 
 /// NDE FIELD_FILENAME
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("{base} {path:#?}")]
 pub struct FilenameField {
     base: StringField,
@@ -574,8 +1226,8 @@ pub struct FilenameField {
 }
 
 impl FilenameField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<FilenameField> {
-        let base = StringField::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<FilenameField> {
+        let base = StringField::new(rdr, id, opts)?;
         let path = std::path::PathBuf::from(base.text());
         Ok(FilenameField {
             base: base,
@@ -584,7 +1236,7 @@ impl FilenameField {
     }
 }
 
-#[typetag::serde]
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for FilenameField {
     fn id(&self) -> i32 {
         self.base.base.id
@@ -598,13 +1250,17 @@ impl NdeField for FilenameField {
     fn next_field_pos(&self) -> u64 {
         self.base.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Filename(self.path.clone())
     }
 }
 
 /// NDE FIELD_INDEX
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("{base}, pos: {pos}, type: {ftype}, name: {name}")]
 pub struct IndexField {
     base: NdeFieldBase,
@@ -614,19 +1270,17 @@ pub struct IndexField {
 }
 
 impl IndexField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<IndexField> {
-        let base = NdeFieldBase::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<IndexField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
         let mut buf: [u8; 4] = [0; 4];
         rdr.read_exact(&mut buf)?;
-        let pos = u32::from_le_bytes(buf) as u64;
+        let pos = opts.byte_order.u32(buf) as u64;
         rdr.read_exact(&mut buf)?;
-        let ftype = i32::from_le_bytes(buf);
+        let ftype = opts.byte_order.i32(buf);
         let mut buf: [u8; 1] = [0; 1];
         rdr.read_exact(&mut buf)?;
         let cb = buf[0] as usize;
-        let mut buf: Vec<u8> = Vec::with_capacity(cb);
-        buf.resize(cb, 0);
-        rdr.read_exact(buf.as_mut_slice())?;
+        let buf = read_bytes(rdr, cb, opts)?;
         let name = String::from_utf8(buf)?;
         Ok(IndexField {
             base: base,
@@ -635,9 +1289,21 @@ impl IndexField {
             name: name,
         })
     }
+    /// The name of this index (conventionally, that of the column by which it orders records)
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+    /// The position recorded for this index
+    pub fn pos(&self) -> u64 {
+        self.pos
+    }
+    /// The (numeric) type recorded for this index
+    pub fn ftype(&self) -> i32 {
+        self.ftype
+    }
 }
 
-#[typetag::serde]
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for IndexField {
     fn id(&self) -> i32 {
         self.base.id
@@ -651,13 +1317,17 @@ impl NdeField for IndexField {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Index((self.id(), self.ftype))
     }
 }
 
 /// NDE FIELD_INT64
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("{base} {data}")]
 pub struct Int64Field {
     base: NdeFieldBase,
@@ -665,11 +1335,11 @@ pub struct Int64Field {
 }
 
 impl Int64Field {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<Int64Field> {
-        let base = NdeFieldBase::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<Int64Field> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
         let mut buf: [u8; 8] = [0; 8];
         rdr.read_exact(&mut buf)?;
-        let data = i64::from_le_bytes(buf);
+        let data = opts.byte_order.i64(buf);
         Ok(Int64Field {
             base: base,
             data: data,
@@ -677,7 +1347,7 @@ impl Int64Field {
     }
 }
 
-#[typetag::serde]
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for Int64Field {
     fn id(&self) -> i32 {
         self.base.id
@@ -691,13 +1361,17 @@ impl NdeField for Int64Field {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Int64(self.data)
     }
 }
 
 /// NDE FIELD_INTEGER
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("{base} {data}")]
 pub struct IntegerField {
     base: NdeFieldBase,
@@ -705,11 +1379,11 @@ pub struct IntegerField {
 }
 
 impl IntegerField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<IntegerField> {
-        let base = NdeFieldBase::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<IntegerField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
         let mut buf: [u8; 4] = [0; 4];
         rdr.read_exact(&mut buf)?;
-        let data = i32::from_le_bytes(buf);
+        let data = opts.byte_order.i32(buf);
         Ok(IntegerField {
             base: base,
             data: data,
@@ -717,7 +1391,7 @@ impl IntegerField {
     }
 }
 
-#[typetag::serde]
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for IntegerField {
     fn id(&self) -> i32 {
         self.base.id
@@ -731,13 +1405,17 @@ impl NdeField for IntegerField {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Integer(self.data)
     }
 }
 
 /// NDE FIELD_LENGTH
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("{base} {data}")]
 pub struct LengthField {
     base: NdeFieldBase,
@@ -745,19 +1423,27 @@ pub struct LengthField {
 }
 
 impl LengthField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<LengthField> {
-        let base = NdeFieldBase::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<LengthField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
         let mut buf: [u8; 4] = [0; 4];
         rdr.read_exact(&mut buf)?;
-        let data = i32::from_le_bytes(buf);
+        let data = opts.byte_order.i32(buf);
         Ok(LengthField {
             base: base,
             data: data,
         })
     }
+    /// The value of this field, exactly as it appears on disk
+    pub fn raw(&self) -> i32 {
+        self.data
+    }
+    /// The value of this field in seconds, interpreted according to `units`
+    pub fn seconds(&self, units: LengthUnits) -> f64 {
+        units.seconds(self.data)
+    }
 }
 
-#[typetag::serde]
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for LengthField {
     fn id(&self) -> i32 {
         self.base.id
@@ -771,13 +1457,106 @@ impl NdeField for LengthField {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::Length(self.data)
     }
 }
 
+/// NDE FIELD_PRIVATE
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[display("Private: {base} owner: {owner:02x?}, data: {data:#?}")]
+pub struct PrivateField {
+    base: NdeFieldBase,
+    /// GUID identifying the plugin that wrote this field
+    owner: [u8; 16],
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")
+    )]
+    data: Vec<u8>,
+}
+
+impl PrivateField {
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<PrivateField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
+        if base.max_size_on_disk() < 16 {
+            return Err(Error::new(Cause::FieldTooSmall {
+                size: base.max_size_on_disk(),
+            }));
+        }
+        let mut owner: [u8; 16] = [0; 16];
+        rdr.read_exact(&mut owner)?;
+        let data = read_bytes(rdr, base.max_size_on_disk() - 16, opts)?;
+        Ok(PrivateField {
+            base: base,
+            owner: owner,
+            data: data,
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl NdeField for PrivateField {
+    fn id(&self) -> i32 {
+        self.base.id
+    }
+    fn type_id(&self) -> Option<FieldType> {
+        Some(FieldType::Private)
+    }
+    fn prev_field_pos(&self) -> u64 {
+        self.base.prev_field_pos
+    }
+    fn next_field_pos(&self) -> u64 {
+        self.base.next_field_pos
+    }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
+    fn value(&self) -> FieldValue {
+        FieldValue::Private {
+            owner: self.owner,
+            data: self.data.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod private_field_tests {
+
+    /// Trivial test case
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        let bytes = crate::builder::field_body(
+            &FieldValue::Private {
+                owner: [0x11; 16],
+                data: vec![0xab, 0xcd],
+            },
+            0,
+            0,
+        );
+        let field = PrivateField::new(&mut bytes.as_slice(), 3, &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        match field.value() {
+            FieldValue::Private { owner, data } => {
+                assert_eq!(owner, [0x11; 16]);
+                assert_eq!(data, vec![0xab, 0xcd]);
+            }
+            _ => {
+                return Err(String::from("bad field value"));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// NDE FIELD_STRING
-#[derive(Debug, Deserialize, Display, Serialize)]
+#[derive(Debug, Display)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[display("{base} {text}")]
 pub struct StringField {
     base: NdeFieldBase,
@@ -785,13 +1564,13 @@ pub struct StringField {
 }
 
 impl StringField {
-    pub fn new<R: Read>(rdr: &mut R, id: i32) -> Result<StringField> {
-        let base = NdeFieldBase::new(rdr, id)?;
+    pub fn new<R: Read>(rdr: &mut R, id: i32, opts: &ParseOptions) -> Result<StringField> {
+        let base = NdeFieldBase::new(rdr, id, opts)?;
 
         // Next up: a u16 containing the string length
         let mut buf: [u8; 2] = [0; 2];
         rdr.read_exact(&mut buf)?;
-        let cb = u16::from_le_bytes(buf) as usize;
+        let cb = opts.byte_order.u16(buf) as usize;
 
         if cb == 0 {
             return Ok(StringField {
@@ -800,34 +1579,7 @@ impl StringField {
             });
         }
 
-        let mut buf: Vec<u8> = Vec::with_capacity(cb);
-        buf.resize(cb, 0);
-        rdr.read_exact(buf.as_mut_slice())?;
-
-        // the text *may* be UTF-16 encoded; from reading the NDE source code, it appears we can
-        // depend on a BOM being present if so.
-        let text = if cb >= 2 && cb % 2 == 0 && buf[0] == 0xff && buf[1] == 0xfe {
-            // the rest of `buf' are little-endian u16-s giving a utf-16 encoding
-            let mut buf16: Vec<u16> = Vec::with_capacity(cb - 2);
-            for i in (2..cb).step_by(2) {
-                // TODO(sp1ff): there must be a better way
-                let tmp = [buf[i], buf[i + 1]];
-                buf16.push(u16::from_le_bytes(tmp));
-            }
-            String::from_utf16(&buf16)?
-        } else if cb >= 2 && cb % 2 == 0 && buf[0] == 0xfe && buf[1] == 0xff {
-            // the rest of `buf' are big-endian u16-s giving a utf-16 encoding
-            let mut buf16: Vec<u16> = Vec::with_capacity(cb - 2);
-            for i in (2..cb).step_by(2) {
-                // TODO(sp1ff): there must be a better way
-                let tmp = [buf[i], buf[i + 1]];
-                buf16.push(u16::from_be_bytes(tmp));
-            }
-            String::from_utf16(&buf16)?
-        } else {
-            // `buf' contains a utf-8 string
-            String::from_utf8(buf)?
-        };
+        let text = decode_text(read_bytes(rdr, cb, opts)?, opts)?;
 
         Ok(StringField {
             base: base,
@@ -839,7 +1591,50 @@ impl StringField {
     }
 }
 
-#[typetag::serde]
+/// Decode the text of a string or filename field, as directed by `opts`
+fn decode_text(buf: Vec<u8>, opts: &ParseOptions) -> Result<String> {
+    let cb = buf.len();
+    // the text *may* be UTF-16 encoded; from reading the NDE source code, it appears we can
+    // depend on a BOM being present if so.
+    let text = if opts.strict_utf8 {
+        String::from_utf8(buf)?
+    } else if cb >= 2 && cb % 2 == 0 && buf[0] == 0xff && buf[1] == 0xfe {
+        // the rest of `buf' are little-endian u16-s giving a utf-16 encoding
+        let mut buf16: Vec<u16> = Vec::with_capacity(cb - 2);
+        for i in (2..cb).step_by(2) {
+            // TODO(sp1ff): there must be a better way
+            let tmp = [buf[i], buf[i + 1]];
+            buf16.push(u16::from_le_bytes(tmp));
+        }
+        String::from_utf16(&buf16)?
+    } else if cb >= 2 && cb % 2 == 0 && buf[0] == 0xfe && buf[1] == 0xff {
+        // the rest of `buf' are big-endian u16-s giving a utf-16 encoding
+        let mut buf16: Vec<u16> = Vec::with_capacity(cb - 2);
+        for i in (2..cb).step_by(2) {
+            // TODO(sp1ff): there must be a better way
+            let tmp = [buf[i], buf[i + 1]];
+            buf16.push(u16::from_be_bytes(tmp));
+        }
+        String::from_utf16(&buf16)?
+    } else if opts.guess_utf16 && looks_like_utf16le(&buf) {
+        // no BOM, but `buf' sure looks like little-endian u16-s; if they don't decode after
+        // all, fall back to utf-8
+        let buf16: Vec<u16> = buf
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .collect();
+        match String::from_utf16(&buf16) {
+            Ok(text) => text,
+            Err(_) => decode_utf8(buf, opts.string_fallback)?,
+        }
+    } else {
+        // `buf' contains a utf-8 string (or ought to)
+        decode_utf8(buf, opts.string_fallback)?
+    };
+    Ok(text)
+}
+
+#[cfg_attr(feature = "serde", typetag::serde)]
 impl NdeField for StringField {
     fn id(&self) -> i32 {
         self.base.id
@@ -853,6 +1648,9 @@ impl NdeField for StringField {
     fn next_field_pos(&self) -> u64 {
         self.base.next_field_pos
     }
+    fn serialized_len(&self) -> usize {
+        self.base.serialized_len()
+    }
     fn value(&self) -> FieldValue {
         FieldValue::String(self.text.clone())
     }
@@ -865,12 +1663,9 @@ mod string_field_tests {
     /// StringField smoke tests
     fn string_field_smoke() -> Result<(), String> {
         use super::*;
-        let bytes: [u8; 32] = [
-            0x14, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x12, 0x00,
-            0xff, 0xfe, 0x43, 0x00, 0x3a, 0x00, 0x5c, 0x00, 0x61, 0x00, 0x2e, 0x00, 0x6d, 0x00,
-            0x70, 0x00, 0x33, 0x00,
-        ];
-        let s = match StringField::new(&mut bytes.as_ref(), 1) {
+        let bytes =
+            crate::builder::field_body(&FieldValue::String(String::from("C:\\a.mp3")), 0x14, 0);
+        let s = match StringField::new(&mut bytes.as_slice(), 1, &ParseOptions::default()) {
             Ok(s) => s,
             Err(err) => {
                 return Err(format!("{}", err));
@@ -884,20 +1679,301 @@ mod string_field_tests {
         };
         eprintln!("t is {}", t);
         assert_eq!(t, "C:\\a.mp3");
+        assert_eq!(format!("{}", s.value()), "C:\\a.mp3");
         Ok(())
     }
 }
 
-pub fn field_factory<R: Read>(rdr: &mut R, id: i32, ft: FieldType) -> Result<Box<dyn NdeField>> {
+#[cfg(test)]
+mod decode_text_tests {
+
+    /// A leading BOM should be honored, unless we're told the text is strictly UTF-8
+    #[test]
+    fn strict() -> Result<(), String> {
+        use super::*;
+        // The UTF-16LE BOM followed by "ab"; a lone 0xff isn't valid UTF-8
+        let buf: Vec<u8> = vec![0xff, 0xfe, 0x61, 0x00, 0x62, 0x00];
+        let lax = ParseOptions::default();
+        let strict = ParseOptions::new().strict_utf8(true);
+        assert_eq!(
+            decode_text(buf.clone(), &lax).map_err(|err| format!("{}", err))?,
+            "ab"
+        );
+        assert!(decode_text(buf, &strict).is_err());
+        let buf = Vec::from("ÿþab");
+        assert_eq!(
+            decode_text(buf.clone(), &strict).map_err(|err| format!("{}", err))?,
+            "ÿþab"
+        );
+        assert_eq!(
+            decode_text(buf, &lax).map_err(|err| format!("{}", err))?,
+            "ÿþab"
+        );
+        Ok(())
+    }
+}
+
+/// A function parsing a field of some particular type
+type Parser<R> = fn(&mut R, i32, &ParseOptions) -> Result<Box<dyn NdeField>>;
+
+/// Return the parser for fields of type `ft`, or `None` if we don't know how to parse them; this
+/// is the one place that records which types we can handle (cf. [`is_supported`])
+///
+/// [`is_supported`]: fn.is_supported.html
+fn parser<R: Read>(ft: FieldType) -> Option<Parser<R>> {
     match ft {
-        FieldType::Column => Ok(Box::new(ColumnField::new(rdr, id)?)),
-        FieldType::Datetime => Ok(Box::new(DatetimeField::new(rdr, id)?)),
-        FieldType::Filename => Ok(Box::new(FilenameField::new(rdr, id)?)),
-        FieldType::Index => Ok(Box::new(IndexField::new(rdr, id)?)),
-        FieldType::Integer => Ok(Box::new(IntegerField::new(rdr, id)?)),
-        FieldType::Int64 => Ok(Box::new(Int64Field::new(rdr, id)?)),
-        FieldType::Length => Ok(Box::new(LengthField::new(rdr, id)?)),
-        FieldType::String => Ok(Box::new(StringField::new(rdr, id)?)),
-        _ => Ok(Box::new(UnsupportedNdeField::new(rdr, id, ft)?)),
+        FieldType::Column => Some(|rdr, id, opts| Ok(Box::new(ColumnField::new(rdr, id, opts)?))),
+        FieldType::Datetime => {
+            Some(|rdr, id, opts| Ok(Box::new(DatetimeField::new(rdr, id, opts)?)))
+        }
+        FieldType::Filename => {
+            Some(|rdr, id, opts| Ok(Box::new(FilenameField::new(rdr, id, opts)?)))
+        }
+        FieldType::Index => Some(|rdr, id, opts| Ok(Box::new(IndexField::new(rdr, id, opts)?))),
+        FieldType::Integer => Some(|rdr, id, opts| Ok(Box::new(IntegerField::new(rdr, id, opts)?))),
+        FieldType::Int64 => Some(|rdr, id, opts| Ok(Box::new(Int64Field::new(rdr, id, opts)?))),
+        FieldType::Length => Some(|rdr, id, opts| Ok(Box::new(LengthField::new(rdr, id, opts)?))),
+        FieldType::Private => Some(|rdr, id, opts| Ok(Box::new(PrivateField::new(rdr, id, opts)?))),
+        FieldType::String => Some(|rdr, id, opts| Ok(Box::new(StringField::new(rdr, id, opts)?))),
+        _ => None,
+    }
+}
+
+fn new_field<R: Read>(
+    rdr: &mut R,
+    id: i32,
+    ft: FieldType,
+    opts: &ParseOptions,
+) -> Result<Box<dyn NdeField>> {
+    match parser::<R>(ft) {
+        Some(parse) => parse(rdr, id, opts),
+        None => Ok(Box::new(UnsupportedNdeField::new(rdr, id, ft, opts)?)),
+    }
+}
+
+/// Return true if we can parse fields of type `ft`, rather than just carrying their raw bytes
+/// around in an [`UnsupportedNdeField`]
+///
+/// Redirectors count as supported: they're never parsed as fields in their own right, but they
+/// are followed to the fields they redirect to.
+///
+/// [`UnsupportedNdeField`]: struct.UnsupportedNdeField.html
+pub fn is_supported(ft: FieldType) -> bool {
+    ft == FieldType::Redirector || parser::<&[u8]>(ft).is_some()
+}
+
+/// Return every field type, along with whether we can parse it (cf. [`is_supported`])
+///
+/// [`is_supported`]: fn.is_supported.html
+pub fn supported_field_types() -> Vec<(FieldType, bool)> {
+    (0..=u8::MAX)
+        .map_while(|i| FieldType::from(i).ok())
+        .map(|ft| (ft, is_supported(ft)))
+        .collect()
+}
+
+#[cfg(test)]
+mod supported_tests {
+
+    /// Every field type should be listed, & those we parse marked as such
+    #[test]
+    fn smoke() {
+        use super::*;
+        let types = supported_field_types();
+        assert_eq!(types.len(), 16);
+        assert!(types.contains(&(FieldType::String, true)));
+        assert!(types.contains(&(FieldType::Redirector, true)));
+        assert!(types.contains(&(FieldType::Guid, false)));
+        assert!(types.contains(&(FieldType::Binary, false)));
+        assert!(is_supported(FieldType::Int64));
+        assert!(!is_supported(FieldType::Float));
+    }
+}
+
+/// Parse a field of type `ft` from `rdr`; `offset` is the location in the data file of the field
+/// being parsed, and is used only to annotate errors
+pub fn field_factory<R: Read>(
+    rdr: &mut R,
+    id: i32,
+    ft: FieldType,
+    offset: u64,
+    opts: &ParseOptions,
+) -> Result<Box<dyn NdeField>> {
+    new_field(rdr, id, ft, opts).map_err(|err| err.at(offset, ft))
+}
+
+#[cfg(test)]
+mod utf16_guess_tests {
+
+    /// Sniff out UTF-16LE text without a BOM
+    #[test]
+    fn guess() {
+        use super::*;
+        assert!(looks_like_utf16le(&[0x61, 0x00, 0x62, 0x00]));
+        assert!(!looks_like_utf16le(&[0x61, 0x00, 0x62]));
+        assert!(!looks_like_utf16le(b"abcd"));
+        assert!(!looks_like_utf16le(&[]));
+    }
+}
+
+#[cfg(test)]
+mod field_size_tests {
+
+    /// A field claiming an absurd size should be rejected before we try to allocate it
+    #[test]
+    fn too_large() -> Result<(), String> {
+        use super::*;
+        let mut bytes = crate::builder::body(&[], 0, 0);
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes()); // max size
+        match UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Binary,
+            &ParseOptions::default(),
+        ) {
+            Ok(_) => Err(String::from("construction should have failed")),
+            Err(err) => match err.cause {
+                Cause::FieldTooLarge { size } => {
+                    assert_eq!(size, 0xffffffff);
+                    Ok(())
+                }
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod unsupported_field_tests {
+
+    /// Unsupported fields should serialize their type & their payload as hex
+    #[cfg(feature = "serde")]
+    #[test]
+    fn hex() -> Result<(), String> {
+        use super::*;
+        let bytes = crate::builder::body(&[0x01, 0xab, 0xff], 0, 0);
+        let field = UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Guid,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let json = serde_json::to_string(&field).map_err(|err| format!("{}", err))?;
+        assert!(json.contains(r#""field_type":"GUID""#));
+        assert!(json.contains(r#""bytes":"01abff""#));
+        let field: UnsupportedNdeField =
+            serde_json::from_str(&json).map_err(|err| format!("{}", err))?;
+        assert_eq!(field.bytes, vec![0x01, 0xab, 0xff]);
+        Ok(())
+    }
+
+    /// Binary fields' payloads should be stripped of their length prefix
+    #[cfg(feature = "serde")]
+    #[test]
+    fn binary_payload() -> Result<(), String> {
+        use super::*;
+        let bytes = crate::builder::body(&[0x02, 0x00, 0xff, 0xd8, 0xff], 0, 0);
+        let field = UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Binary,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(field.binary_payload(), Some(&[0xffu8, 0xd8][..]));
+        let field = UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Guid,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(field.binary_payload(), None);
+        let field = field.into_extracted(std::path::PathBuf::from("x/1_art.jpg"));
+        let json = serde_json::to_string(&field).map_err(|err| format!("{}", err))?;
+        assert!(json.contains(r#""file":"x/1_art.jpg""#));
+        Ok(())
+    }
+
+    /// BINARY fields should be taken for bitmaps if & only if their payload is a known image
+    #[test]
+    fn bitmaps() -> Result<(), String> {
+        use super::*;
+        assert_eq!(image_format(b"BM\x36\x00"), Some("bmp"));
+        assert_eq!(image_format(b"GIF89a"), Some("gif"));
+        assert_eq!(image_format(&[0xff, 0xd8, 0xff, 0xe0]), Some("jpg"));
+        assert_eq!(image_format(b"\x89PNG\r\n\x1a\n"), Some("png"));
+        assert_eq!(image_format(b"B"), None);
+        assert_eq!(image_format(&[]), None);
+
+        let mut bytes =
+            crate::builder::field_body(&FieldValue::Bitmap(b"BM\x36\x00".to_vec()), 0, 0);
+        let field = UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Binary,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert!(field.is_bitmap());
+        match field.value() {
+            FieldValue::Bitmap(data) => assert_eq!(data, vec![b'B', b'M', 0x36, 0x00]),
+            value => return Err(format!("unexpected value {:?}", value)),
+        }
+        assert_eq!(format!("{}", field.value()), "bitmap: 4 bytes");
+
+        // Same payload, but BINARY32: only type 6 is ambiguous
+        let field = UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Binary32,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert!(!field.is_bitmap());
+
+        bytes[14] = b'X';
+        let field = UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Binary,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert!(!field.is_bitmap());
+        assert!(matches!(field.value(), FieldValue::Unknown));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod field_factory_tests {
+
+    /// A truncated field should report where it was found
+    #[test]
+    fn truncated() -> Result<(), String> {
+        use super::*;
+        let mut bytes = crate::builder::integer(1, FieldType::Integer, 1).2;
+        bytes.truncate(2);
+        let mut bytes = crate::builder::body(&bytes, 0, 0);
+        bytes[..4].copy_from_slice(&4u32.to_le_bytes()); // claims more than it holds
+        match field_factory(
+            &mut bytes.as_slice(),
+            1,
+            FieldType::Integer,
+            0x1234,
+            &ParseOptions::default(),
+        ) {
+            Ok(_) => Err(String::from("parse should have failed")),
+            Err(err) => match err.cause {
+                Cause::FieldParse { offset, field_type } => {
+                    assert_eq!(offset, 0x1234);
+                    assert_eq!(field_type, FieldType::Integer);
+                    Ok(())
+                }
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
     }
 }
@@ -100,21 +100,58 @@
 //! a record.  The question marks for the second element are because I never figured out what this
 //! was for.
 
+/// Implement `From<$t>` for the `Error` type in scope, boxing the source error & attributing it
+/// to `Cause::Other` (or the given `Cause` variant)
+///
+/// Every xnde error type has the same shape (a `Cause`, an optional boxed source & an optional
+/// backtrace), so this works for all of them.
+#[macro_export]
+macro_rules! error_from {
+    ($t:ty) => {
+        $crate::error_from!($t, Other);
+    };
+    ($t:ty, $cause:ident) => {
+        impl std::convert::From<$t> for Error {
+            fn from(err: $t) -> Self {
+                Error {
+                    cause: Cause::$cause,
+                    source: Some(Box::new(err)),
+                    trace: $crate::capture_backtrace(),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod builder;
 pub mod fields;
+#[cfg(feature = "playlists")]
+pub mod playlists;
 pub mod tracks;
 
-use fields::{field_factory, FieldType, NdeField};
-use tracks::{new_column_map, Track};
+use fields::{field_factory, FieldType, FieldValue, IndexField, NdeField};
+#[cfg(feature = "serde")]
+use tracks::WithNulls;
+use tracks::{
+    compare_tracks, new_column_map, new_column_types, AttrValue, ColumnMap, ColumnTypes, NullAs,
+    Track, TrackAttrs, TrackFilter,
+};
 
 use parse_display::Display;
+#[cfg(feature = "serde")]
+use serde::Serialize;
 
 use log::{debug, info};
 
 use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     fs::File,
-    io::{BufReader, Read, Seek, SeekFrom},
-    path::Path,
+    io::{BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
 };
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -138,19 +175,95 @@ pub enum Cause {
     /// Failed to read a UTF-16 string
     #[display("Failed to read a UTF-16 string")]
     NotUtf16,
-    /// A non-column field appeared in the first record
-    #[display("While parsing first record, got field of type {}")]
-    NonColumnField(FieldType),
+    /// A non-column field appeared in the record defining the table's columns
+    #[display("While parsing the column definitions, got a field of type {ftype} at {offset:#x}")]
+    NonColumnField { ftype: FieldType, offset: u64 },
+    /// A non-index field appeared in the record defining the table's indicies
+    #[display("While parsing the index definitions, got a field of type {ftype} at {offset:#x}")]
+    NonIndexField { ftype: FieldType, offset: u64 },
     /// Bad format specification
     #[display("Couldn't interepret {} as a format")]
     BadFormat(String),
+    /// A file is gzip-compressed, but we were built without gzip support
+    #[display("{} is gzip-compressed, but xnde was built without the `gzip' feature")]
+    NoGzip(String),
+    /// Both the index & data files were to be read from stdin
+    #[display("Only one of the index & data files may be read from stdin")]
+    StdinTwice,
+    /// An index entry points past the end of the data file
+    #[display("Record {record} is at offset {offset:#x}, but the data file is only {len} bytes")]
+    OffsetOutOfRange {
+        record: usize,
+        offset: u64,
+        len: u64,
+    },
+    /// Unknown output encoding
+    #[display("Unknown encoding {}")]
+    BadEncoding(String),
+    /// An output encoding was requested, but we were built without encoding support
+    #[display("Can't write {} output; xnde was built without the `encoding' feature")]
+    NoEncoding(String),
+    /// Output in the given format can't be appended to an existing file
+    #[display("Can't append {} output to an existing file; only ndjson can be appended to")]
+    CantAppend(Format),
+    /// The table's metadata records can't be written in the given format
+    #[display("Can't include the table's metadata in {} output")]
+    NoMetaIn(Format),
+    /// Failed to build a track from a record-- cf. source
+    #[display("Failed to parse record {record} at offset {offset:#x}-- cf. source.")]
+    RecordParse { record: usize, offset: u64 },
+    /// Ran out of data while parsing a record
+    #[display(
+        "Hit the end of the data file while parsing record {record} at offset {offset:#x}; the \
+         file may be truncated, or the index out of date"
+    )]
+    UnexpectedEof { record: usize, offset: u64 },
+    /// A field header named a field type we don't recognize
+    #[display("Unknown field type {byte} in the field header at offset {offset:#x}")]
+    UnknownFieldType { offset: u64, byte: u8 },
+    /// A record number past the end of the primary index
+    #[display("There is no record {record}; the table has only {len} records")]
+    NoSuchRecord { record: usize, len: usize },
+    /// Parsing a field consumed bytes belonging to the next field
+    #[display("The field at {offset:#x} runs through {end:#x}, past the next field at {next:#x}")]
+    FieldOverrun { offset: u64, end: u64, next: u64 },
+    #[display("The column definitions at {offset:#x} run past {limit} columns")]
+    TooManyColumns { offset: u64, limit: usize },
+    /// A schema was requested, but we were built without schema support
+    #[display("Can't describe the track schema; xnde was built without the `schema' feature")]
+    NoSchema,
+    /// An index in the index file ended before all its records had been read
+    #[display(
+        "The index file is truncated: an index ends after {got} of its {expected} records; \
+         it may have been copied incompletely"
+    )]
+    TruncatedIndex { expected: usize, got: usize },
+    /// The index file's record count is more than the file could possibly hold
+    #[display("The index file claims {count} records, but is only {len} bytes long")]
+    BadRecordCount { count: usize, len: u64 },
+    /// iTunes output was requested, but we were built without iTunes support
+    #[display("Can't write an iTunes library; xnde was built without the `itunes' feature")]
+    NoItunes,
+    /// Serialized output was requested, but we were built without serde support
+    #[display("Can't write {} output; xnde was built without the `serde' feature")]
+    NoSerde(Format),
+    /// A directory holds no NDE tables
+    #[display("No NDE tables (pairs of `.idx' & `.dat' files) found in {}")]
+    NoTables(String),
+    /// A chain of redirectors led back to one already followed
+    #[display("The redirector at {offset:#x} is part of a cycle")]
+    RedirectCycle { offset: u64 },
+    /// A record's chain of fields led back to a field already read
+    #[display("The record at {record:#x} loops back to its field at {offset:#x}")]
+    FieldCycle { record: u64, offset: u64 },
+    /// An export gave up on a table for reading more than it was permitted to
+    #[display("Gave up after exceeding the limit of {limit} {what}")]
+    LimitExceeded { what: &'static str, limit: u64 },
 }
 
-#[derive(Debug, Display)]
-#[display("{cause} Source (if any): {source} Stack trace (if any): {trace}")]
+#[derive(Debug)]
 pub struct Error {
     /// Enumerated status code
-    #[display("XNDE error {}.")]
     cause: Cause,
     // This is an Option that may contain a Box containing something that implements
     // std::error::Error.  It is still unclear to me how this satisfies the lifetime bound in
@@ -158,13 +271,9 @@ pub struct Error {
     // lifetime. There is a discussion of this at
     // <https://users.rust-lang.org/t/what-does-it-mean-to-return-dyn-error-static/37619/6>,
     // but at the time of this writing, i cannot follow it.
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("XNDE error caused by {:#?}.")]
-    source: Option<Box<dyn std::error::Error>>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
     /// Optional backtrace
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("backtrace: {:#?}.")]
-    trace: Option<backtrace::Backtrace>,
+    trace: Option<Backtrace>,
 }
 
 impl Error {
@@ -173,9 +282,113 @@ impl Error {
         Error {
             cause: cause,
             source: None,
-            trace: Some(backtrace::Backtrace::new()),
+            trace: capture_backtrace(),
+        }
+    }
+    /// What went wrong
+    pub fn cause(&self) -> &Cause {
+        &self.cause
+    }
+    /// Wrap this error in one noting the number & offset of the record that was being parsed when
+    /// it occurred; if it came down to running out of data, say so, since that most likely means
+    /// a truncated data file
+    fn at_record(self, record: usize, offset: u64) -> Error {
+        let cause = if self.is_eof() {
+            Cause::UnexpectedEof {
+                record: record,
+                offset: offset,
+            }
+        } else {
+            Cause::RecordParse {
+                record: record,
+                offset: offset,
+            }
+        };
+        Error {
+            cause: cause,
+            source: Some(Box::new(self)),
+            trace: capture_backtrace(),
+        }
+    }
+    /// Return true if this error, or any underlying it, is an I/O error for want of data
+    fn is_eof(&self) -> bool {
+        let mut err: Option<&(dyn std::error::Error + 'static)> = Some(self);
+        while let Some(x) = err {
+            if let Some(io) = x.downcast_ref::<std::io::Error>() {
+                if io.kind() == std::io::ErrorKind::UnexpectedEof {
+                    return true;
+                }
+            }
+            err = x.source();
+        }
+        false
+    }
+}
+
+/// Return true if the user has asked for backtraces: either by building xnde with the
+/// `backtraces` feature, or by setting `RUST_BACKTRACE`
+fn backtraces_requested() -> bool {
+    cfg!(feature = "backtraces") || std::env::var_os("RUST_BACKTRACE").map_or(false, |x| x != "0")
+}
+
+/// The backtrace captured by xnde's errors
+#[cfg(feature = "backtrace")]
+pub type Backtrace = backtrace::Backtrace;
+
+/// Stand-in for a backtrace when xnde is built without the `backtrace` feature; it is never
+/// actually captured
+#[cfg(not(feature = "backtrace"))]
+#[derive(Debug)]
+pub struct Backtrace;
+
+/// Capture a backtrace for a new error, but only if backtraces have been requested; capturing
+/// them is expensive, and most errors are just reported & forgotten
+#[cfg(feature = "backtrace")]
+pub fn capture_backtrace() -> Option<Backtrace> {
+    if backtraces_requested() {
+        Some(backtrace::Backtrace::new())
+    } else {
+        None
+    }
+}
+
+#[cfg(not(feature = "backtrace"))]
+pub fn capture_backtrace() -> Option<Backtrace> {
+    None
+}
+
+/// Format an xnde error: its cause, followed (unless the alternate flag is given) by the chain of
+/// errors underlying it, one per line, and its backtrace if backtraces have been requested
+///
+/// Each source is formatted with the alternate flag, which xnde's error types take to mean "just
+/// the cause, please", since we're walking the chain here.
+pub fn fmt_error(
+    f: &mut std::fmt::Formatter,
+    cause: &dyn std::fmt::Display,
+    err: &dyn std::error::Error,
+    trace: Option<&Backtrace>,
+) -> std::fmt::Result {
+    write!(f, "{}", cause)?;
+    if f.alternate() {
+        return Ok(());
+    }
+    let mut source = err.source();
+    while let Some(src) = source {
+        write!(f, "\n    caused by: {:#}", src)?;
+        source = src.source();
+    }
+    if let Some(trace) = trace {
+        if backtraces_requested() {
+            write!(f, "\n{:?}", trace)?;
         }
     }
+    Ok(())
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt_error(f, &self.cause, self, self.trace.as_ref())
+    }
 }
 
 impl std::error::Error for Error {
@@ -193,89 +406,221 @@ impl std::error::Error for Error {
     }
 }
 
-impl std::convert::From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
-    }
-}
+error_from!(std::io::Error);
+error_from!(std::string::FromUtf8Error, NotUtf8);
+error_from!(std::string::FromUtf16Error, NotUtf16);
+error_from!(fields::Error);
+error_from!(crate::tracks::Error);
 
-impl std::convert::From<std::string::FromUtf8Error> for Error {
-    fn from(err: std::string::FromUtf8Error) -> Self {
-        Error {
-            cause: Cause::NotUtf8,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
-    }
-}
+#[cfg(feature = "serde")]
+error_from!(serde_lexpr::error::Error);
+#[cfg(feature = "serde")]
+error_from!(serde_json::error::Error);
 
-impl std::convert::From<std::string::FromUtf16Error> for Error {
-    fn from(err: std::string::FromUtf16Error) -> Self {
-        Error {
-            cause: Cause::NotUtf16,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
-    }
-}
+#[cfg(feature = "parallel")]
+error_from!(rayon::ThreadPoolBuildError);
 
-impl std::convert::From<fields::Error> for Error {
-    fn from(err: fields::Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
+#[cfg(feature = "itunes")]
+error_from!(plist::Error);
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod error_tests {
+
+    /// Errors should display their chain of sources, one per line
+    #[test]
+    fn chain() {
+        use super::*;
+        let err = fields::Error::from(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "disk on fire",
+        ))
+        .at(0x20, FieldType::String);
+        let err = Error::from(err);
+        let text = format!("{}", err);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "An error in another crate or module-- cf. source."
+        );
+        assert_eq!(
+            lines[1],
+            "    caused by: Failed to parse a field of type STRING at offset 0x20-- cf. source."
+        );
+        assert_eq!(
+            lines[2],
+            "    caused by: An error in another crate or  module-- cf. source."
+        );
+        assert_eq!(lines[3], "    caused by: disk on fire");
+        assert_eq!(
+            format!("{:#}", err),
+            "An error in another crate or module-- cf. source."
+        );
     }
 }
 
-impl std::convert::From<serde_lexpr::error::Error> for Error {
-    fn from(err: serde_lexpr::error::Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
-    }
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                         parse options                                          //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Options governing how a table's index & data files are read; the defaults suit tables written
+/// by Winamp on Windows
+///
+/// These are carried along with each table (by [`Table`] & [`Tracks`], e.g.), so tables written
+/// differently may be read side-by-side.
+///
+/// [`Table`]: struct.Table.html
+/// [`Tracks`]: struct.Tracks.html
+#[derive(Clone, Debug)]
+pub struct ParseOptions {
+    byte_order: fields::ByteOrder,
+    check_overruns: bool,
+    max_field_size: usize,
+    guess_utf16: bool,
+    strict_utf8: bool,
+    string_fallback: fields::StringFallback,
+    length_units: fields::LengthUnits,
+    buffer_size: usize,
+    max_columns: usize,
+    resync_records: bool,
+    omit_empty_strings: bool,
+    column_aliases: Vec<(String, TrackAttrs)>,
 }
 
-impl std::convert::From<serde_json::error::Error> for Error {
-    fn from(err: serde_json::error::Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
+impl std::default::Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            byte_order: fields::ByteOrder::Little,
+            check_overruns: false,
+            max_field_size: fields::DEFAULT_MAX_FIELD_SIZE,
+            guess_utf16: false,
+            strict_utf8: false,
+            string_fallback: fields::StringFallback::Error,
+            length_units: fields::LengthUnits::Auto,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            max_columns: DEFAULT_MAX_COLUMNS,
+            resync_records: false,
+            omit_empty_strings: false,
+            column_aliases: Vec::new(),
         }
     }
 }
 
-impl std::convert::From<crate::tracks::Error> for Error {
-    fn from(err: crate::tracks::Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
+impl ParseOptions {
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+    /// Read integers in the index & data files in this byte order (little-endian by default)
+    pub fn byte_order(mut self, order: fields::ByteOrder) -> ParseOptions {
+        self.byte_order = order;
+        self
+    }
+    /// Check each field against the offset of the next field in its record (i.e. that parsing it
+    /// didn't consume the start of the next; off by default)
+    pub fn check_overruns(mut self, check: bool) -> ParseOptions {
+        self.check_overruns = check;
+        self
+    }
+    /// Refuse to allocate more than this many bytes to read any one field; fields claiming more
+    /// will fail to parse with `fields::Cause::FieldTooLarge`. Sizes are read straight from the
+    /// data file, so this guards against corrupt (or malicious) files provoking enormous
+    /// allocations.
+    pub fn max_field_size(mut self, size: usize) -> ParseOptions {
+        self.max_field_size = size;
+        self
+    }
+    /// Check strings without a BOM for UTF-16LE encoding before assuming them to be UTF-8 (off by
+    /// default)
+    pub fn guess_utf16(mut self, guess: bool) -> ParseOptions {
+        self.guess_utf16 = guess;
+        self
+    }
+    /// Always decode strings as UTF-8, with no attempt to detect UTF-16 (BOM or no), failing on
+    /// invalid text (off by default); this takes precedence over both `guess_utf16` &
+    /// `string_fallback`
+    pub fn strict_utf8(mut self, strict: bool) -> ParseOptions {
+        self.strict_utf8 = strict;
+        self
+    }
+    /// Handle strings that are neither UTF-16 nor valid UTF-8 thus ([`StringFallback::Error`] by
+    /// default)
+    ///
+    /// [`StringFallback::Error`]: fields/enum.StringFallback.html#variant.Error
+    pub fn string_fallback(mut self, fallback: fields::StringFallback) -> ParseOptions {
+        self.string_fallback = fallback;
+        self
+    }
+    /// Interpret LENGTH fields in these units ([`LengthUnits::Auto`] by default)
+    ///
+    /// [`LengthUnits::Auto`]: fields/enum.LengthUnits.html#variant.Auto
+    pub fn length_units(mut self, units: fields::LengthUnits) -> ParseOptions {
+        self.length_units = units;
+        self
+    }
+    /// Read data files through a buffer of this many bytes; zero means read them directly, with a
+    /// syscall for every read & seek
+    pub fn buffer_size(mut self, size: usize) -> ParseOptions {
+        self.buffer_size = size;
+        self
+    }
+    /// Give up on a table after reading this many column definitions; no real NDE table has more
+    /// than a few dozen, so this mostly guards against garbage offsets sending us round in circles
+    pub fn max_columns(mut self, limit: usize) -> ParseOptions {
+        self.max_columns = limit;
+        self
+    }
+    /// Check the offset of each record before reading it, & if it doesn't look like the start of
+    /// a record, search a small window around it for one that does (off by default); this
+    /// salvages indicies that are slightly out of step with their data file
+    pub fn resync_records(mut self, resync: bool) -> ParseOptions {
+        self.resync_records = resync;
+        self
+    }
+    /// Treat empty strings as missing attributes when building [`Track`]s (& so omit them when
+    /// serializing), rather than keeping them as `""` (the default)
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn omit_empty_strings(mut self, omit: bool) -> ParseOptions {
+        self.omit_empty_strings = omit;
+        self
+    }
+    /// Map these column names to [`Track`] attributes, in preference to the built-in names, so
+    /// that a library whose plugins name their columns unconventionally (`ARTIST_V2`, e.g.) can
+    /// still be read
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn column_aliases(mut self, aliases: Vec<(String, TrackAttrs)>) -> ParseOptions {
+        self.column_aliases = aliases;
+        self
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
-
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 //                                           NDE Index                                            //
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// A single index in an NDE index file: an ID, together with a list of records' offsets into the
+/// data file (and a second, as-yet unexplained, value for each), in the order defined by that
+/// index
+///
+/// Obtain these via [`read_indicies`].
+///
+/// [`read_indicies`]: fn.read_indicies.html
 pub struct NdeIndex {
-    _id: i32,
-    table: Vec<(u64, i32)>,
+    id: i32,
+    /// Each record's offset into the data file...
+    offsets: Vec<u64>,
+    /// ...& its second value
+    extras: Vec<i32>,
 }
 
 impl NdeIndex {
-    fn from_reader<R: std::io::Read>(r: &mut R, nrec: usize) -> Result<Option<NdeIndex>> {
+    fn from_reader<R: std::io::Read>(
+        r: &mut R,
+        nrec: usize,
+        opts: &ParseOptions,
+    ) -> Result<Option<NdeIndex>> {
         // We expect to be looking at this index's ID...
         let mut buf: [u8; 4] = [0; 4];
         // if this file is exhausted, we are looking at EOF. Check for that in particular:
@@ -289,33 +634,80 @@ impl NdeIndex {
             }
             _ => (),
         }
-        let id = i32::from_le_bytes(buf);
+        let id = opts.byte_order.i32(buf);
 
-        let mut table: Vec<(u64, i32)> = Vec::with_capacity(nrec);
-        for _i in 0..nrec {
-            r.read_exact(&mut buf)?;
-            let off = u32::from_le_bytes(buf);
-            r.read_exact(&mut buf)?;
-            let collab = i32::from_le_bytes(buf);
-            table.push((off as u64, collab));
+        // Past the ID, though, running out of data means the file is short
+        let truncated = |err: std::io::Error, got: usize| {
+            if err.kind() == std::io::ErrorKind::UnexpectedEof {
+                Error::new(Cause::TruncatedIndex {
+                    expected: nrec,
+                    got: got,
+                })
+            } else {
+                Error::from(err)
+            }
+        };
+        let mut offsets: Vec<u64> = Vec::with_capacity(nrec);
+        let mut extras: Vec<i32> = Vec::with_capacity(nrec);
+        for i in 0..nrec {
+            r.read_exact(&mut buf).map_err(|err| truncated(err, i))?;
+            let off = opts.byte_order.u32(buf);
+            r.read_exact(&mut buf).map_err(|err| truncated(err, i))?;
+            let collab = opts.byte_order.i32(buf);
+            offsets.push(off as u64);
+            extras.push(collab);
         }
         Ok(Some(NdeIndex {
-            _id: id,
-            table: table,
+            id: id,
+            offsets: offsets,
+            extras: extras,
         }))
     }
+    /// Retrieve this index's ID
+    pub fn id(&self) -> i32 {
+        self.id
+    }
     /// Retrieve the offset for record i in this index
-    fn off(&self, i: usize) -> u64 {
-        self.table[i].0
+    pub fn off(&self, i: usize) -> u64 {
+        self.offsets[i]
+    }
+    /// Retrieve the second value for record i in this index (the "????" above-- I don't know what
+    /// this is, but it's exposed in the hopes someone will figure it out)
+    pub fn extra(&self, i: usize) -> i32 {
+        self.extras[i]
     }
-    fn len(&self) -> usize {
-        self.table.len()
+    /// The number of records in this index
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+    /// True if this index has no records
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+    /// Iterate over the records in this index, in order, yielding each one's offset into the data
+    /// file along with its second value
+    pub fn iter(&self) -> impl Iterator<Item = (u64, i32)> + '_ {
+        self.offsets
+            .iter()
+            .copied()
+            .zip(self.extras.iter().copied())
+    }
+    /// The offsets into the data file of the records in this index, in order
+    pub fn offsets(&self) -> &[u64] {
+        &self.offsets
+    }
+}
+
+impl From<&NdeIndex> for Vec<u64> {
+    /// Collect the offsets of an index's records, in order
+    fn from(index: &NdeIndex) -> Vec<u64> {
+        index.offsets().to_vec()
     }
 }
 
 /// Read all indicies out of an index file; rdr is assumed to be pointing at the signature (i.e.
 /// byte zero if we're reading a .idx file)
-pub fn read_indicies<R: Read + Seek>(rdr: &mut R) -> Result<Vec<NdeIndex>> {
+pub fn read_indicies<R: Read + Seek>(rdr: &mut R, opts: &ParseOptions) -> Result<Vec<NdeIndex>> {
     let mut buf: [u8; 8] = [0; 8];
     rdr.read_exact(&mut buf)?;
     if b"NDEINDEX" != &buf {
@@ -324,19 +716,114 @@ pub fn read_indicies<R: Read + Seek>(rdr: &mut R) -> Result<Vec<NdeIndex>> {
 
     let mut buf: [u8; 4] = [0; 4];
     rdr.read_exact(&mut buf)?;
-    let nrecs = u32::from_le_bytes(buf) as usize;
+    let nrecs = opts.byte_order.u32(buf) as usize;
+
+    // The record count comes straight from the file, & we'll allocate space for that many; so
+    // make sure there's room for at least one index that size (four bytes of ID, eight per record);
+    // a table with no records may have no indicies at all, however
+    let here = rdr.stream_position()?;
+    let len = rdr.seek(SeekFrom::End(0))?;
+    rdr.seek(SeekFrom::Start(here))?;
+    let need = (nrecs as u64).checked_mul(8).and_then(|x| x.checked_add(4));
+    if nrecs != 0 && need.is_none_or(|need| need > len.saturating_sub(here)) {
+        return Err(Error::new(Cause::BadRecordCount {
+            count: nrecs,
+            len: len,
+        }));
+    }
 
     // Read {id, nrec*(u32,i32)} until EOF
     let mut idxes: Vec<NdeIndex> = Vec::new();
-    let mut next = NdeIndex::from_reader(rdr, nrecs)?;
+    let mut next = NdeIndex::from_reader(rdr, nrecs, opts)?;
     while let Some(index) = next {
         idxes.push(index);
-        next = NdeIndex::from_reader(rdr, nrecs)?;
+        next = NdeIndex::from_reader(rdr, nrecs, opts)?;
     }
 
     Ok(idxes)
 }
 
+/// All the indicies in an NDE index file
+///
+/// The first index is the primary; the record numbers used throughout this crate are positions
+/// in it. Each subsequent index references the same records, ordered differently (by artist, say,
+/// or by title). [`Indices::record_order`] recovers that ordering in terms of primary record
+/// numbers.
+///
+/// [`Indices::record_order`]: struct.Indices.html#method.record_order
+pub struct Indices {
+    indices: Vec<NdeIndex>,
+}
+
+impl std::convert::From<Vec<NdeIndex>> for Indices {
+    fn from(indices: Vec<NdeIndex>) -> Indices {
+        Indices { indices: indices }
+    }
+}
+
+impl Indices {
+    /// Read all indicies out of an index file; rdr is assumed to be pointing at the signature
+    pub fn from_reader<R: Read + Seek>(rdr: &mut R, opts: &ParseOptions) -> Result<Indices> {
+        Ok(Indices::from(read_indicies(rdr, opts)?))
+    }
+    /// The indicies in this file, primary first
+    pub fn indices(&self) -> &[NdeIndex] {
+        &self.indices
+    }
+    /// The primary index, if there is one
+    pub fn primary(&self) -> Option<&NdeIndex> {
+        self.indices.first()
+    }
+    /// Consume these indicies, returning the primary (if there is one)
+    pub fn into_primary(self) -> Option<NdeIndex> {
+        self.indices.into_iter().next()
+    }
+    /// Return the order in which the index with ID `index_id` visits the records, expressed as
+    /// primary record numbers
+    ///
+    /// Returns `None` if there is no such index. Offsets that don't appear in the primary (see
+    /// [`check_indices`]) are skipped, so the result may be shorter than the index.
+    ///
+    /// [`check_indices`]: fn.check_indices.html
+    pub fn record_order(&self, index_id: i32) -> Option<Vec<usize>> {
+        let index = self.indices.iter().find(|x| x.id() == index_id)?;
+        let recnos = self.record_numbers();
+        Some(
+            index
+                .iter()
+                .filter_map(|(off, _)| recnos.get(&off).copied())
+                .collect(),
+        )
+    }
+    /// Iterate over every index's ordering of the records, yielding each index's ID along with
+    /// its [`record_order`]
+    ///
+    /// [`record_order`]: #method.record_order
+    pub fn orderings(&self) -> impl Iterator<Item = (i32, Vec<usize>)> + '_ {
+        let recnos = self.record_numbers();
+        self.indices.iter().map(move |index| {
+            (
+                index.id(),
+                index
+                    .iter()
+                    .filter_map(|(off, _)| recnos.get(&off).copied())
+                    .collect(),
+            )
+        })
+    }
+    /// Map each offset in the primary index to its record number
+    fn record_numbers(&self) -> HashMap<u64, usize> {
+        match self.primary() {
+            Some(primary) => primary
+                .iter()
+                .enumerate()
+                .map(|(i, (off, _))| (off, i))
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod index_tests {
 
@@ -348,7 +835,7 @@ mod index_tests {
             0xff, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00,
             0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
         ];
-        let idx = match NdeIndex::from_reader(&mut bytes.as_ref(), 2) {
+        let idx = match NdeIndex::from_reader(&mut bytes.as_ref(), 2, &ParseOptions::default()) {
             Ok(opt) => match opt {
                 Some(x) => x,
                 None => {
@@ -360,8 +847,39 @@ mod index_tests {
             }
         };
         assert_eq!(idx.len(), 2);
+        assert_eq!(idx.id(), 255);
         assert_eq!(idx.off(0), 8);
+        assert_eq!(idx.extra(0), 0);
         assert_eq!(idx.off(1), 32);
+        assert_eq!(idx.extra(1), 1);
+        assert_eq!(idx.iter().collect::<Vec<_>>(), vec![(8, 0), (32, 1)]);
+        assert_eq!(idx.offsets(), &[8, 32]);
+        assert_eq!(Vec::<u64>::from(&idx), vec![8, 32]);
+        Ok(())
+    }
+
+    /// Indicies of either byte order should be readable side-by-side
+    #[test]
+    fn byte_order() -> Result<(), String> {
+        use super::*;
+        let big = ParseOptions::new().byte_order(fields::ByteOrder::Big);
+        let little = ParseOptions::default();
+        let read = |bytes: &[u8], opts: &ParseOptions| -> std::result::Result<u64, String> {
+            match NdeIndex::from_reader(&mut bytes.as_ref(), 1, opts) {
+                Ok(Some(idx)) => Ok(idx.off(0)),
+                Ok(None) => Err(String::from("premature EOF")),
+                Err(err) => Err(format!("{}", err)),
+            }
+        };
+        let be: [u8; 12] = [
+            0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let le: [u8; 12] = [
+            0xff, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(read(&be, &big)?, 8);
+        assert_eq!(read(&le, &little)?, 8);
+        assert_eq!(read(&le, &big)?, 0x08000000);
         Ok(())
     }
 
@@ -372,11 +890,17 @@ mod index_tests {
         let bytes: [u8; 12] = [
             0xff, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
         ];
-        let _idx = match NdeIndex::from_reader(&mut bytes.as_ref(), 2) {
+        let _idx = match NdeIndex::from_reader(&mut bytes.as_ref(), 2, &ParseOptions::default()) {
             Ok(_) => {
                 return Err(String::from("construction should have failed"));
             }
-            Err(_) => (),
+            Err(err) => match err.cause() {
+                Cause::TruncatedIndex { expected, got } => {
+                    assert_eq!(*expected, 2);
+                    assert_eq!(*got, 1);
+                }
+                _ => return Err(format!("unexpected error {}", err)),
+            },
         };
         Ok(())
     }
@@ -400,7 +924,7 @@ mod index_tests {
         buf.extend_from_slice(&1i32.to_le_bytes());
 
         let mut cur = std::io::Cursor::new(buf);
-        match read_indicies(&mut cur) {
+        match read_indicies(&mut cur, &ParseOptions::default()) {
             Ok(v) => {
                 assert_eq!(v.len(), 2);
                 assert_eq!(v[0].len(), 2);
@@ -417,26 +941,136 @@ mod index_tests {
 
         Ok(())
     }
+
+    /// A record count the file couldn't possibly hold should be caught before we allocate for it
+    #[test]
+    fn bad_count() -> Result<(), String> {
+        use super::*;
+        let mut buf: Vec<u8> = b"NDEINDEX".to_vec();
+        buf.extend_from_slice(&u32::MAX.to_le_bytes());
+        buf.extend_from_slice(&[0x00; 12]);
+        match read_indicies(&mut std::io::Cursor::new(buf), &ParseOptions::default()) {
+            Err(err) => match err.cause() {
+                Cause::BadRecordCount { count, len } => {
+                    assert_eq!(*count, u32::MAX as usize);
+                    assert_eq!(*len, 24);
+                }
+                _ => return Err(format!("unexpected error {}", err)),
+            },
+            Ok(_) => return Err(String::from("the record count should have been rejected")),
+        }
+        Ok(())
+    }
+
+    /// A header-only index (that of an empty table) should read as holding no indicies
+    #[test]
+    fn empty() -> Result<(), String> {
+        use super::*;
+        let mut buf: Vec<u8> = b"NDEINDEX".to_vec();
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        match read_indicies(&mut std::io::Cursor::new(buf), &ParseOptions::default()) {
+            Ok(v) => assert!(v.is_empty()),
+            Err(err) => return Err(format!("{}", err)),
+        }
+        Ok(())
+    }
+
+    /// An index cut off partway through its records should be reported as such
+    #[test]
+    fn truncated() -> Result<(), String> {
+        use super::*;
+        let mut buf: Vec<u8> = b"NDEINDEX".to_vec();
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        // a complete primary...
+        buf.extend_from_slice(&0xffi32.to_le_bytes());
+        buf.extend_from_slice(&[0x08, 0, 0, 0, 0, 0, 0, 0, 0x20, 0, 0, 0, 0, 0, 0, 0]);
+        // ...followed by a secondary that stops halfway through its second record
+        buf.extend_from_slice(&0i32.to_le_bytes());
+        buf.extend_from_slice(&[0x20, 0, 0, 0, 0, 0, 0, 0, 0x08, 0, 0, 0]);
+        match read_indicies(&mut std::io::Cursor::new(buf), &ParseOptions::default()) {
+            Err(err) => match err.cause() {
+                Cause::TruncatedIndex { expected, got } => {
+                    assert_eq!(*expected, 2);
+                    assert_eq!(*got, 1);
+                }
+                _ => return Err(format!("unexpected error {}", err)),
+            },
+            Ok(_) => return Err(String::from("the truncation should have been caught")),
+        }
+        Ok(())
+    }
+
+    /// Secondary indicies' orderings should be expressed as primary record numbers
+    #[test]
+    fn record_order() {
+        use super::*;
+        let indices = Indices::from(vec![
+            NdeIndex {
+                id: 255,
+                offsets: vec![8, 0x20, 0x40],
+
+                extras: vec![0, 0, 0],
+            },
+            NdeIndex {
+                id: 0,
+                offsets: vec![0x40, 8, 0x20],
+
+                extras: vec![0, 0, 0],
+            },
+            NdeIndex {
+                id: 1,
+                offsets: vec![0x20, 0x30, 8],
+
+                extras: vec![0, 0, 0],
+            },
+        ]);
+        assert_eq!(indices.record_order(255), Some(vec![0, 1, 2]));
+        assert_eq!(indices.record_order(0), Some(vec![2, 0, 1]));
+        assert_eq!(indices.record_order(1), Some(vec![1, 0]));
+        assert_eq!(indices.record_order(2), None);
+        assert_eq!(
+            indices.orderings().collect::<Vec<_>>(),
+            vec![(255, vec![0, 1, 2]), (0, vec![2, 0, 1]), (1, vec![1, 0])]
+        );
+    }
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 //                                       redirect handling                                        //
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-fn follow_redirects<R: Read + Seek>(rdr: &mut R) -> Result<(u8, FieldType)> {
+/// Read the field header at the current position, following any redirectors to the field proper;
+/// return its ID & type, leaving `rdr` positioned just past them
+///
+/// The redirectors' offsets come straight from the file, so a corrupt (or malicious) file could
+/// send us round in circles; each redirector may be visited only once.
+fn follow_redirects<R: Read + Seek>(rdr: &mut R, opts: &ParseOptions) -> Result<(u8, FieldType)> {
     let mut id: u8 = 0;
     let mut ftype = FieldType::Redirector;
+    let mut seen: HashSet<u64> = HashSet::new();
     while ftype == FieldType::Redirector {
         // read two chars: ID & type
+        let offset = rdr.stream_position()?;
+        if !seen.insert(offset) {
+            return Err(Error::new(Cause::RedirectCycle { offset: offset }));
+        }
         let mut buf: [u8; 2] = [0; 2];
         rdr.read_exact(&mut buf)?;
 
         id = buf[0];
-        ftype = FieldType::from(buf[1])?;
+        ftype = match FieldType::from(buf[1]) {
+            Ok(ftype) => ftype,
+            Err(_) => {
+                return Err(Error::new(Cause::UnknownFieldType {
+                    offset: offset,
+                    byte: buf[1],
+                }));
+            }
+        };
         if ftype == FieldType::Redirector {
             let mut buf: [u8; 4] = [0; 4];
             rdr.read_exact(&mut buf)?;
-            let at = u32::from_le_bytes(buf) as u64;
+            let at = opts.byte_order.u32(buf) as u64;
             rdr.seek(SeekFrom::Start(at))?;
             debug!("found redirect, jumping to {:#04x}", at);
         }
@@ -445,6 +1079,42 @@ fn follow_redirects<R: Read + Seek>(rdr: &mut R) -> Result<(u8, FieldType)> {
     Ok((id, ftype))
 }
 
+/// Maximum number of bytes we'll scan looking for a field header after an unknown field type
+const RESYNC_WINDOW: u64 = 64 * 1024;
+
+/// Return true if `hdr` (at least fourteen bytes, found at offset `at` in a data file of `len`
+/// bytes) could plausibly be a field header: a known type, a size that fits in the file, & a
+/// next field (if any) following this one
+fn plausible_header(hdr: &[u8], at: u64, len: u64, opts: &ParseOptions) -> bool {
+    let u32_at = |i: usize| {
+        opts.byte_order
+            .u32([hdr[i], hdr[i + 1], hdr[i + 2], hdr[i + 3]])
+    };
+    match FieldType::from(hdr[1]) {
+        Ok(FieldType::Redirector) | Err(_) => return false,
+        _ => (),
+    }
+    let end = at + 14 + u32_at(2) as u64;
+    // NB. the next field's offset precedes the previous field's
+    let (next, prev) = (u32_at(6) as u64, u32_at(10) as u64);
+    end <= len && prev < at && (next == 0 || (next >= end && next < len))
+}
+
+/// Scan forward from `from` for something that looks like a field header, returning its offset;
+/// this is best-effort, & gives up after [`RESYNC_WINDOW`] bytes
+fn resync<R: Read + Seek>(rdr: &mut R, from: u64, opts: &ParseOptions) -> Result<Option<u64>> {
+    let len = rdr.seek(SeekFrom::End(0))?;
+    if from >= len {
+        return Ok(None);
+    }
+    rdr.seek(SeekFrom::Start(from))?;
+    let mut buf: Vec<u8> = Vec::new();
+    rdr.by_ref().take(RESYNC_WINDOW).read_to_end(&mut buf)?;
+    Ok((0..buf.len().saturating_sub(13))
+        .find(|&i| plausible_header(&buf[i..i + 14], from + i as u64, len, opts))
+        .map(|i| from + i as u64))
+}
+
 #[cfg(test)]
 mod redirect_tests {
 
@@ -454,7 +1124,7 @@ mod redirect_tests {
         use super::*;
         let bytes: [u8; 2] = [0x01, 0x00];
         let mut rdr = std::io::Cursor::new(bytes);
-        match follow_redirects(&mut rdr) {
+        match follow_redirects(&mut rdr, &ParseOptions::default()) {
             Ok((id, ft)) => {
                 assert_eq!(id, 1);
                 assert_eq!(ft, FieldType::Column);
@@ -466,181 +1136,585 @@ mod redirect_tests {
 
         Ok(())
     }
-}
-
-////////////////////////////////////////////////////////////////////////////////////////////////////
-//                                         dumping logic                                          //
-////////////////////////////////////////////////////////////////////////////////////////////////////
-
-#[derive(Debug)]
-pub enum DumpFormat {
-    Display,
-    Sexp,
-    Json,
-}
 
-impl TryFrom<&str> for DumpFormat {
-    type Error = Error;
-    fn try_from(x: &str) -> std::result::Result<DumpFormat, Error> {
-        match x {
-            "display" => Ok(DumpFormat::Display),
-            "sexp" => Ok(DumpFormat::Sexp),
-            "json" => Ok(DumpFormat::Json),
-            _ => Err(Error::new(Cause::BadFormat(String::from(x)))),
-        }
+    /// A redirector should send us to the field at the offset it names, even by way of another
+    /// redirector
+    #[test]
+    fn jump() -> Result<(), String> {
+        use super::*;
+        let mut bytes: Vec<u8> = vec![0x00, FieldType::Redirector as u8];
+        bytes.extend_from_slice(&0x20u32.to_le_bytes());
+        bytes.resize(0x10, 0xaa);
+        bytes.extend_from_slice(&[0x07, FieldType::Integer as u8]); // ID 7, INTEGER @ 0x10
+        bytes.resize(0x20, 0xaa);
+        bytes.extend_from_slice(&[0x00, FieldType::Redirector as u8]); // @ 0x20
+        bytes.extend_from_slice(&0x10u32.to_le_bytes());
+        let mut rdr = std::io::Cursor::new(bytes);
+        let (id, ft) = follow_redirects(&mut rdr, &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        assert_eq!(id, 7);
+        assert_eq!(ft, FieldType::Integer);
+        // & we should be positioned just past the real field's ID & type
+        assert_eq!(rdr.position(), 0x12);
+        Ok(())
     }
-}
-
-// TODO(sp1ff): re-write to take readers; write unit tests
-/// Dump the contents of a Winamp Music Library to stdout
-pub fn dump(idx: &Path, dat: &Path, format: DumpFormat) -> Result<()> {
-    let fdidx = File::open(idx)?;
-    let mut bufidx = BufReader::new(fdidx);
-    let idxes = read_indicies(&mut bufidx)?;
-    info!("There are {} indicies.", idxes.len());
 
-    if idxes.len() == 0 {
-        return Err(Error::new(Cause::NoIndicies));
+    /// A redirector that (eventually) points back at itself should be caught, not followed forever
+    #[test]
+    fn cycle() -> Result<(), String> {
+        use super::*;
+        let mut bytes: Vec<u8> = vec![0x00, FieldType::Redirector as u8];
+        bytes.extend_from_slice(&0x10u32.to_le_bytes());
+        bytes.resize(0x10, 0xaa);
+        bytes.extend_from_slice(&[0x00, FieldType::Redirector as u8]); // @ 0x10
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        match follow_redirects(&mut std::io::Cursor::new(bytes), &ParseOptions::default()) {
+            Ok(_) => Err(String::from("follow_redirects should have failed")),
+            Err(err) => match err.cause() {
+                Cause::RedirectCycle { offset } => {
+                    assert_eq!(*offset, 0);
+                    Ok(())
+                }
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
     }
-    let nrecs = idxes[0].len();
-    info!("Each index has {} records.", nrecs);
 
-    // Alright: if we've made it this far, we've parsed the index file. Now use the primary
-    // index to walk the data file.
-    let mut fddat = File::open(dat)?;
+    /// An unknown field type should be reported along with its offset, & we should be able to
+    /// find the next field header following it
+    #[test]
+    fn resync() -> Result<(), String> {
+        use super::*;
+        let mut bytes: Vec<u8> = vec![0x00; 4];
+        bytes.extend_from_slice(&[0x01, 0x63]); // ID 1, type 99
+        bytes.extend_from_slice(&[0xaa; 6]);
+        // ID 2, type INTEGER @ 0x0c
+        bytes.extend_from_slice(&builder::raw_record(
+            0x0c,
+            &[builder::integer(2, FieldType::Integer, -1)],
+        ));
 
-    let mut buf: [u8; 8] = [0; 8];
-    fddat.read_exact(&mut buf)?;
-    if b"NDETABLE" != &buf {
-        return Err(Error::new(Cause::NoSig));
+        let mut rdr = std::io::Cursor::new(bytes);
+        rdr.seek(SeekFrom::Start(4))
+            .map_err(|err| format!("{}", err))?;
+        match follow_redirects(&mut rdr, &ParseOptions::default()) {
+            Ok(_) => return Err(String::from("type 99 should have been rejected")),
+            Err(err) => match err.cause() {
+                Cause::UnknownFieldType {
+                    offset: 4,
+                    byte: 99,
+                } => (),
+                _ => return Err(format!("unexpected error {}", err)),
+            },
+        }
+        assert_eq!(
+            resync(&mut rdr, 5, &ParseOptions::default()).map_err(|err| format!("{}", err))?,
+            Some(0x0c)
+        );
+        assert_eq!(
+            resync(&mut rdr, 0x0e, &ParseOptions::default()).map_err(|err| format!("{}", err))?,
+            None
+        );
+        Ok(())
     }
 
-    for i in 0..nrecs {
-        let at = idxes[0].off(i);
-        debug!("Parsing record {} at {:#04x}.", i, at);
-        fddat.seek(SeekFrom::Start(at))?;
-
-        // we now walk the fields in record `i':
-        let mut next_field_pos: u64 = at;
+    /// A column record beginning with a redirector should be followed before checking the field
+    /// type, & a redirect landing on anything else should be reported with its offset
+    #[test]
+    fn redirected_columns() -> Result<(), String> {
+        use super::*;
+        // Offset zero ends a chain of fields, so the record follows the signature
+        let record = |ftype: u8| -> Vec<u8> {
+            let mut buf: Vec<u8> = b"NDETABLE".to_vec();
+            buf.extend_from_slice(&[0x00, 0x02]); // ID 0, type REDIRECTOR
+            buf.extend_from_slice(&0x18u32.to_le_bytes());
+            buf.resize(0x18, 0xaa);
+            buf.extend_from_slice(&builder::raw_record(
+                0x18,
+                &[builder::column(0, FieldType::Filename, "filename")],
+            ));
+            buf[0x19] = ftype;
+            buf
+        };
 
-        while next_field_pos != 0 {
-            let (id, ftype) = follow_redirects(&mut fddat)?;
-            // field-specific data follows..
-            match field_factory(&mut fddat, id as i32, ftype) {
-                Ok(x) => {
-                    // Display x:
-                    match format {
-                        DumpFormat::Display => info!("{}", x),
-                        DumpFormat::Sexp => info!("{}", serde_lexpr::to_string(&x)?),
-                        DumpFormat::Json => info!("{}", serde_json::to_string(&x)?),
-                    }
-                    next_field_pos = x.next_field_pos();
-                }
-                Err(err) => {
-                    return Err(Error::from(err));
-                }
-            }
+        let mut rdr = std::io::Cursor::new(record(0x00));
+        let cols = read_columns(&mut rdr, 8, &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        assert_eq!(cols.len(), 1);
+        assert_eq!(cols[0].name(), "filename");
+        assert_eq!(cols[0].col_type(), FieldType::Filename);
 
-            if next_field_pos != 0 {
-                fddat.seek(SeekFrom::Start(next_field_pos))?;
-            }
+        let mut rdr = std::io::Cursor::new(record(0x03));
+        match read_columns(&mut rdr, 8, &ParseOptions::default()) {
+            Ok(_) => return Err(String::from("a STRING field isn't a column")),
+            Err(err) => match err.cause() {
+                Cause::NonColumnField {
+                    ftype: FieldType::String,
+                    offset: 0x18,
+                } => (),
+                _ => return Err(format!("unexpected error {}", err)),
+            },
         }
+        Ok(())
     }
-
-    Ok(())
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
-//                                          export logic                                          //
+//                                         output formats                                         //
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-pub enum ExportFormat {
-    Json,
+/// Formats in which xnde can write its output
+///
+/// This enum is non-exhaustive, as more formats may be added; the predicates below say what a
+/// format can do without matching on every one.
+#[derive(Clone, Copy, Debug, Display, Eq, PartialEq)]
+#[display(style = "lowercase")]
+#[non_exhaustive]
+pub enum Format {
+    /// Human-readable text
+    Display,
+    /// S-expressions
     Sexp,
+    /// JSON
+    Json,
+    /// Newline-delimited JSON: one object per line
+    Ndjson,
+    /// An iTunes library (`iTunes Music Library.xml`); only tracks may be written this way
+    #[display("itunes")]
+    Itunes,
+}
+
+impl Format {
+    /// Return true if this format renders values via their [`Display`] implementations, rather
+    /// than serializing them; such output is meant for people, not programs, & so isn't available
+    /// everywhere (`export`, e.g.)
+    ///
+    /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+    pub fn supports_display(&self) -> bool {
+        matches!(self, Format::Display)
+    }
+    /// Return true if this format writes one item per line, with no enclosing structure; such
+    /// output can be extended simply by appending more lines
+    pub fn is_line_oriented(&self) -> bool {
+        matches!(self, Format::Ndjson)
+    }
+    /// Return true if this format can carry the table's metadata records alongside its tracks
+    /// (cf. [`ExportOptions::include_meta`])
+    ///
+    /// [`ExportOptions::include_meta`]: struct.ExportOptions.html#method.include_meta
+    pub fn supports_meta(&self) -> bool {
+        matches!(self, Format::Sexp | Format::Json)
+    }
+    /// Guess the format in which `path` is to be written from its extension (`.json`, `.sexp`,
+    /// &c); return `None` if it has none, or none we recognize
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "sexp" | "el" | "lisp" => Some(Format::Sexp),
+            "json" => Some(Format::Json),
+            "ndjson" | "jsonl" => Some(Format::Ndjson),
+            "xml" | "plist" => Some(Format::Itunes),
+            _ => None,
+        }
+    }
+    /// The extension conventionally given to files written in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Display => "txt",
+            Format::Sexp => "sexp",
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Itunes => "xml",
+        }
+    }
 }
 
-impl TryFrom<&str> for ExportFormat {
+impl TryFrom<&str> for Format {
     type Error = Error;
-    fn try_from(x: &str) -> std::result::Result<Self, Error> {
+    fn try_from(x: &str) -> std::result::Result<Format, Error> {
         match x {
-            "sexp" => Ok(ExportFormat::Sexp),
-            "json" => Ok(ExportFormat::Json),
+            "display" => Ok(Format::Display),
+            "sexp" => Ok(Format::Sexp),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "itunes" => Ok(Format::Itunes),
             _ => Err(Error::new(Cause::BadFormat(String::from(x)))),
         }
     }
 }
 
-// TODO(sp1ff): re-write to take readers; write unit tests
-/// transform your Winamp music library into an in-memory datastructure and serialize it
-/// to any variety of formats via Serde.
-pub fn export(idx: &Path, dat: &Path, format: ExportFormat, out: &Path) -> Result<()> {
-    let fdidx = File::open(idx)?;
-    let mut bufidx = BufReader::new(fdidx);
-    let idxes = read_indicies(&mut bufidx)?;
-    debug!("There are {} indicies.", idxes.len());
+#[cfg(test)]
+mod format_tests {
 
-    if idxes.len() == 0 {
-        return Err(Error::new(Cause::NoIndicies));
+    /// Formats should round-trip through their names; only `display` is for people
+    #[test]
+    fn names() -> Result<(), String> {
+        use super::*;
+        for name in &["display", "sexp", "json", "ndjson", "itunes"] {
+            let format = Format::try_from(*name).map_err(|err| format!("{}", err))?;
+            assert_eq!(format.to_string(), *name);
+            assert_eq!(format.supports_display(), *name == "display");
+            assert_eq!(format.supports_meta(), *name == "sexp" || *name == "json");
+        }
+        assert!(Format::try_from("xml").is_err());
+        Ok(())
     }
-    let nrecs = idxes[0].len();
-    debug!("Each index has {} records.", nrecs);
-
-    // Alright: if we've made it this far, we've parsed the index file. Now use the primary
-    // index to walk the data file.
-    let mut fddat = File::open(dat)?;
 
-    let mut buf: [u8; 8] = [0; 8];
-    fddat.read_exact(&mut buf)?;
-    if b"NDETABLE" != &buf {
-        return Err(Error::new(Cause::NoSig));
+    /// Formats should be recognized by their customary extensions
+    #[test]
+    fn extensions() -> Result<(), String> {
+        use super::*;
+        let guess = |x: &str| Format::from_extension(Path::new(x));
+        assert_eq!(guess("main.json"), Some(Format::Json));
+        assert_eq!(guess("main.JSONL"), Some(Format::Ndjson));
+        assert_eq!(guess("out.el"), Some(Format::Sexp));
+        assert_eq!(guess("iTunes Music Library.xml"), Some(Format::Itunes));
+        assert_eq!(guess("main.out"), None);
+        assert_eq!(guess("main"), None);
+        Ok(())
     }
+}
 
-    // The first record should list the columns in this table.
-    let at = idxes[0].off(0);
-    fddat.seek(SeekFrom::Start(at))?;
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                         dumping logic                                          //
+////////////////////////////////////////////////////////////////////////////////////////////////////
 
-    let mut cols: Vec<fields::ColumnField> = Vec::new();
-    let mut next_field_pos: u64 = at;
-    while next_field_pos != 0 {
-        let (id, ftype) = follow_redirects(&mut fddat)?;
-        if ftype != FieldType::Column {
-            return Err(Error::new(Cause::NonColumnField(ftype)));
-        }
-        let x = fields::ColumnField::new(&mut fddat, id as i32)?;
-        next_field_pos = x.next_field_pos();
-        cols.push(x);
-        if next_field_pos != 0 {
-            fddat.seek(SeekFrom::Start(next_field_pos))?;
+/// Read the names of a table's columns, keyed by column ID; this is best-effort, so if the
+/// column definitions can't be found, return an empty map
+fn column_names<R: Read + Seek>(
+    rdr: &mut R,
+    index: &NdeIndex,
+    opts: &ParseOptions,
+) -> HashMap<i32, String> {
+    let (cols_rec, _) = find_meta_records(rdr, index, opts);
+    match read_columns(rdr, index.off(cols_rec), opts) {
+        Ok(cols) => cols.iter().map(|col| (col.id(), col.name())).collect(),
+        Err(err) => {
+            debug!("Failed to read the column definitions: {}", err);
+            HashMap::new()
         }
     }
+}
 
-    debug!("There are {} columns.", cols.len());
+/// Choose an extension for a binary payload based on its magic number
+fn binary_extension(payload: &[u8]) -> &'static str {
+    fields::image_format(payload).unwrap_or("bin")
+}
+
+/// Write the payload of binary field `field` (from record `rec`) to a file of its own in `dir`,
+/// named for the record & column, & return a field referencing that file in its stead
+fn extract(
+    field: fields::UnsupportedNdeField,
+    dir: &Path,
+    rec: usize,
+    col_names: &HashMap<i32, String>,
+) -> fields::Result<Box<dyn NdeField>> {
+    let payload = field.binary_payload().unwrap_or(&[]);
+    // Column names come out of the data file, so keep them to characters safe in a file name
+    let col = match col_names.get(&field.id()) {
+        Some(name) => name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect(),
+        None => format!("col{}", field.id()),
+    };
+    let file = dir.join(format!("{}_{}.{}", rec, col, binary_extension(payload)));
+    std::fs::write(&file, payload)?;
+    debug!("Wrote {} bytes to {}.", payload.len(), file.display());
+    Ok(Box::new(field.into_extracted(file)))
+}
+
+#[cfg(test)]
+mod extract_tests {
+
+    /// Binary payloads should be written to files named for their record & column
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        assert_eq!(binary_extension(&[0xff, 0xd8, 0xff, 0xe0]), "jpg");
+        assert_eq!(binary_extension(b"\x89PNG\r\n\x1a\n\x00"), "png");
+        assert_eq!(binary_extension(b"BM\x36\x00"), "bmp");
+        assert_eq!(binary_extension(&[0x00]), "bin");
+
+        let bytes = builder::body(&[0x02, 0x00, 0xff, 0xd8, 0x00], 0, 0);
+        let field = fields::UnsupportedNdeField::new(
+            &mut bytes.as_slice(),
+            7,
+            FieldType::Binary,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let dir = std::env::temp_dir().join(format!("xnde-extract-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).map_err(|err| format!("{}", err))?;
+        let mut names: HashMap<i32, String> = HashMap::new();
+        names.insert(7, String::from("album art"));
+        extract(field, &dir, 3, &names).map_err(|err| format!("{}", err))?;
+        let file = dir.join("3_album_art.bin");
+        let payload = std::fs::read(&file).map_err(|err| format!("{}", err))?;
+        assert_eq!(payload, vec![0xff, 0xd8]);
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
+
+/// Options governing [`dump`]; the defaults dump every record, reading the data file in the
+/// usual way, failing on unknown field types & writing binary fields in-line
+///
+/// [`dump`]: fn.dump.html
+#[derive(Clone, Debug, Default)]
+pub struct DumpOptions {
+    mmap: bool,
+    skip_meta: bool,
+    max_records: Option<usize>,
+    tail: Option<usize>,
+    record: Option<usize>,
+    tolerate_unknown_types: bool,
+    extract_binary: Option<PathBuf>,
+    pretty_bytes: bool,
+    parse_opts: ParseOptions,
+}
+
+impl DumpOptions {
+    pub fn new() -> DumpOptions {
+        DumpOptions::default()
+    }
+    /// Memory-map the data file (if xnde was built with the `mmap` feature)
+    pub fn mmap(mut self, mmap: bool) -> DumpOptions {
+        self.mmap = mmap;
+        self
+    }
+    /// Skip the records defining the table's columns & indicies
+    pub fn skip_meta(mut self, skip_meta: bool) -> DumpOptions {
+        self.skip_meta = skip_meta;
+        self
+    }
+    /// Dump at most this many records (in primary index order)
+    pub fn max_records(mut self, max_records: Option<usize>) -> DumpOptions {
+        self.max_records = max_records;
+        self
+    }
+    /// Dump only the last this many records (in primary index order); since the index gives
+    /// every record's offset, the records before them aren't read at all
+    pub fn tail(mut self, tail: Option<usize>) -> DumpOptions {
+        self.tail = tail;
+        self
+    }
+    /// Dump only this record (by its number in the primary index, as reported by `find`), seeking
+    /// straight to it; this takes precedence over `max_records` & `tail`
+    pub fn record(mut self, record: Option<usize>) -> DumpOptions {
+        self.record = record;
+        self
+    }
+    /// On meeting a field of unknown type, look for the next plausible field header & carry on
+    pub fn tolerate_unknown_types(mut self, tolerate: bool) -> DumpOptions {
+        self.tolerate_unknown_types = tolerate;
+        self
+    }
+    /// Write the payloads of BINARY & BINARY32 fields to files in this directory (named
+    /// `<record>_<column>.<ext>`), & replace them in the output by their paths
+    pub fn extract_binary(mut self, dir: Option<PathBuf>) -> DumpOptions {
+        self.extract_binary = dir;
+        self
+    }
+    /// Follow the raw value of the `filesize` column with a human-readable rendering (`4.2 MiB`,
+    /// e.g.); this only affects the `display` format
+    pub fn pretty_bytes(mut self, pretty_bytes: bool) -> DumpOptions {
+        self.pretty_bytes = pretty_bytes;
+        self
+    }
+    /// Read the table according to these options
+    pub fn parse_options(mut self, parse_opts: ParseOptions) -> DumpOptions {
+        self.parse_opts = parse_opts;
+        self
+    }
+}
+
+/// Render a count of bytes in human-readable form, in binary units (`512 B`, `4.2 MiB`)
+pub fn pretty_bytes(n: i64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if n.unsigned_abs() < 1024 {
+        return format!("{} B", n);
+    }
+    let mut x = n as f64 / 1024.0;
+    let mut unit = 0;
+    while x.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        x /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", x, UNITS[unit])
+}
+
+#[cfg(test)]
+mod pretty_bytes_tests {
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        assert_eq!(pretty_bytes(0), "0 B");
+        assert_eq!(pretty_bytes(1023), "1023 B");
+        assert_eq!(pretty_bytes(1024), "1.0 KiB");
+        assert_eq!(pretty_bytes(4404019), "4.2 MiB");
+        assert_eq!(pretty_bytes(3 << 30), "3.0 GiB");
+        assert_eq!(pretty_bytes(i64::MAX), "8.0 EiB");
+        assert_eq!(pretty_bytes(-2048), "-2.0 KiB");
+        Ok(())
+    }
+}
+
+/// Work out which of `nrecs` records to dump: at most the first `head`, & of those, only the
+/// last `tail`
+fn dump_range(nrecs: usize, head: Option<usize>, tail: Option<usize>) -> std::ops::Range<usize> {
+    let end = head.map_or(nrecs, |head| head.min(nrecs));
+    let start = tail.map_or(0, |tail| end.saturating_sub(tail));
+    start..end
+}
+
+#[cfg(test)]
+mod dump_range_tests {
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        assert_eq!(dump_range(10, None, None), 0..10);
+        assert_eq!(dump_range(10, Some(3), None), 0..3);
+        assert_eq!(dump_range(10, Some(30), None), 0..10);
+        assert_eq!(dump_range(10, None, Some(3)), 7..10);
+        assert_eq!(dump_range(10, None, Some(30)), 0..10);
+        assert_eq!(dump_range(10, Some(5), Some(2)), 3..5);
+        assert_eq!(dump_range(0, None, Some(2)), 0..0);
+        Ok(())
+    }
+}
 
-    let col_map = new_column_map(cols.iter());
-    debug!("column map: {:#?}", col_map);
+// TODO(sp1ff): re-write to take readers; write unit tests
+/// Dump the contents of a Winamp Music Library to stdout
+///
+/// The fields themselves are written to stdout; status messages go through the logger. See
+/// [`DumpOptions`] for the ways in which this may be tailored.
+///
+/// [`DumpOptions`]: struct.DumpOptions.html
+pub fn dump(idx: &Path, dat: &Path, format: Format, opts: &DumpOptions) -> Result<()> {
+    let popts = &opts.parse_opts;
+    let (idxes, mut fddat) = open_table(idx, dat, opts.mmap, popts)?;
+    info!("There are {} indicies.", idxes.len());
+    let nrecs = idxes[0].len();
+    info!("Each index has {} records.", nrecs);
+
+    // Alright: if we've made it this far, we've parsed the index file. Now use the primary
+    // index to walk the data file.
 
-    // The second record should contain the indicies defined on this table; we're only making
-    // use of the primary, so skip this.
-    let mut trks: Vec<tracks::Track> = Vec::with_capacity(nrecs);
-    info!("Creating {} Tracks...", nrecs - 2);
-    for i in 2..nrecs {
+    let meta = if opts.skip_meta {
+        find_meta_records(&mut fddat, &idxes[0], popts).1
+    } else {
+        Vec::new()
+    };
+    let extract_binary = opts.extract_binary.as_deref();
+    if let Some(dir) = extract_binary {
+        std::fs::create_dir_all(dir)?;
+    }
+    let col_names = if extract_binary.is_some() || opts.pretty_bytes {
+        column_names(&mut fddat, &idxes[0], popts)
+    } else {
+        HashMap::new()
+    };
+    let range = match opts.record {
+        Some(record) if record >= nrecs => {
+            return Err(Error::new(Cause::NoSuchRecord {
+                record: record,
+                len: nrecs,
+            }))
+        }
+        Some(record) => record..record + 1,
+        None => dump_range(nrecs, opts.max_records, opts.tail),
+    };
+    if range.len() < nrecs {
+        info!(
+            "Dumping only {} of {} records, starting at record {}.",
+            range.len(),
+            nrecs,
+            range.start
+        );
+    }
+    for i in range {
+        if !is_track_record(&meta, i) {
+            debug!("Skipping record {}, which isn't a track.", i);
+            continue;
+        }
         let at = idxes[0].off(i);
+        debug!("Parsing record {} at {:#04x}.", i, at);
         fddat.seek(SeekFrom::Start(at))?;
 
         // we now walk the fields in record `i':
-        let mut rec: Vec<Box<dyn fields::NdeField>> = Vec::with_capacity(cols.len());
         let mut next_field_pos: u64 = at;
 
         while next_field_pos != 0 {
-            let (id, ftype) = follow_redirects(&mut fddat)?;
+            let (id, ftype) = match follow_redirects(&mut fddat, popts) {
+                Ok(x) => x,
+                Err(err) => match (opts.tolerate_unknown_types, err.cause()) {
+                    (true, Cause::UnknownFieldType { offset, byte }) => {
+                        log::warn!(
+                            "Unknown field type {} at {:#x} in record {}; resyncing.",
+                            byte,
+                            offset,
+                            i
+                        );
+                        match resync(&mut fddat, offset + 1, popts)? {
+                            Some(at) => {
+                                log::warn!("Resuming at the field header at {:#x}.", at);
+                                fddat.seek(SeekFrom::Start(at))?;
+                                next_field_pos = at;
+                                continue;
+                            }
+                            None => {
+                                log::warn!("No field header found; abandoning record {}.", i);
+                                break;
+                            }
+                        }
+                    }
+                    _ => return Err(err.at_record(i, at)),
+                },
+            };
+            let start = fddat.stream_position()?.saturating_sub(2);
             // field-specific data follows..
-            match field_factory(&mut fddat, id as i32, ftype) {
+            let field = match extract_binary {
+                Some(dir) if ftype.is_binary() => {
+                    fields::UnsupportedNdeField::new(&mut fddat, id as i32, ftype, popts)
+                        .map_err(|err| err.at(next_field_pos, ftype))
+                        .and_then(|x| extract(x, dir, i, &col_names))
+                }
+                _ => field_factory(&mut fddat, id as i32, ftype, next_field_pos, popts),
+            };
+            match field {
                 Ok(x) => {
+                    check_overrun(&mut fddat, start, x.next_field_pos(), popts)?;
+                    // Display x:
+                    match format {
+                        Format::Display => match x.value() {
+                            FieldValue::Int64(n)
+                                if opts.pretty_bytes
+                                    && col_names.get(&x.id()).map(String::as_str)
+                                        == Some("filesize") =>
+                            {
+                                println!("{} ({})", x, pretty_bytes(n))
+                            }
+                            _ => println!("{}", x),
+                        },
+                        #[cfg(feature = "serde")]
+                        Format::Sexp => println!("{}", serde_lexpr::to_string(&x)?),
+                        #[cfg(feature = "serde")]
+                        Format::Json | Format::Ndjson => {
+                            println!("{}", serde_json::to_string(&x)?)
+                        }
+                        #[cfg(not(feature = "serde"))]
+                        Format::Sexp | Format::Json | Format::Ndjson => {
+                            return Err(Error::new(Cause::NoSerde(format)))
+                        }
+                        Format::Itunes => {
+                            return Err(Error::new(Cause::BadFormat(format.to_string())))
+                        }
+                    }
                     next_field_pos = x.next_field_pos();
-                    rec.push(x);
                 }
                 Err(err) => {
-                    return Err(Error::from(err));
+                    return Err(Error::from(err).at_record(i, at));
                 }
             }
 
@@ -648,20 +1722,3046 @@ pub fn export(idx: &Path, dat: &Path, format: ExportFormat, out: &Path) -> Resul
                 fddat.seek(SeekFrom::Start(next_field_pos))?;
             }
         }
+    }
 
-        // Between `cols' & `rec', we have enough to create a Track
-        let t = Track::new(&col_map, rec.iter())?;
-        trks.push(t);
+    Ok(())
+}
+
+/// Dump the indicies in a Winamp Music Library index file to stdout; for each index, print each
+/// record number along with its offset into the data file & the mysterious second value
+pub fn dump_indices(idx: &Path, opts: &ParseOptions) -> Result<()> {
+    let mut rdr = open_index(idx)?;
+    let idxes = read_indicies(&mut rdr, opts)?;
+    info!("There are {} indicies.", idxes.len());
+
+    for index in idxes {
+        println!("Index {} has {} records:", index.id(), index.len());
+        for i in 0..index.len() {
+            println!(
+                "{}: offset {:#06x}, extra {}",
+                i,
+                index.off(i),
+                index.extra(i)
+            );
+        }
     }
-    info!("Creating {} Tracks...done.", nrecs - 2);
 
-    info!("Writing {}...", out.display());
-    let f = File::create(out)?;
-    match format {
-        ExportFormat::Sexp => serde_lexpr::to_writer(f, &trks)?,
-        ExportFormat::Json => serde_json::to_writer(f, &trks)?,
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                       field statistics                                         //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Tally the types of all the fields in the records at `offsets`, without parsing them
+///
+/// After following any redirects, we read just enough of each field's header to find the next
+/// field, so this works even for fields xnde can't parse. Return (type, count) pairs, most common
+/// type first.
+fn count_field_types<R: Read + Seek>(
+    rdr: &mut R,
+    offsets: impl Iterator<Item = u64>,
+    opts: &ParseOptions,
+) -> Result<Vec<(FieldType, usize)>> {
+    let mut counts: HashMap<FieldType, usize> = HashMap::new();
+    for at in offsets {
+        rdr.seek(SeekFrom::Start(at))?;
+        let mut next_field_pos: u64 = at;
+        while next_field_pos != 0 {
+            let (_, ftype) = follow_redirects(rdr, opts)?;
+            *counts.entry(ftype).or_insert(0) += 1;
+            // skip `max_size'; next up is the offset of the next field
+            let mut buf: [u8; 4] = [0; 4];
+            rdr.read_exact(&mut buf)?;
+            rdr.read_exact(&mut buf)?;
+            next_field_pos = opts.byte_order.u32(buf) as u64;
+            if next_field_pos != 0 {
+                rdr.seek(SeekFrom::Start(next_field_pos))?;
+            }
+        }
+    }
+
+    let mut counts: Vec<(FieldType, usize)> = counts.into_iter().collect();
+    counts.sort_by(|lhs, rhs| rhs.1.cmp(&lhs.1).then((lhs.0 as u8).cmp(&(rhs.0 as u8))));
+    Ok(counts)
+}
+
+/// Walk every record in a Winamp Music Library & tally the types of the fields therein; useful
+/// for seeing what's in your library that xnde doesn't yet handle. Return (type, count) pairs,
+/// most common type first.
+pub fn field_stats(idx: &Path, dat: &Path, opts: &ParseOptions) -> Result<Vec<(FieldType, usize)>> {
+    let (idxes, mut fddat) = open_table(idx, dat, false, opts)?;
+    let primary = &idxes[0];
+    count_field_types(&mut fddat, primary.offsets().iter().copied(), opts)
+}
+
+#[cfg(test)]
+mod field_stats_tests {
+
+    /// Count the fields in a single two-field record
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        // Offset zero marks the end of a chain of fields, so records follow the signature
+        let mut buf: Vec<u8> = b"NDETABLE".to_vec();
+        // @ 0x08: an integer field, then a GUID field, which we can't parse
+        buf.extend_from_slice(&builder::raw_record(
+            8,
+            &[
+                builder::integer(0, FieldType::Integer, 11),
+                (1, FieldType::Guid, vec![0xab; 16]),
+            ],
+        ));
+
+        let mut cur = std::io::Cursor::new(buf);
+        let counts = count_field_types(&mut cur, vec![8u64].into_iter(), &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        assert_eq!(counts, vec![(FieldType::Integer, 1), (FieldType::Guid, 1)]);
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                          verify logic                                          //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// An offset present in one index but not another
+#[derive(Debug, PartialEq)]
+pub struct Discrepancy {
+    /// The offending offset
+    pub offset: u64,
+    /// The ID of the index in which `offset` appears
+    pub present: i32,
+    /// The ID of the index from which `offset` is missing
+    pub missing: i32,
+}
+
+impl std::fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "offset {:#06x} appears in index {} but not in index {}",
+            self.offset, self.present, self.missing
+        )
+    }
+}
+
+/// Compare the set of offsets in each index to those in the primary (i.e. the first); each should
+/// reference exactly the same records, just in a different order
+pub fn check_indices(idxes: &[NdeIndex]) -> Vec<Discrepancy> {
+    let mut discrepancies: Vec<Discrepancy> = Vec::new();
+    let primary = match idxes.first() {
+        Some(index) => index,
+        None => {
+            return discrepancies;
+        }
+    };
+    let mut lhs: Vec<u64> = primary.offsets().to_vec();
+    lhs.sort_unstable();
+
+    for index in &idxes[1..] {
+        let mut rhs: Vec<u64> = index.offsets().to_vec();
+        rhs.sort_unstable();
+        // walk both sorted vectors in tandem
+        let (mut i, mut j) = (0, 0);
+        while i < lhs.len() || j < rhs.len() {
+            if j == rhs.len() || (i < lhs.len() && lhs[i] < rhs[j]) {
+                discrepancies.push(Discrepancy {
+                    offset: lhs[i],
+                    present: primary.id(),
+                    missing: index.id(),
+                });
+                i += 1;
+            } else if i == lhs.len() || rhs[j] < lhs[i] {
+                discrepancies.push(Discrepancy {
+                    offset: rhs[j],
+                    present: index.id(),
+                    missing: primary.id(),
+                });
+                j += 1;
+            } else {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+
+    discrepancies
+}
+
+/// Read all the indicies in a Winamp Music Library index file & cross-check them, printing any
+/// discrepancies to stdout. Return the number of discrepancies found.
+pub fn verify(idx: &Path, opts: &ParseOptions) -> Result<usize> {
+    let mut rdr = open_index(idx)?;
+    let idxes = read_indicies(&mut rdr, opts)?;
+    info!("There are {} indicies.", idxes.len());
+
+    if idxes.is_empty() {
+        return Err(Error::new(Cause::NoIndicies));
+    }
+
+    let discrepancies = check_indices(&idxes);
+    for d in &discrepancies {
+        println!("{}", d);
+    }
+    Ok(discrepancies.len())
+}
+
+#[cfg(test)]
+mod verify_tests {
+
+    /// Indicies in different orders agree; those with different offsets don't
+    #[test]
+    fn smoke() {
+        use super::*;
+        let idxes = vec![
+            NdeIndex {
+                id: 255,
+                offsets: vec![8, 0x20, 0x40],
+
+                extras: vec![0, 0, 0],
+            },
+            NdeIndex {
+                id: 0,
+                offsets: vec![0x40, 8, 0x20],
+
+                extras: vec![0, 0, 0],
+            },
+            NdeIndex {
+                id: 1,
+                offsets: vec![0x40, 8, 0x30],
+
+                extras: vec![0, 0, 0],
+            },
+        ];
+        assert_eq!(
+            check_indices(&idxes),
+            vec![
+                Discrepancy {
+                    offset: 0x20,
+                    present: 255,
+                    missing: 1
+                },
+                Discrepancy {
+                    offset: 0x30,
+                    present: 1,
+                    missing: 255
+                },
+            ]
+        );
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                        streaming tracks                                        //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Verify that every record in `index` lies within a data file of `len` bytes
+fn check_offsets(index: &NdeIndex, len: u64) -> Result<()> {
+    for i in 0..index.len() {
+        if index.off(i) >= len {
+            return Err(Error::new(Cause::OffsetOutOfRange {
+                record: i,
+                offset: index.off(i),
+                len: len,
+            }));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod check_offsets_tests {
+
+    /// An offset past the end of the data file should be caught
+    #[test]
+    fn out_of_range() -> Result<(), String> {
+        use super::*;
+        let index = NdeIndex {
+            id: 255,
+            offsets: vec![8, 0x20],
+
+            extras: vec![0, 0],
+        };
+        if let Err(err) = check_offsets(&index, 0x21) {
+            return Err(format!("{}", err));
+        }
+        match check_offsets(&index, 0x20) {
+            Ok(_) => Err(String::from("check should have failed")),
+            Err(err) => match err.cause {
+                Cause::OffsetOutOfRange {
+                    record,
+                    offset,
+                    len,
+                } => {
+                    assert_eq!(record, 1);
+                    assert_eq!(offset, 0x20);
+                    assert_eq!(len, 0x20);
+                    Ok(())
+                }
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
+    }
+}
+
+/// Open an NDE table: read all the indicies out of `idx`, verify the signature on `dat` & check
+/// that the primary index doesn't point past the end of `dat`
+fn open_table(
+    idx: &Path,
+    dat: &Path,
+    mmap: bool,
+    opts: &ParseOptions,
+) -> Result<(Vec<NdeIndex>, Box<dyn ReadSeek>)> {
+    if is_stdin(idx) && is_stdin(dat) {
+        return Err(Error::new(Cause::StdinTwice));
+    }
+    let mut rdr = open_index(idx)?;
+    let idxes = read_indicies(&mut rdr, opts)?;
+    let mut fddat = open_data(dat, mmap, opts)?;
+    check_table(&idxes, &mut fddat)?;
+    Ok((idxes, fddat))
+}
+
+/// Check that `idxes` is non-empty, that `fddat` bears the NDE table signature & that the primary
+/// index doesn't point past its end; leave `fddat` positioned just past the signature
+fn check_table<R: Read + Seek>(idxes: &[NdeIndex], fddat: &mut R) -> Result<()> {
+    debug!("There are {} indicies.", idxes.len());
+    if idxes.is_empty() {
+        return Err(Error::new(Cause::NoIndicies));
+    }
+    debug!("Each index has {} records.", idxes[0].len());
+
+    let mut buf: [u8; 8] = [0; 8];
+    fddat.read_exact(&mut buf)?;
+    if b"NDETABLE" != &buf {
+        return Err(Error::new(Cause::NoSig));
     }
-    info!("Writing {}...done.", out.display());
+
+    // Catch index & data files that don't belong together before we get a confusing partial parse
+    let len = fddat.seek(SeekFrom::End(0))?;
+    check_offsets(&idxes[0], len)?;
+    fddat.seek(SeekFrom::Start(8))?;
 
     Ok(())
 }
+
+/// Anything we can both read & seek; lets us choose at runtime between reading a file directly,
+/// reading it through a memory map, or reading a decompressed copy
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// Default size, in bytes, of the buffer through which we read data files
+pub const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// A buffered reader that can seek without discarding its buffer
+///
+/// `BufReader::seek` always throws away the buffer, which defeats the purpose when (as here) the
+/// parser seeks to every field, even one that immediately follows the field just read. This
+/// tracks the stream position so that absolute seeks can be made relative to it, & so land in
+/// the buffer if they can.
+pub struct BufSeeker<R: Read + Seek> {
+    inner: BufReader<R>,
+    pos: u64,
+}
+
+impl<R: Read + Seek> BufSeeker<R> {
+    /// Wrap `inner` (which is assumed to be positioned at its start) in a buffer of `capacity`
+    /// bytes
+    pub fn with_capacity(capacity: usize, inner: R) -> BufSeeker<R> {
+        BufSeeker {
+            inner: BufReader::with_capacity(capacity, inner),
+            pos: 0,
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for BufSeeker<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for BufSeeker<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        // Offsets come from the data file, so guard against their overflowing an i64
+        let target = match pos {
+            SeekFrom::Start(n) => i64::try_from(n).ok(),
+            SeekFrom::Current(n) => i64::try_from(self.pos).ok().and_then(|x| x.checked_add(n)),
+            SeekFrom::End(_) => {
+                self.pos = self.inner.seek(pos)?;
+                return Ok(self.pos);
+            }
+        };
+        let target = match target {
+            Some(target) if target >= 0 => target,
+            Some(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "attempt to seek before the start of the stream",
+                ))
+            }
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "seek offset overflows",
+                ))
+            }
+        };
+        self.inner.seek_relative(target - self.pos as i64)?;
+        self.pos = target as u64;
+        Ok(self.pos)
+    }
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        Ok(self.pos)
+    }
+}
+
+/// Wrap a data file in a buffer of `size` bytes (or not at all, if that's zero)
+fn buffer_data(fddat: File, size: usize) -> Box<dyn ReadSeek> {
+    match size {
+        0 => Box::new(fddat),
+        n => Box::new(BufSeeker::with_capacity(n, fddat)),
+    }
+}
+
+#[cfg(test)]
+mod buf_seeker_tests {
+    use std::io::{Read, Seek, SeekFrom};
+
+    /// Seeks within & beyond the buffer should land where they're supposed to
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut rdr = BufSeeker::with_capacity(16, std::io::Cursor::new(bytes));
+        let mut buf: [u8; 4] = [0; 4];
+        let mut check =
+            |rdr: &mut BufSeeker<_>, pos: SeekFrom, exp: u8| -> std::result::Result<(), String> {
+                let at = rdr.seek(pos).map_err(|err| format!("{}", err))?;
+                rdr.read_exact(&mut buf).map_err(|err| format!("{}", err))?;
+                assert_eq!(at, exp as u64);
+                assert_eq!(buf, [exp, exp + 1, exp + 2, exp + 3]);
+                Ok(())
+            };
+        check(&mut rdr, SeekFrom::Start(0), 0)?;
+        check(&mut rdr, SeekFrom::Start(10), 10)?;
+        check(&mut rdr, SeekFrom::Current(-6), 8)?;
+        check(&mut rdr, SeekFrom::Start(200), 200)?;
+        check(&mut rdr, SeekFrom::End(-4), 252)?;
+        check(&mut rdr, SeekFrom::Start(4), 4)?;
+        assert!(rdr.seek(SeekFrom::Current(-100)).is_err());
+        // Offsets too large for an i64 should be refused, rather than wrapping
+        assert!(rdr.seek(SeekFrom::Start(u64::MAX)).is_err());
+        assert!(rdr.seek(SeekFrom::Current(i64::MAX)).is_err());
+        Ok(())
+    }
+
+    /// Counts the reads & seeks made on the underlying stream; each would be a syscall, were it a
+    /// `File`
+    struct Counter<R> {
+        inner: R,
+        calls: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl<R: Read> Read for Counter<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.calls.set(self.calls.get() + 1);
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Seek> Seek for Counter<R> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.calls.set(self.calls.get() + 1);
+            self.inner.seek(pos)
+        }
+    }
+
+    /// Reading a table through the buffer should touch the underlying stream far less often than
+    /// reading it directly
+    #[test]
+    fn fewer_calls() -> Result<(), String> {
+        use super::*;
+        use crate::fields::FieldValue;
+        let mut records = vec![vec![
+            (
+                0,
+                FieldValue::Column((FieldType::Filename as i32, String::from("filename"))),
+            ),
+            (
+                1,
+                FieldValue::Column((FieldType::String as i32, String::from("artist"))),
+            ),
+            (
+                2,
+                FieldValue::Column((FieldType::Integer as i32, String::from("year"))),
+            ),
+        ]];
+        for i in 0..100 {
+            records.push(vec![
+                (
+                    0,
+                    FieldValue::Filename(std::path::PathBuf::from(format!("{}.mp3", i))),
+                ),
+                (1, FieldValue::String(String::from("ABBA"))),
+                (2, FieldValue::Integer(1974)),
+            ]);
+        }
+        let (idx, dat) = builder::table(&records);
+
+        let count = |buffered: bool| -> std::result::Result<usize, String> {
+            let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+            let inner = Counter {
+                inner: std::io::Cursor::new(dat.clone()),
+                calls: calls.clone(),
+            };
+            let rdr: Box<dyn ReadSeek> = if buffered {
+                Box::new(BufSeeker::with_capacity(DEFAULT_BUFFER_SIZE, inner))
+            } else {
+                Box::new(inner)
+            };
+            let mut idxes = read_indicies(
+                &mut std::io::Cursor::new(idx.clone()),
+                &ParseOptions::default(),
+            )
+            .map_err(|err| format!("{}", err))?;
+            let tracks = Tracks::new(rdr, idxes.remove(0), &ParseOptions::default())
+                .map_err(|err| format!("{}", err))?;
+            assert_eq!(tracks.count(), 100);
+            Ok(calls.get())
+        };
+        let direct = count(false)?;
+        let buffered = count(true)?;
+        assert!(
+            buffered * 20 < direct,
+            "{} calls buffered vs {} direct",
+            buffered,
+            direct
+        );
+        Ok(())
+    }
+}
+
+/// Return true if the file at `path` (open on `fd`) is gzip-compressed, judging by either its
+/// extension or its first two bytes; `fd` is left positioned at the start of the file
+fn is_gzipped(path: &Path, fd: &mut File) -> Result<bool> {
+    if path.extension().map_or(false, |ext| ext == "gz") {
+        return Ok(true);
+    }
+    let mut magic: [u8; 2] = [0; 2];
+    let gzipped = match fd.read_exact(&mut magic) {
+        Ok(_) => magic == [0x1f, 0x8b],
+        Err(err) => {
+            if err.kind() == std::io::ErrorKind::UnexpectedEof {
+                false
+            } else {
+                return Err(Error::from(err));
+            }
+        }
+    };
+    fd.seek(SeekFrom::Start(0))?;
+    Ok(gzipped)
+}
+
+/// Decompress a gzip'd file into memory; `GzDecoder` can't seek, so we need the whole thing
+#[cfg(feature = "gzip")]
+fn decompress(_path: &Path, fd: File) -> Result<Box<dyn ReadSeek>> {
+    let mut buf: Vec<u8> = Vec::new();
+    flate2::read::GzDecoder::new(fd).read_to_end(&mut buf)?;
+    Ok(Box::new(std::io::Cursor::new(buf)))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress(path: &Path, _fd: File) -> Result<Box<dyn ReadSeek>> {
+    Err(Error::new(Cause::NoGzip(format!("{}", path.display()))))
+}
+
+/// Return true if `path` is "-", by convention naming stdin
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Read all of stdin into memory
+///
+/// The parser needs to seek, which stdin (a pipe, typically) can't do, so there's no alternative
+/// to buffering it in its entirety. Bear in mind that this means the entire data file will be held
+/// in memory; for a large library that could be hundreds of megabytes.
+fn read_stdin() -> Result<Box<dyn ReadSeek>> {
+    let mut buf: Vec<u8> = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut buf)?;
+    debug!("Read {} bytes from stdin.", buf.len());
+    Ok(Box::new(std::io::Cursor::new(buf)))
+}
+
+/// Open an index file for reading, transparently decompressing it if need be; an `idx` of "-"
+/// will be read from stdin
+fn open_index(idx: &Path) -> Result<Box<dyn ReadSeek>> {
+    if is_stdin(idx) {
+        return read_stdin();
+    }
+    let mut fdidx = File::open(idx)?;
+    if is_gzipped(idx, &mut fdidx)? {
+        return decompress(idx, fdidx);
+    }
+    Ok(Box::new(BufReader::new(fdidx)))
+}
+
+/// Open a data file for reading, transparently decompressing it if need be, & otherwise
+/// memory-mapping it if `mmap` is true or buffering it as `opts` direct if not; a `dat` of "-"
+/// will be read from stdin
+fn open_data(dat: &Path, mmap: bool, opts: &ParseOptions) -> Result<Box<dyn ReadSeek>> {
+    if is_stdin(dat) {
+        return read_stdin();
+    }
+    let mut fddat = File::open(dat)?;
+    if is_gzipped(dat, &mut fddat)? {
+        return decompress(dat, fddat);
+    }
+    if mmap {
+        map_data(fddat, opts)
+    } else {
+        Ok(buffer_data(fddat, opts.buffer_size))
+    }
+}
+
+/// Map a data file into memory, sparing us a syscall per seek
+#[cfg(feature = "mmap")]
+fn map_data(fddat: File, _opts: &ParseOptions) -> Result<Box<dyn ReadSeek>> {
+    // Safety: the map is only unsound if the file is modified out from under us; we're reading a
+    // database that Winamp presumably isn't running against.
+    let map = unsafe { memmap2::Mmap::map(&fddat)? };
+    Ok(Box::new(std::io::Cursor::new(map)))
+}
+
+#[cfg(not(feature = "mmap"))]
+fn map_data(fddat: File, opts: &ParseOptions) -> Result<Box<dyn ReadSeek>> {
+    log::warn!("xnde was built without the `mmap' feature; reading the data file as usual.");
+    Ok(buffer_data(fddat, opts.buffer_size))
+}
+
+/// Having just parsed a field whose header began at `start`, check that we haven't read into the
+/// next field, at `next`
+///
+/// A field's `max_size` may exceed the space it actually takes up, but a field that claims a
+/// size that would swallow the field following it is corrupt. This check is only made if asked
+/// for (via [`ParseOptions::check_overruns`]), and only if the next field follows this one in the
+/// file.
+///
+/// [`ParseOptions::check_overruns`]: struct.ParseOptions.html#method.check_overruns
+fn check_overrun<R: Seek>(rdr: &mut R, start: u64, next: u64, opts: &ParseOptions) -> Result<()> {
+    if !opts.check_overruns || next <= start {
+        return Ok(());
+    }
+    let end = rdr.stream_position()?;
+    if end > next {
+        return Err(Error::new(Cause::FieldOverrun {
+            offset: start,
+            end: end,
+            next: next,
+        }));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod overrun_tests {
+
+    /// A string claiming more bytes than lie between it & the next field should be caught
+    #[test]
+    fn overrun() -> Result<(), String> {
+        use super::*;
+        let mut buf: Vec<u8> = b"NDETABLE".to_vec();
+        buf.extend_from_slice(&builder::raw_record(
+            8,
+            &[builder::string(0, FieldType::String, "abcde")],
+        ));
+        // Give the field a max size of 7 & a next of 0x18 (i.e. just after a two-byte string),
+        // while the string still claims to be five bytes long
+        buf[10..14].copy_from_slice(&7u32.to_le_bytes());
+        buf[14..18].copy_from_slice(&0x18u32.to_le_bytes());
+
+        let opts = ParseOptions::new().check_overruns(true);
+        match read_record(&mut std::io::Cursor::new(buf), 8, &opts) {
+            Ok(_) => Err(String::from("read should have failed")),
+            Err(err) => match err.cause {
+                Cause::FieldOverrun { offset, end, next } => {
+                    assert_eq!(offset, 8);
+                    assert_eq!(end, 0x1d);
+                    assert_eq!(next, 0x18);
+                    Ok(())
+                }
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
+    }
+}
+
+/// Number of bytes either side of a record's indexed offset that we'll search for its true start
+const RECORD_RESYNC_WINDOW: u64 = 64;
+
+/// Find where the record that the index places at `at` actually begins: `at` itself, if there's
+/// a plausible header there for the first field in a record (one with no previous field), else
+/// the nearest such header within [`RECORD_RESYNC_WINDOW`] bytes either side; failing that, `at`
+/// (& let the parse fail as it will)
+fn realign<R: Read + Seek>(rdr: &mut R, at: u64, opts: &ParseOptions) -> Result<u64> {
+    let len = rdr.seek(SeekFrom::End(0))?;
+    // Don't search back into the table's signature
+    let lo = std::cmp::max(8, at.saturating_sub(RECORD_RESYNC_WINDOW));
+    let hi = std::cmp::min(len, at.saturating_add(RECORD_RESYNC_WINDOW + 14));
+    if lo >= hi {
+        return Ok(at);
+    }
+    rdr.seek(SeekFrom::Start(lo))?;
+    let mut buf: Vec<u8> = vec![0; (hi - lo) as usize];
+    rdr.read_exact(&mut buf)?;
+
+    let starts_record = |off: u64| -> bool {
+        if off < lo || off + 14 > hi {
+            return false;
+        }
+        let hdr = &buf[(off - lo) as usize..(off - lo) as usize + 14];
+        plausible_header(hdr, off, len, opts) && hdr[10..14] == [0; 4]
+    };
+    for d in 0..=RECORD_RESYNC_WINDOW {
+        let candidates = [at.checked_sub(d), at.checked_add(d)];
+        if let Some(off) = candidates
+            .iter()
+            .flatten()
+            .copied()
+            .find(|x| starts_record(*x))
+        {
+            if off != at {
+                log::warn!(
+                    "The record indexed at {:#x} appears to begin at {:#x}; reading it from there.",
+                    at,
+                    off
+                );
+            }
+            return Ok(off);
+        }
+    }
+    Ok(at)
+}
+
+#[cfg(test)]
+mod realign_tests {
+
+    /// An offset a few bytes off either way should be pulled back to the start of its record
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        use crate::fields::FieldValue;
+        let (_, dat) = builder::table(&[
+            vec![(
+                0,
+                FieldValue::Column((FieldType::Filename as i32, String::from("filename"))),
+            )],
+            vec![
+                (0, FieldValue::Filename(std::path::PathBuf::from("a.mp3"))),
+                (1, FieldValue::Integer(1997)),
+            ],
+        ]);
+        // The second record follows the first, which is a single, 14 + 11 byte, column field
+        let second = 8 + 25;
+        let mut rdr = std::io::Cursor::new(dat);
+        for at in [second, second - 3, second + 5, second + 20] {
+            assert_eq!(
+                realign(&mut rdr, at, &ParseOptions::default())
+                    .map_err(|err| format!("{}", err))?,
+                second
+            );
+        }
+        // Nothing plausible within reach: leave the offset be
+        assert_eq!(
+            realign(&mut rdr, 1000, &ParseOptions::default()).map_err(|err| format!("{}", err))?,
+            1000
+        );
+        Ok(())
+    }
+}
+
+/// Read the fields making up the record at offset `at` in a data file, in the order in which
+/// they're chained together (each field's `next` offset leading to the one after it)
+fn read_record<R: Read + Seek>(
+    rdr: &mut R,
+    at: u64,
+    opts: &ParseOptions,
+) -> Result<Vec<Box<dyn NdeField>>> {
+    let at = if opts.resync_records {
+        realign(rdr, at, opts)?
+    } else {
+        at
+    };
+    rdr.seek(SeekFrom::Start(at))?;
+
+    let mut rec: Vec<Box<dyn NdeField>> = Vec::new();
+    // `next` offsets come straight from the file; don't let a cycle among them run us out of memory
+    let mut seen: HashSet<u64> = HashSet::new();
+    let mut next_field_pos: u64 = at;
+    while next_field_pos != 0 {
+        if !seen.insert(next_field_pos) {
+            return Err(Error::new(Cause::FieldCycle {
+                record: at,
+                offset: next_field_pos,
+            }));
+        }
+        let (id, ftype) = follow_redirects(rdr, opts)?;
+        let start = rdr.stream_position()?.saturating_sub(2);
+        // field-specific data follows..
+        let x = field_factory(rdr, id as i32, ftype, next_field_pos, opts)?;
+        check_overrun(rdr, start, x.next_field_pos(), opts)?;
+        next_field_pos = x.next_field_pos();
+        rec.push(x);
+
+        if next_field_pos != 0 {
+            rdr.seek(SeekFrom::Start(next_field_pos))?;
+        }
+    }
+
+    Ok(rec)
+}
+
+#[cfg(test)]
+mod record_tests {
+
+    /// Each field should account for exactly the bytes between it & the next
+    #[test]
+    fn serialized_len() -> Result<(), String> {
+        use super::*;
+        use fields::FieldValue;
+
+        let mut dat: Vec<u8> = b"NDETABLE".to_vec();
+        dat.extend_from_slice(&builder::record(
+            8,
+            &[
+                (0, FieldValue::Filename(std::path::PathBuf::from("a.mp3"))),
+                (1, FieldValue::String(String::from("ABBA"))),
+                (2, FieldValue::Integer(1974)),
+                (3, FieldValue::Int64(2654208)),
+            ],
+        ));
+        let len = dat.len() as u64;
+        let rec = read_record(&mut std::io::Cursor::new(dat), 8, &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        let mut at: u64 = 8;
+        for field in &rec {
+            at += field.serialized_len() as u64;
+            if field.next_field_pos() != 0 {
+                assert_eq!(field.next_field_pos(), at);
+            }
+        }
+        assert_eq!(at, len);
+        Ok(())
+    }
+
+    /// Fields should come back in the order in which they're chained, which needn't be that of
+    /// their IDs, nor of their offsets in the file
+    #[test]
+    fn chain_order() -> Result<(), String> {
+        use super::*;
+        use fields::FieldValue;
+
+        // Lay out three fields, IDs 2, 0 & 1, then chain them last-to-first: the record starts
+        // with the field at the highest offset
+        let values = [
+            (2, FieldValue::Integer(1974)),
+            (0, FieldValue::String(String::from("ABBA"))),
+            (1, FieldValue::Int64(2654208)),
+        ];
+        let mut offsets: Vec<u32> = Vec::new();
+        let mut at: u32 = 8;
+        for (_, value) in &values {
+            offsets.push(at);
+            at += (fields::FIELD_HEADER_SIZE + builder::field_data(value).1.len()) as u32;
+        }
+        let mut dat: Vec<u8> = b"NDETABLE".to_vec();
+        for (i, (id, value)) in values.iter().enumerate() {
+            let next = if i == 0 { 0 } else { offsets[i - 1] };
+            let prev = offsets.get(i + 1).copied().unwrap_or(0);
+            dat.push(*id);
+            dat.push(builder::field_data(value).0 as u8);
+            dat.extend_from_slice(&builder::field_body(value, next, prev));
+        }
+
+        let rec = read_record(
+            &mut std::io::Cursor::new(dat),
+            offsets[2] as u64,
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(
+            rec.iter().map(|f| f.id()).collect::<Vec<i32>>(),
+            vec![1, 0, 2]
+        );
+        Ok(())
+    }
+
+    /// A record whose fields' `next` offsets form a cycle should be rejected, rather than read
+    /// until we run out of memory
+    #[test]
+    fn cycle() -> Result<(), String> {
+        use super::*;
+        use fields::FieldValue;
+
+        let mut dat: Vec<u8> = b"NDETABLE".to_vec();
+        dat.extend_from_slice(&builder::record(
+            8,
+            &[
+                (0, FieldValue::Filename(std::path::PathBuf::from("a.mp3"))),
+                (1, FieldValue::String(String::from("ABBA"))),
+            ],
+        ));
+        // Point the second field's `next` back at the first
+        let second = u32::from_le_bytes([dat[14], dat[15], dat[16], dat[17]]) as usize;
+        dat[second + 6..second + 10].copy_from_slice(&8u32.to_le_bytes());
+        match read_record(&mut std::io::Cursor::new(dat), 8, &ParseOptions::default()) {
+            Ok(_) => Err(String::from("read_record should have failed")),
+            Err(err) => match err.cause() {
+                Cause::FieldCycle { record, offset } => {
+                    assert_eq!(*record, 8);
+                    assert_eq!(*offset, 8);
+                    Ok(())
+                }
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
+    }
+}
+
+/// Default limit on the number of columns we'll read from a table's column definitions
+pub const DEFAULT_MAX_COLUMNS: usize = 256;
+
+/// Read the column definitions making up the record at offset `at` in a data file
+fn read_columns<R: Read + Seek>(
+    rdr: &mut R,
+    at: u64,
+    opts: &ParseOptions,
+) -> Result<Vec<fields::ColumnField>> {
+    rdr.seek(SeekFrom::Start(at))?;
+
+    let limit = opts.max_columns;
+    let mut cols: Vec<fields::ColumnField> = Vec::new();
+    let mut next_field_pos: u64 = at;
+    while next_field_pos != 0 {
+        if cols.len() >= limit {
+            return Err(Error::new(Cause::TooManyColumns {
+                offset: at,
+                limit: limit,
+            }));
+        }
+        let (id, ftype) = follow_redirects(rdr, opts)?;
+        // having followed any redirects, this is where the field header actually begins
+        let start = rdr.stream_position()?.saturating_sub(2);
+        if ftype != FieldType::Column {
+            return Err(Error::new(Cause::NonColumnField {
+                ftype: ftype,
+                offset: start,
+            }));
+        }
+        let x = fields::ColumnField::new(rdr, id as i32, opts)
+            .map_err(|err| err.at(next_field_pos, FieldType::Column))?;
+        check_overrun(rdr, start, x.next_field_pos(), opts)?;
+        next_field_pos = x.next_field_pos();
+        cols.push(x);
+        if next_field_pos != 0 {
+            rdr.seek(SeekFrom::Start(next_field_pos))?;
+        }
+    }
+
+    debug!("There are {} columns.", cols.len());
+    Ok(cols)
+}
+
+#[cfg(test)]
+mod column_limit_tests {
+
+    /// Column definitions that point back at one another should be cut off rather than read
+    /// forever
+    #[test]
+    fn cycle() -> Result<(), String> {
+        use super::*;
+        use crate::fields::FieldValue;
+        let mut dat: Vec<u8> = b"NDETABLE".to_vec();
+        dat.extend_from_slice(&builder::record(
+            8,
+            &[
+                (
+                    0,
+                    FieldValue::Column((FieldType::Filename as i32, String::from("filename"))),
+                ),
+                (
+                    1,
+                    FieldValue::Column((FieldType::String as i32, String::from("artist"))),
+                ),
+            ],
+        ));
+        // Point the second column's `next` back at the first
+        let second = u32::from_le_bytes([dat[14], dat[15], dat[16], dat[17]]) as usize;
+        dat[second + 6..second + 10].copy_from_slice(&8u32.to_le_bytes());
+        let mut rdr = std::io::Cursor::new(dat);
+        match read_columns(&mut rdr, 8, &ParseOptions::default()) {
+            Ok(_) => Err(String::from("read_columns should have failed")),
+            Err(err) => match err.cause() {
+                Cause::TooManyColumns { offset, limit } => {
+                    assert_eq!(*offset, 8);
+                    assert_eq!(*limit, DEFAULT_MAX_COLUMNS);
+                    Ok(())
+                }
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
+    }
+}
+
+/// Read the record at `at` defining a table's indicies
+fn read_index_fields<R: Read + Seek>(
+    rdr: &mut R,
+    at: u64,
+    opts: &ParseOptions,
+) -> Result<Vec<IndexField>> {
+    rdr.seek(SeekFrom::Start(at))?;
+
+    let mut idxes: Vec<IndexField> = Vec::new();
+    let mut seen: HashSet<u64> = HashSet::new();
+    let mut next_field_pos: u64 = at;
+    while next_field_pos != 0 {
+        if !seen.insert(next_field_pos) {
+            return Err(Error::new(Cause::FieldCycle {
+                record: at,
+                offset: next_field_pos,
+            }));
+        }
+        let (id, ftype) = follow_redirects(rdr, opts)?;
+        // having followed any redirects, this is where the field header actually begins
+        let start = rdr.stream_position()?.saturating_sub(2);
+        if ftype != FieldType::Index {
+            return Err(Error::new(Cause::NonIndexField {
+                ftype: ftype,
+                offset: start,
+            }));
+        }
+        let x = IndexField::new(rdr, id as i32, opts)
+            .map_err(|err| err.at(next_field_pos, FieldType::Index))?;
+        check_overrun(rdr, start, x.next_field_pos(), opts)?;
+        next_field_pos = x.next_field_pos();
+        idxes.push(x);
+        if next_field_pos != 0 {
+            rdr.seek(SeekFrom::Start(next_field_pos))?;
+        }
+    }
+
+    debug!("There are {} index definitions.", idxes.len());
+    Ok(idxes)
+}
+
+/// The number of records at the start of a table that we'll scan looking for column & index
+/// definitions
+const META_SCAN_LIMIT: usize = 8;
+
+/// Return true if `rec` is non-empty & consists entirely of fields of type `ftype`
+fn all_of_type(rec: &[Box<dyn NdeField>], ftype: FieldType) -> bool {
+    !rec.is_empty() && rec.iter().all(|f| f.type_id() == Some(ftype))
+}
+
+/// Scan the first few records in `index` for the one defining the table's columns, along with
+/// those defining its indicies
+///
+/// In my database, the first record lists the columns & the second the indicies, but Winamp
+/// versions differ. Look for a record made up entirely of column fields; if we can't find one,
+/// fall back to assuming the first two records are the columns & indicies, respectively. Return
+/// the number of the record containing the columns, along with the numbers of all records holding
+/// such metadata (i.e. the records that are *not* tracks).
+fn find_meta_records<R: Read + Seek>(
+    rdr: &mut R,
+    index: &NdeIndex,
+    opts: &ParseOptions,
+) -> (usize, Vec<usize>) {
+    let mut cols: Option<usize> = None;
+    let mut meta: Vec<usize> = Vec::new();
+    for i in 0..std::cmp::min(META_SCAN_LIMIT, index.len()) {
+        match read_record(rdr, index.off(i), opts) {
+            Ok(rec) => {
+                if all_of_type(&rec, FieldType::Column) {
+                    if cols.is_none() {
+                        cols = Some(i);
+                    }
+                    meta.push(i);
+                } else if all_of_type(&rec, FieldType::Index) {
+                    meta.push(i);
+                }
+            }
+            Err(err) => {
+                debug!(
+                    "Failed to parse record {} while scanning for columns: {}",
+                    i, err
+                );
+            }
+        }
+    }
+
+    match cols {
+        Some(i) => {
+            info!("Found the column definitions in record {}.", i);
+            (i, meta)
+        }
+        None => {
+            info!("Couldn't find the column definitions; assuming they're in record 0.");
+            (0, vec![0, 1])
+        }
+    }
+}
+
+/// Return true if record `i` describes a track, rather than the table's columns or indicies;
+/// `meta` is the list of metadata records returned by `find_meta_records`
+fn is_track_record(meta: &[usize], i: usize) -> bool {
+    !meta.contains(&i)
+}
+
+#[cfg(test)]
+mod meta_tests {
+
+    /// The column definitions needn't be in the first record
+    #[test]
+    fn columns_not_first() -> Result<(), String> {
+        use super::*;
+        use crate::fields::FieldValue;
+        // record 0: a lone integer field; record 1: a lone column field
+        let (idx, dat) = builder::table(&[
+            vec![(0, FieldValue::Integer(11))],
+            vec![(
+                0,
+                FieldValue::Column((FieldType::Filename as i32, String::from("filename"))),
+            )],
+        ]);
+        let indices =
+            Indices::from_reader(&mut std::io::Cursor::new(idx), &ParseOptions::default())
+                .map_err(|err| format!("{}", err))?;
+        let index = indices
+            .primary()
+            .ok_or_else(|| String::from("failed to read index"))?;
+
+        let mut cur = std::io::Cursor::new(dat);
+        let (cols, meta) = find_meta_records(&mut cur, index, &ParseOptions::default());
+        assert_eq!(cols, 1);
+        assert_eq!(meta, vec![1]);
+        Ok(())
+    }
+}
+
+/// A best guess at the generation of Winamp that wrote an NDE table
+///
+/// The on-disk format never changed its signature, but later Winamps added wider field types:
+/// 64-bit integers (for file sizes) & BINARY32 fields, whose sizes are 32 rather than 16 bits
+/// wide. A table declaring columns of either type was certainly written by one of them; one that
+/// doesn't *may* be older. This is a heuristic, meant to help explain surprises (lengths out by a
+/// factor of a thousand, say), not to drive the parse.
+#[derive(Clone, Copy, Debug, Default, Display, Eq, PartialEq)]
+#[display(style = "lowercase")]
+pub enum NdeVariant {
+    /// Nothing to go on: the table has no `filename` column, so it isn't a library table
+    #[default]
+    Unknown,
+    /// A library table using only the original field types
+    Classic,
+    /// A library table with INT64 or BINARY32 columns
+    Extended,
+}
+
+/// Guess the [`NdeVariant`] of a table from its column definitions
+///
+/// [`NdeVariant`]: enum.NdeVariant.html
+pub fn detect_version(columns: &[fields::ColumnField]) -> NdeVariant {
+    if !columns.iter().any(|col| col.name() == "filename") {
+        NdeVariant::Unknown
+    } else if columns
+        .iter()
+        .any(|col| matches!(col.col_type(), FieldType::Int64 | FieldType::Binary32))
+    {
+        NdeVariant::Extended
+    } else {
+        NdeVariant::Classic
+    }
+}
+
+#[cfg(test)]
+mod variant_tests {
+
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        use crate::builder::field_body;
+        let cols = |defs: &[(FieldType, &str)]| {
+            defs.iter()
+                .enumerate()
+                .map(|(id, (col_type, name))| {
+                    let value = FieldValue::Column((*col_type as i32, String::from(*name)));
+                    fields::ColumnField::new(
+                        &mut field_body(&value, 0, 0).as_slice(),
+                        id as i32,
+                        &ParseOptions::default(),
+                    )
+                    .map_err(|err| format!("{}", err))
+                })
+                .collect::<std::result::Result<Vec<_>, String>>()
+        };
+        assert_eq!(detect_version(&[]), NdeVariant::Unknown);
+        assert_eq!(
+            detect_version(&cols(&[(FieldType::String, "title")])?),
+            NdeVariant::Unknown
+        );
+        assert_eq!(
+            detect_version(&cols(&[
+                (FieldType::Filename, "filename"),
+                (FieldType::Integer, "filesize")
+            ])?),
+            NdeVariant::Classic
+        );
+        assert_eq!(
+            detect_version(&cols(&[
+                (FieldType::Filename, "filename"),
+                (FieldType::Int64, "filesize")
+            ])?),
+            NdeVariant::Extended
+        );
+        assert_eq!(format!("{}", NdeVariant::Extended), "extended");
+        Ok(())
+    }
+}
+
+/// Parse the record at `at` into a [`Track`]
+///
+/// [`Track`]: tracks/struct.Track.html
+fn read_track<R: Read + Seek>(
+    rdr: &mut R,
+    at: u64,
+    col_map: &ColumnMap,
+    col_types: &ColumnTypes,
+    opts: &ParseOptions,
+) -> Result<Track> {
+    let rec = read_record(rdr, at, opts)?;
+    // Between the columns & `rec', we have enough to create a Track
+    Ok(Track::new(col_map, col_types, rec.iter(), opts)?)
+}
+
+/// An NDE table: its indicies, together with the data file they index
+///
+/// [`Table::open`] reads the indicies, checks the data file's signature & reads the column
+/// definitions; from there, [`Table::track`] will parse any one record & [`Table::tracks`] will
+/// stream them all.
+///
+/// [`Table::open`]: struct.Table.html#method.open
+/// [`Table::track`]: struct.Table.html#method.track
+/// [`Table::tracks`]: struct.Table.html#method.tracks
+pub struct Table {
+    rdr: Box<dyn ReadSeek>,
+    indices: Indices,
+    columns: Vec<fields::ColumnField>,
+    col_map: ColumnMap,
+    col_types: ColumnTypes,
+    /// Numbers of the records defining columns & indicies, rather than tracks
+    meta: Vec<usize>,
+    variant: NdeVariant,
+    opts: ParseOptions,
+}
+
+impl Table {
+    /// Open the NDE table described by `idx` & `dat`; either (but not both) may be "-", meaning
+    /// stdin
+    pub fn open(idx: &Path, dat: &Path, opts: &ParseOptions) -> Result<Table> {
+        Table::open_with(idx, dat, false, opts)
+    }
+    /// Open the NDE table described by `idx` & `dat`, memory-mapping the latter if `mmap` is true
+    fn open_with(idx: &Path, dat: &Path, mmap: bool, opts: &ParseOptions) -> Result<Table> {
+        let (idxes, rdr) = open_table(idx, dat, mmap, opts)?;
+        Table::from_parts(Indices::from(idxes), rdr, opts)
+    }
+    /// Build a [`Table`] from its indicies & data file; `rdr` is assumed to have had its
+    /// signature verified, & both are read according to `opts`.
+    ///
+    /// [`Table`]: struct.Table.html
+    pub fn from_parts(
+        indices: Indices,
+        mut rdr: Box<dyn ReadSeek>,
+        opts: &ParseOptions,
+    ) -> Result<Table> {
+        let primary = match indices.primary() {
+            Some(primary) if !primary.is_empty() => primary,
+            _ => return Err(Error::new(Cause::NoIndicies)),
+        };
+        // One of the first few records should list the columns in this table; the indicies
+        // defined on this table are also in there somewhere.
+        let (cols_rec, meta) = find_meta_records(&mut rdr, primary, opts);
+        let columns = read_columns(&mut rdr, primary.off(cols_rec), opts)?;
+        let col_map = new_column_map(columns.iter(), opts);
+        debug!("column map: {:#?}", col_map);
+        let col_types = new_column_types(columns.iter());
+        let variant = detect_version(&columns);
+        info!("Guessing this table to be of the {} NDE variety.", variant);
+        Ok(Table {
+            rdr: rdr,
+            indices: indices,
+            columns: columns,
+            col_map: col_map,
+            col_types: col_types,
+            meta: meta,
+            variant: variant,
+            opts: opts.clone(),
+        })
+    }
+    /// This table's indicies
+    pub fn indices(&self) -> &Indices {
+        &self.indices
+    }
+    /// This table's column definitions
+    pub fn columns(&self) -> &[fields::ColumnField] {
+        &self.columns
+    }
+    /// Our best guess at the generation of Winamp that wrote this table
+    pub fn variant(&self) -> NdeVariant {
+        self.variant
+    }
+    /// The number of tracks in this table (i.e. records other than those defining its columns &
+    /// indicies)
+    pub fn len(&self) -> usize {
+        (0..self.nrecs())
+            .filter(|i| is_track_record(&self.meta, *i))
+            .count()
+    }
+    /// True if this table has no tracks
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Parse record `i` (by the primary index) into a [`Track`]
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn track(&mut self, i: usize) -> Result<Track> {
+        let len = self.nrecs();
+        let at = match self.indices.primary() {
+            Some(primary) if i < len => primary.off(i),
+            _ => {
+                return Err(Error::new(Cause::NoSuchRecord {
+                    record: i,
+                    len: len,
+                }))
+            }
+        };
+        debug!("Parsing record {} at {:#04x}.", i, at);
+        read_track(
+            &mut self.rdr,
+            at,
+            &self.col_map,
+            &self.col_types,
+            &self.opts,
+        )
+        .map_err(|err| err.at_record(i, at))
+    }
+    /// Read record `i` (by the primary index) as raw fields, without interpreting it as a
+    /// [`Track`]; unlike [`track`], this works for the records defining columns & indicies, too
+    ///
+    /// The fields are returned in the order in which they're chained together on disk.
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    /// [`track`]: #method.track
+    pub fn record(&mut self, i: usize) -> Result<Vec<Box<dyn NdeField>>> {
+        let len = self.nrecs();
+        let at = match self.indices.primary() {
+            Some(primary) if i < len => primary.off(i),
+            _ => {
+                return Err(Error::new(Cause::NoSuchRecord {
+                    record: i,
+                    len: len,
+                }))
+            }
+        };
+        debug!("Reading record {} at {:#04x}.", i, at);
+        read_record(&mut self.rdr, at, &self.opts).map_err(|err| err.at_record(i, at))
+    }
+    /// Stream this table's [`Track`]s in primary index order, each paired with its record number
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn tracks(&mut self) -> impl Iterator<Item = Result<(usize, Track)>> + '_ {
+        let recs: Vec<usize> = (0..self.nrecs())
+            .filter(|i| is_track_record(&self.meta, *i))
+            .collect();
+        recs.into_iter().map(move |i| self.track(i).map(|t| (i, t)))
+    }
+    /// Consume this table, returning an iterator over its [`Track`]s
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn into_tracks(self) -> Tracks<Box<dyn ReadSeek>> {
+        Tracks {
+            rdr: self.rdr,
+            // `from_parts` checked that there's a primary
+            index: self.indices.into_primary().unwrap_or(NdeIndex {
+                id: 0,
+                offsets: Vec::new(),
+                extras: Vec::new(),
+            }),
+            col_map: self.col_map,
+            col_types: self.col_types,
+            meta: self.meta,
+            variant: self.variant,
+            opts: self.opts,
+            next: 0,
+        }
+    }
+    /// The number of records in this table, tracks or otherwise
+    fn nrecs(&self) -> usize {
+        self.indices.primary().map_or(0, |x| x.len())
+    }
+}
+
+/// Stream the [`Track`]s in an NDE table, one record at a time, in primary index order
+///
+/// Each item is the primary index record number paired with the [`Track`] built from that record.
+///
+/// [`Track`]: tracks/struct.Track.html
+pub struct Tracks<R: Read + Seek> {
+    rdr: R,
+    index: NdeIndex,
+    col_map: ColumnMap,
+    col_types: ColumnTypes,
+    /// Numbers of the records defining columns & indicies, rather than tracks
+    meta: Vec<usize>,
+    variant: NdeVariant,
+    opts: ParseOptions,
+    next: usize,
+}
+
+impl<R: Read + Seek> Tracks<R> {
+    /// Build a [`Tracks`] iterator from a data file & its primary index; `rdr` is assumed to have
+    /// had its signature verified, & is read according to `opts`.
+    ///
+    /// [`Tracks`]: struct.Tracks.html
+    pub fn new(mut rdr: R, index: NdeIndex, opts: &ParseOptions) -> Result<Tracks<R>> {
+        if index.is_empty() {
+            return Err(Error::new(Cause::NoIndicies));
+        }
+        // One of the first few records should list the columns in this table; the indicies
+        // defined on this table are also in there somewhere, but we're only making use of the
+        // primary, so just skip them.
+        let (cols_rec, meta) = find_meta_records(&mut rdr, &index, opts);
+        let cols = read_columns(&mut rdr, index.off(cols_rec), opts)?;
+        let col_map = new_column_map(cols.iter(), opts);
+        debug!("column map: {:#?}", col_map);
+        let col_types = new_column_types(cols.iter());
+        let variant = detect_version(&cols);
+        info!("Guessing this table to be of the {} NDE variety.", variant);
+        Ok(Tracks {
+            rdr: rdr,
+            index: index,
+            col_map: col_map,
+            col_types: col_types,
+            meta: meta,
+            variant: variant,
+            opts: opts.clone(),
+            next: 0,
+        })
+    }
+    /// Our best guess at the generation of Winamp that wrote this table
+    pub fn variant(&self) -> NdeVariant {
+        self.variant
+    }
+    /// The number of tracks remaining
+    pub fn len(&self) -> usize {
+        (self.next..self.index.len())
+            .filter(|i| is_track_record(&self.meta, *i))
+            .count()
+    }
+    /// True if there are no tracks remaining
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Read the records defining this table's columns & indicies (i.e. those that *aren't*
+    /// tracks)
+    pub fn meta_records(&mut self) -> Result<Vec<Vec<Box<dyn NdeField>>>> {
+        let offsets: Vec<u64> = self.meta.iter().map(|i| self.index.off(*i)).collect();
+        offsets
+            .into_iter()
+            .map(|at| read_record(&mut self.rdr, at, &self.opts))
+            .collect()
+    }
+    /// Read the definitions of this table's indicies (which are kept in one of the records that
+    /// aren't tracks); if there's no such record, return an empty vector
+    pub fn index_definitions(&mut self) -> Result<Vec<IndexField>> {
+        let offsets: Vec<u64> = self.meta.iter().map(|i| self.index.off(*i)).collect();
+        for at in offsets {
+            if all_of_type(
+                &read_record(&mut self.rdr, at, &self.opts)?,
+                FieldType::Index,
+            ) {
+                return read_index_fields(&mut self.rdr, at, &self.opts);
+            }
+        }
+        Ok(Vec::new())
+    }
+}
+
+impl<R: Read + Seek> Iterator for Tracks<R> {
+    type Item = Result<(usize, Track)>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while !is_track_record(&self.meta, self.next) {
+            self.next += 1;
+        }
+        if self.next >= self.index.len() {
+            return None;
+        }
+        let i = self.next;
+        self.next += 1;
+        let at = self.index.off(i);
+        debug!("Parsing record {} at {:#04x}.", i, at);
+        Some(
+            read_track(
+                &mut self.rdr,
+                at,
+                &self.col_map,
+                &self.col_types,
+                &self.opts,
+            )
+            .map(|t| (i, t))
+            .map_err(|err| err.at_record(i, at)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod truncation_tests {
+
+    /// Running off the end of the data file mid-record should be called out as such
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        use fields::FieldValue;
+
+        let (idx, mut dat) = builder::table(&[
+            vec![(
+                0,
+                FieldValue::Column((FieldType::Filename as i32, String::from("filename"))),
+            )],
+            vec![(0, FieldValue::Filename(std::path::PathBuf::from("a.mp3")))],
+        ]);
+        dat.truncate(dat.len() - 5);
+        let idxes = read_indicies(&mut std::io::Cursor::new(idx), &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        let mut table = Table::from_parts(
+            Indices::from(idxes),
+            Box::new(std::io::Cursor::new(dat)),
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        match table.track(1) {
+            Err(err) => match err.cause() {
+                Cause::UnexpectedEof { record, offset } => {
+                    assert_eq!(*record, 1);
+                    assert_eq!(*offset, 8 + 25);
+                }
+                _ => return Err(format!("unexpected error {}", err)),
+            },
+            Ok(_) => return Err(String::from("the truncated record should have failed")),
+        }
+        Ok(())
+    }
+}
+
+/// Open the NDE table described by `idx` & `dat` and stream its [`Track`]s
+///
+/// [`Track`]: tracks/struct.Track.html
+pub fn tracks(idx: &Path, dat: &Path, opts: &ParseOptions) -> Result<Tracks<Box<dyn ReadSeek>>> {
+    Ok(Table::open(idx, dat, opts)?.into_tracks())
+}
+
+/// Read the definitions of the indicies on the NDE table described by `idx` & `dat`
+///
+/// The names of these indicies are (by convention) those of the columns by which they order the
+/// table's records.
+pub fn read_index_definitions(
+    idx: &Path,
+    dat: &Path,
+    opts: &ParseOptions,
+) -> Result<Vec<IndexField>> {
+    tracks(idx, dat, opts)?.index_definitions()
+}
+
+/// Asynchronously read an NDE table's index & data from `idx` & `dat`, and stream its [`Track`]s
+///
+/// This is for embedding xnde in an async runtime (Tokio, e.g.). Parsing a record means following
+/// field offsets all over the data file, so rather than making every field constructor async,
+/// we read both inputs into memory without blocking, then parse from there. Iterating over the
+/// result never touches I/O, although a large library will still take a while to parse, so
+/// callers may prefer to do that in `spawn_blocking`.
+///
+/// [`Track`]: tracks/struct.Track.html
+#[cfg(feature = "async")]
+pub async fn read_tracks<I, D>(
+    mut idx: I,
+    mut dat: D,
+    opts: &ParseOptions,
+) -> Result<Tracks<std::io::Cursor<Vec<u8>>>>
+where
+    I: tokio::io::AsyncRead + Unpin,
+    D: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut idx_buf: Vec<u8> = Vec::new();
+    idx.read_to_end(&mut idx_buf).await?;
+    let mut dat_buf: Vec<u8> = Vec::new();
+    dat.read_to_end(&mut dat_buf).await?;
+    tracks_from(
+        &mut std::io::Cursor::new(idx_buf),
+        std::io::Cursor::new(dat_buf),
+        opts,
+    )
+}
+
+/// Stream the [`Track`]s of the NDE table whose index & data are read from `idx` & `dat`
+///
+/// [`Track`]: tracks/struct.Track.html
+fn tracks_from<I: Read + Seek, R: Read + Seek>(
+    idx: &mut I,
+    mut dat: R,
+    opts: &ParseOptions,
+) -> Result<Tracks<R>> {
+    let idxes = read_indicies(idx, opts)?;
+    check_table(&idxes, &mut dat)?;
+    let primary = idxes
+        .into_iter()
+        .next()
+        .ok_or(Error::new(Cause::NoIndicies))?;
+    Tracks::new(dat, primary, opts)
+}
+
+/// Parse the [`Track`]s out of an NDE table's index & data, given as bytes
+///
+/// This never touches the filesystem, making it suitable for environments that have none, such as
+/// WebAssembly in the browser (`wasm32-unknown-unknown`); build xnde there without default
+/// features (other than `serde`, if you want [`serialize_tracks`]), so as to leave out the
+/// backtrace support.
+///
+/// [`Track`]: tracks/struct.Track.html
+/// [`serialize_tracks`]: fn.serialize_tracks.html
+pub fn parse_bytes(idx: &[u8], dat: &[u8], opts: &ParseOptions) -> Result<Vec<Track>> {
+    tracks_from(
+        &mut std::io::Cursor::new(idx),
+        std::io::Cursor::new(dat),
+        opts,
+    )?
+    .map(|t| t.map(|(_, t)| t))
+    .collect()
+}
+
+/// An iterator over record numbers & the [`Track`]s parsed from them
+///
+/// [`Track`]: tracks/struct.Track.html
+type TrackResults = Box<dyn Iterator<Item = Result<(usize, Track)>>>;
+
+/// Parse all the records remaining in `iter` on `threads` threads
+///
+/// Since the primary index gives us the offset of every record, parsing is embarrassingly
+/// parallel: each worker parses a contiguous chunk of records through its own cursor on the data.
+/// The chunks are merged back in index order.
+///
+/// The data is read out of `iter` into memory once, up-front, and shared amongst the workers;
+/// re-opening the data file per worker would re-decompress a gzip'd file each time, and a data
+/// file read from stdin can't be re-opened at all.
+#[cfg(feature = "parallel")]
+fn read_parallel<R: Read + Seek + 'static>(
+    mut iter: Tracks<R>,
+    threads: usize,
+) -> Result<TrackResults> {
+    use rayon::prelude::*;
+
+    let mut buf: Vec<u8> = Vec::new();
+    iter.rdr.seek(SeekFrom::Start(0))?;
+    iter.rdr.read_to_end(&mut buf)?;
+    let data: std::sync::Arc<[u8]> = std::sync::Arc::from(buf);
+
+    let recs: Vec<usize> = (iter.next..iter.index.len())
+        .filter(|i| is_track_record(&iter.meta, *i))
+        .collect();
+    let chunk = std::cmp::max(1, recs.len() / (threads * 4));
+    let index = &iter.index;
+    let col_map = &iter.col_map;
+    let col_types = &iter.col_types;
+    let opts = &iter.opts;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()?;
+    let chunks = pool.install(|| {
+        recs.par_chunks(chunk)
+            .map(|chunk| -> Result<Vec<Result<(usize, Track)>>> {
+                let mut fddat = std::io::Cursor::new(std::sync::Arc::clone(&data));
+                // Keep per-record failures as items, so the caller may choose to skip them
+                Ok(chunk
+                    .iter()
+                    .map(|&i| -> Result<(usize, Track)> {
+                        let at = index.off(i);
+                        let rec = read_record(&mut fddat, at, opts)
+                            .map_err(|err| err.at_record(i, at))?;
+                        let trk = Track::new(col_map, col_types, rec.iter(), opts)
+                            .map_err(|err| Error::from(err).at_record(i, at))?;
+                        Ok((i, trk))
+                    })
+                    .collect())
+            })
+            .collect::<Result<Vec<Vec<Result<(usize, Track)>>>>>()
+    })?;
+
+    Ok(Box::new(chunks.into_iter().flatten()))
+}
+
+#[cfg(not(feature = "parallel"))]
+fn read_parallel<R: Read + Seek + 'static>(
+    iter: Tracks<R>,
+    threads: usize,
+) -> Result<TrackResults> {
+    log::warn!(
+        "xnde was built without the `parallel' feature; ignoring the request for {} threads.",
+        threads
+    );
+    Ok(Box::new(iter))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                          export logic                                          //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Periodically log progress through a long-running loop, along with an estimate of the time
+/// remaining
+struct Progress {
+    total: usize,
+    done: usize,
+    /// Log every `interval` iterations
+    interval: usize,
+    start: std::time::Instant,
+}
+
+impl Progress {
+    /// Log every 5% or every ten thousand iterations, whichever comes first
+    fn new(total: usize) -> Progress {
+        Progress {
+            total: total,
+            done: 0,
+            interval: (total / 20).clamp(1, 10_000),
+            start: std::time::Instant::now(),
+        }
+    }
+    fn tick(&mut self) {
+        self.done += 1;
+        if self.done.is_multiple_of(self.interval) && self.done < self.total {
+            let elapsed = self.start.elapsed().as_secs_f64();
+            let eta = elapsed * (self.total - self.done) as f64 / self.done as f64;
+            info!(
+                "Processed {}/{} records ({}%); ETA {}.",
+                self.done,
+                self.total,
+                100 * self.done / self.total,
+                format_duration(eta.round() as i64)
+            );
+        }
+    }
+}
+
+/// An export that includes the table's metadata records, for those auditing its schema
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+struct Audit<'a, T: Serialize> {
+    meta: &'a [Vec<Box<dyn NdeField>>],
+    tracks: &'a [T],
+}
+
+/// Options governing [`export`]; the defaults export every track, in primary index order, parsed
+/// on one thread, as UTF-8, replacing the output file & failing on the first unparseable record
+///
+/// [`export`]: fn.export.html
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    filter: TrackFilter,
+    dedup: Option<TrackAttrs>,
+    threads: usize,
+    mmap: bool,
+    sort_by: Vec<TrackAttrs>,
+    include_meta: bool,
+    encoding: Option<String>,
+    append: bool,
+    null_as: Option<NullAs>,
+    skip_errors: bool,
+    raw: bool,
+    max_bytes: Option<u64>,
+    max_records: Option<usize>,
+    parse_opts: ParseOptions,
+}
+
+impl std::default::Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            filter: TrackFilter::default(),
+            dedup: None,
+            threads: 1,
+            mmap: false,
+            sort_by: Vec::new(),
+            include_meta: false,
+            encoding: None,
+            append: false,
+            null_as: None,
+            skip_errors: false,
+            raw: false,
+            max_bytes: None,
+            max_records: None,
+            parse_opts: ParseOptions::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    pub fn new() -> ExportOptions {
+        ExportOptions::default()
+    }
+    /// Drop [`Track`]s not admitted by `filter`
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn filter(mut self, filter: TrackFilter) -> ExportOptions {
+        self.filter = filter;
+        self
+    }
+    /// Keep only the first [`Track`] for any given value of this attribute (a [`Track`] lacking
+    /// it is always kept)
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn dedup(mut self, key: Option<TrackAttrs>) -> ExportOptions {
+        self.dedup = key;
+        self
+    }
+    /// Parse records on this many threads (if xnde was built with the `parallel` feature)
+    pub fn threads(mut self, threads: usize) -> ExportOptions {
+        self.threads = threads;
+        self
+    }
+    /// Memory-map the data file (if xnde was built with the `mmap` feature)
+    pub fn mmap(mut self, mmap: bool) -> ExportOptions {
+        self.mmap = mmap;
+        self
+    }
+    /// (Stably) sort the [`Track`]s by these attributes, in turn, before writing them
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn sort_by(mut self, sort_by: Vec<TrackAttrs>) -> ExportOptions {
+        self.sort_by = sort_by;
+        self
+    }
+    /// Write the records defining the table's columns & indicies alongside the [`Track`]s, as
+    /// raw fields (i.e. the output will be an object with attributes `meta` & `tracks`, rather
+    /// than a list of [`Track`]s)
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn include_meta(mut self, include_meta: bool) -> ExportOptions {
+        self.include_meta = include_meta;
+        self
+    }
+    /// Transcode the output from UTF-8 to the encoding with this label (if xnde was built with
+    /// the `encoding` feature)
+    pub fn encoding(mut self, label: Option<String>) -> ExportOptions {
+        self.encoding = label;
+        self
+    }
+    /// Append to the output file, rather than replacing it; this is only permitted for
+    /// line-oriented formats (ndjson)
+    pub fn append(mut self, append: bool) -> ExportOptions {
+        self.append = append;
+        self
+    }
+    /// Write attributes missing from a [`Track`], represented as this says (rather than leaving
+    /// them out)
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn null_as(mut self, null_as: Option<NullAs>) -> ExportOptions {
+        self.null_as = null_as;
+        self
+    }
+    /// Log & skip records that can't be parsed, rather than aborting the export
+    pub fn skip_errors(mut self, skip_errors: bool) -> ExportOptions {
+        self.skip_errors = skip_errors;
+        self
+    }
+    /// Write every record as raw fields, rather than as [`Track`]s; the filtering, de-duplicating
+    /// & sorting options don't apply to such an export
+    ///
+    /// [`Track`]: tracks/struct.Track.html
+    pub fn raw(mut self, raw: bool) -> ExportOptions {
+        self.raw = raw;
+        self
+    }
+    /// Give up (with [`Cause::LimitExceeded`]) once more than this many bytes have been read
+    /// from the data file; this guards against pathological files (a corrupt index pointing the
+    /// parser at the same huge field over & over, say). The limit is enforced as the data file is
+    /// read, even partway through a record; since the count is shared, it forces records to be
+    /// parsed on a single thread.
+    ///
+    /// [`Cause::LimitExceeded`]: enum.Cause.html#variant.LimitExceeded
+    pub fn max_bytes(mut self, max_bytes: Option<u64>) -> ExportOptions {
+        self.max_bytes = max_bytes;
+        self
+    }
+    /// Give up (with [`Cause::LimitExceeded`]) once more than this many records have been read
+    /// (including any skipped as unparseable); like [`max_bytes`], this forces records to be
+    /// parsed on a single thread.
+    ///
+    /// [`Cause::LimitExceeded`]: enum.Cause.html#variant.LimitExceeded
+    /// [`max_bytes`]: #method.max_bytes
+    pub fn max_records(mut self, max_records: Option<usize>) -> ExportOptions {
+        self.max_records = max_records;
+        self
+    }
+    /// Read the table according to these options
+    pub fn parse_options(mut self, parse_opts: ParseOptions) -> ExportOptions {
+        self.parse_opts = parse_opts;
+        self
+    }
+    fn has_limits(&self) -> bool {
+        self.max_bytes.is_some() || self.max_records.is_some()
+    }
+    /// Fail if, having read `nrecs` records & `nbytes` bytes, we've exceeded either limit
+    fn check_limits(&self, nrecs: usize, nbytes: u64) -> Result<()> {
+        if let Some(limit) = self.max_records {
+            if nrecs > limit {
+                return Err(Error::new(Cause::LimitExceeded {
+                    what: "records",
+                    limit: limit as u64,
+                }));
+            }
+        }
+        if let Some(limit) = self.max_bytes {
+            if nbytes > limit {
+                return Err(Error::new(Cause::LimitExceeded {
+                    what: "bytes",
+                    limit: limit,
+                }));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Count the bytes read through a reader, so that an export can give up on a pathological data
+/// file (cf. [`ExportOptions::max_bytes`])
+///
+/// Once more than `limit` bytes have been read, all further reads fail; checking the count
+/// between records isn't enough, since a single corrupt record could have us reading forever.
+///
+/// [`ExportOptions::max_bytes`]: struct.ExportOptions.html#method.max_bytes
+struct ReadCounter<R> {
+    rdr: R,
+    count: Rc<Cell<u64>>,
+    limit: Option<u64>,
+}
+
+impl<R: Read> Read for ReadCounter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(limit) = self.limit {
+            if self.count.get() > limit {
+                return Err(std::io::Error::other(format!(
+                    "read more than the limit of {} bytes",
+                    limit
+                )));
+            }
+        }
+        let n = self.rdr.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for ReadCounter<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.rdr.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod read_counter_tests {
+
+    /// Reads should fail once the limit has been passed, not merely be counted
+    #[test]
+    fn limit() -> Result<(), String> {
+        use super::*;
+        let count = Rc::new(Cell::new(0));
+        let mut rdr = ReadCounter {
+            rdr: std::io::Cursor::new(vec![0u8; 64]),
+            count: count.clone(),
+            limit: Some(8),
+        };
+        let mut buf: [u8; 6] = [0; 6];
+        rdr.read_exact(&mut buf).map_err(|err| format!("{}", err))?;
+        rdr.read_exact(&mut buf).map_err(|err| format!("{}", err))?;
+        assert_eq!(count.get(), 12);
+        assert!(rdr.read_exact(&mut buf).is_err());
+        assert_eq!(count.get(), 12);
+        Ok(())
+    }
+}
+
+/// Open the table at `idx` & `dat` per `opts`, keeping a running count of the bytes read from
+/// `dat` in the returned cell
+fn open_counted(idx: &Path, dat: &Path, opts: &ExportOptions) -> Result<(Table, Rc<Cell<u64>>)> {
+    let (idxes, rdr) = open_table(idx, dat, opts.mmap, &opts.parse_opts)?;
+    let count = Rc::new(Cell::new(0));
+    let rdr = ReadCounter {
+        rdr: rdr,
+        count: count.clone(),
+        limit: opts.max_bytes,
+    };
+    // Report a read cut short by the limit as such, rather than as an I/O error
+    let table = Table::from_parts(Indices::from(idxes), Box::new(rdr), &opts.parse_opts)
+        .map_err(|err| opts.check_limits(0, count.get()).err().unwrap_or(err))?;
+    Ok((table, count))
+}
+
+// TODO(sp1ff): re-write to take readers; write unit tests
+/// transform your Winamp music library into an in-memory datastructure and serialize it to any
+/// variety of formats via Serde. If `out` is `None`, nothing will be written; rather, the number
+/// of [`Track`]s & the size of the output that would have been written will be logged (a dry
+/// run). See [`ExportOptions`] for the ways in which this may be tailored.
+///
+/// [`Track`]: tracks/struct.Track.html
+/// [`ExportOptions`]: struct.ExportOptions.html
+pub fn export(
+    idx: &Path,
+    dat: &Path,
+    format: Format,
+    out: Option<&Path>,
+    opts: &ExportOptions,
+) -> Result<()> {
+    if opts.raw {
+        check_export_raw(format, opts)?;
+        let (table, nbytes) = open_counted(idx, dat, opts)?;
+        return export_raw(table, nbytes, format, out, opts);
+    }
+    check_export(format, opts)?;
+    let (table, nbytes) = open_counted(idx, dat, opts)?;
+    export_tracks(table, nbytes, format, out, opts)
+}
+
+/// Check that `opts` make sense for an export of [`Track`]s to `format`; this is done before
+/// opening the table so as to catch mistakes before parsing the entire library
+///
+/// [`Track`]: tracks/struct.Track.html
+fn check_export(format: Format, opts: &ExportOptions) -> Result<()> {
+    if format.supports_display() {
+        return Err(Error::new(Cause::BadFormat(format.to_string())));
+    }
+    if opts.append && !format.is_line_oriented() {
+        return Err(Error::new(Cause::CantAppend(format)));
+    }
+    if opts.include_meta && !format.supports_meta() {
+        return Err(Error::new(Cause::NoMetaIn(format)));
+    }
+    if let Some(label) = opts.encoding.as_deref() {
+        check_encoding(label)?;
+    }
+    if format == Format::Itunes && !cfg!(feature = "itunes") {
+        return Err(Error::new(Cause::NoItunes));
+    }
+    if format != Format::Itunes && !cfg!(feature = "serde") {
+        return Err(Error::new(Cause::NoSerde(format)));
+    }
+    Ok(())
+}
+
+/// Export the [`Track`]s in `table` (already opened, with `nbytes` counting the bytes read from
+/// its data file) to `out` per `opts`, which are assumed to have been checked by
+/// [`check_export`]
+///
+/// [`Track`]: tracks/struct.Track.html
+/// [`check_export`]: fn.check_export.html
+fn export_tracks(
+    table: Table,
+    nbytes: Rc<Cell<u64>>,
+    format: Format,
+    out: Option<&Path>,
+    opts: &ExportOptions,
+) -> Result<()> {
+    let filter = &opts.filter;
+    let dedup = opts.dedup.as_ref();
+    let sort_by = &opts.sort_by[..];
+    let include_meta = opts.include_meta;
+    let encoding = opts.encoding.as_deref();
+    let append = opts.append;
+    let null_as = opts.null_as.as_ref();
+    let mut iter = table.into_tracks();
+    let meta = if include_meta {
+        iter.meta_records()?
+    } else {
+        Vec::new()
+    };
+    let ntrks = iter.len();
+    if opts.threads > 1 && opts.has_limits() {
+        log::warn!("Parse limits are checked record-by-record; reading on a single thread.");
+    }
+    let iter: TrackResults = if opts.threads > 1 && !opts.has_limits() {
+        read_parallel(iter, opts.threads)?
+    } else {
+        Box::new(iter)
+    };
+
+    let mut trks: Vec<Track> = Vec::with_capacity(ntrks);
+    let mut seen: HashSet<AttrValue> = HashSet::new();
+    let mut ndups: usize = 0;
+    let mut progress = Progress::new(ntrks);
+    info!("Creating {} Tracks...", ntrks);
+    let mut nskipped: usize = 0;
+    let mut nfiltered: usize = 0;
+    let mut nread: usize = 0;
+    for t in iter {
+        progress.tick();
+        nread += 1;
+        opts.check_limits(nread, nbytes.get())?;
+        let (i, t) = match t {
+            Ok(t) => t,
+            Err(err) if opts.skip_errors => {
+                log::warn!("Skipping unparseable record: {}", err);
+                nskipped += 1;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        if !filter.matches(&t) {
+            nfiltered += 1;
+            continue;
+        }
+        // A track lacking the key can't be a duplicate of anything
+        if let Some(key) = dedup.and_then(|key| t.attr_value(key)) {
+            if !seen.insert(key.clone()) {
+                debug!("Dropping record {} as a duplicate of {}.", i, key);
+                ndups += 1;
+                continue;
+            }
+        }
+        trks.push(t);
+    }
+    info!("Creating {} Tracks...done.", ntrks);
+    if !filter.is_empty() {
+        info!("Filtered out {} Tracks.", nfiltered);
+    }
+    if dedup.is_some() {
+        info!("Dropped {} duplicate Tracks.", ndups);
+    }
+    if nskipped > 0 {
+        log::warn!("Skipped {} unparseable records.", nskipped);
+    }
+    if !sort_by.is_empty() {
+        info!("Sorting {} Tracks...", trks.len());
+        trks.sort_by(|lhs, rhs| compare_tracks(lhs, rhs, sort_by));
+        info!("Sorting {} Tracks...done.", trks.len());
+    }
+
+    let meta = if include_meta { Some(&meta[..]) } else { None };
+    match out {
+        Some(out) => {
+            info!("Writing {}...", out.display());
+            match encoding {
+                Some(label) => {
+                    let mut buf: Vec<u8> = Vec::new();
+                    write_tracks(&mut buf, &format, meta, &trks, null_as)?;
+                    create_output(out, append)?.write_all(&transcode(buf, label)?)?;
+                }
+                None => write_tracks(create_output(out, append)?, &format, meta, &trks, null_as)?,
+            }
+            info!("Writing {}...done.", out.display());
+        }
+        None => {
+            let mut counter = ByteCounter { count: 0 };
+            write_tracks(&mut counter, &format, meta, &trks, null_as)?;
+            info!(
+                "Dry run: would have written {} Tracks ({} bytes).",
+                trks.len(),
+                counter.count
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the NDE tables in `dir`: the names of all `<name>.idx` files having a corresponding
+/// `<name>.dat`, in sorted order
+pub fn find_tables(dir: &Path) -> Result<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|x| x.to_str()) != Some("idx") {
+            continue;
+        }
+        if let Some(name) = path.file_stem().and_then(|x| x.to_str()) {
+            if path.with_extension("dat").is_file() {
+                names.push(String::from(name));
+            } else {
+                debug!("{} has no data file; skipping it.", path.display());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Export every NDE table in `dir` (`main`, `playlists` &c) to its own file in `out_dir`, named
+/// for the table & the format (`playlists.json`, e.g.); if `out_dir` is `None`, this is a dry
+/// run
+///
+/// Tables holding tracks (i.e. having a `filename` column) are exported per `opts`. xnde doesn't
+/// know the schemas of the others, so they are written raw, as lists of fields (cf. [`export`]),
+/// or skipped altogether if `format` is `itunes`. Return the names of the tables written.
+///
+/// [`export`]: fn.export.html
+pub fn export_tables(
+    dir: &Path,
+    format: Format,
+    out_dir: Option<&Path>,
+    opts: &ExportOptions,
+) -> Result<Vec<String>> {
+    if format.supports_display() {
+        return Err(Error::new(Cause::BadFormat(format.to_string())));
+    }
+    let names = find_tables(dir)?;
+    if names.is_empty() {
+        return Err(Error::new(Cause::NoTables(dir.display().to_string())));
+    }
+    if let Some(out_dir) = out_dir {
+        std::fs::create_dir_all(out_dir)?;
+    }
+    let mut written: Vec<String> = Vec::with_capacity(names.len());
+    for name in names {
+        let idx = dir.join(format!("{}.idx", name));
+        let dat = dir.join(format!("{}.dat", name));
+        let out = out_dir.map(|d| d.join(format!("{}.{}", name, format.extension())));
+        let (table, nbytes) = open_counted(&idx, &dat, opts)?;
+        let has_tracks = table.columns().iter().any(|col| col.name() == "filename");
+        info!("Exporting table {}...", name);
+        if opts.raw {
+            check_export_raw(format, opts)?;
+            export_raw(table, nbytes, format, out.as_deref(), opts)?;
+        } else if has_tracks {
+            check_export(format, opts)?;
+            export_tracks(table, nbytes, format, out.as_deref(), opts)?;
+        } else if format == Format::Itunes {
+            log::warn!("Table {} doesn't hold tracks; skipping it.", name);
+            continue;
+        } else {
+            info!(
+                "Table {} doesn't hold tracks; exporting its raw records.",
+                name
+            );
+            check_export_raw(format, opts)?;
+            export_raw(table, nbytes, format, out.as_deref(), opts)?;
+        }
+        info!("Exporting table {}...done.", name);
+        written.push(name);
+    }
+    Ok(written)
+}
+
+/// Check that `opts` make sense for a raw export to `format` (cf. [`export_raw`])
+///
+/// [`export_raw`]: fn.export_raw.html
+fn check_export_raw(format: Format, opts: &ExportOptions) -> Result<()> {
+    // Raw records have no iTunes representation
+    if format.supports_display() || format == Format::Itunes {
+        return Err(Error::new(Cause::BadFormat(format.to_string())));
+    }
+    if opts.append && !format.is_line_oriented() {
+        return Err(Error::new(Cause::CantAppend(format)));
+    }
+    if let Some(label) = opts.encoding.as_deref() {
+        check_encoding(label)?;
+    }
+    if !cfg!(feature = "serde") {
+        return Err(Error::new(Cause::NoSerde(format)));
+    }
+    Ok(())
+}
+
+/// Serialize every record in `table` (already opened, with `nbytes` counting the bytes read from
+/// its data file), as raw fields, rather than as [`Track`]s
+///
+/// This is for understanding a table whose schema xnde doesn't know: each record is written as a
+/// list of its fields (ID, type & value), in primary index order, including the records defining
+/// the table's columns & indicies. Within each record, the fields are listed in the order in which
+/// they're chained together on disk, so the output reflects the record's actual layout. `opts` are
+/// assumed to have been checked by [`check_export_raw`].
+///
+/// [`Track`]: tracks/struct.Track.html
+/// [`check_export_raw`]: fn.check_export_raw.html
+fn export_raw(
+    mut table: Table,
+    nbytes: Rc<Cell<u64>>,
+    format: Format,
+    out: Option<&Path>,
+    opts: &ExportOptions,
+) -> Result<()> {
+    let nrecs = table.nrecs();
+    let mut recs: Vec<Vec<Box<dyn NdeField>>> = Vec::with_capacity(nrecs);
+    let mut progress = Progress::new(nrecs);
+    info!("Reading {} records...", nrecs);
+    for i in 0..nrecs {
+        progress.tick();
+        let rec = table.record(i);
+        opts.check_limits(i + 1, nbytes.get())?;
+        recs.push(rec?);
+    }
+    info!("Reading {} records...done.", nrecs);
+
+    match out {
+        Some(out) => {
+            info!("Writing {}...", out.display());
+            match opts.encoding.as_deref() {
+                Some(label) => {
+                    let mut buf: Vec<u8> = Vec::new();
+                    serialize_records(&mut buf, &format, &recs)?;
+                    create_output(out, opts.append)?.write_all(&transcode(buf, label)?)?;
+                }
+                None => serialize_records(create_output(out, opts.append)?, &format, &recs)?,
+            }
+            info!("Writing {}...done.", out.display());
+        }
+        None => {
+            let mut counter = ByteCounter { count: 0 };
+            serialize_records(&mut counter, &format, &recs)?;
+            info!(
+                "Dry run: would have written {} records ({} bytes).",
+                recs.len(),
+                counter.count
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn serialize_records<W: Write>(
+    w: W,
+    format: &Format,
+    recs: &[Vec<Box<dyn NdeField>>],
+) -> Result<()> {
+    match format {
+        Format::Display | Format::Itunes => {
+            return Err(Error::new(Cause::BadFormat(format.to_string())))
+        }
+        Format::Sexp => serde_lexpr::to_writer(w, &recs)?,
+        Format::Json => serde_json::to_writer(w, recs)?,
+        Format::Ndjson => {
+            let mut w = std::io::BufWriter::new(w);
+            for rec in recs {
+                serde_json::to_writer(&mut w, rec)?;
+                w.write_all(b"\n")?;
+            }
+            w.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn serialize_records<W: Write>(
+    _w: W,
+    format: &Format,
+    _recs: &[Vec<Box<dyn NdeField>>],
+) -> Result<()> {
+    Err(Error::new(Cause::NoSerde(*format)))
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod export_raw_tests {
+
+    /// Each record should come out as a list of its fields, meta records included
+    #[test]
+    fn smoke() -> Result<(), String> {
+        use super::*;
+        use fields::FieldValue;
+
+        let (idx, dat) = builder::table(&[
+            vec![(
+                0,
+                FieldValue::Column((FieldType::Filename as i32, String::from("filename"))),
+            )],
+            vec![(0, FieldValue::Filename(std::path::PathBuf::from("a.mp3")))],
+        ]);
+        let idxes = read_indicies(&mut std::io::Cursor::new(idx), &ParseOptions::default())
+            .map_err(|err| format!("{}", err))?;
+        let mut table = Table::from_parts(
+            Indices::from(idxes),
+            Box::new(std::io::Cursor::new(dat)),
+            &ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(table.len(), 1);
+        let recs: Vec<Vec<Box<dyn NdeField>>> = (0..2)
+            .map(|i| table.record(i))
+            .collect::<Result<_>>()
+            .map_err(|err| format!("{}", err))?;
+        assert!(table.record(2).is_err());
+
+        let mut buf: Vec<u8> = Vec::new();
+        serialize_records(&mut buf, &Format::Ndjson, &recs).map_err(|err| format!("{}", err))?;
+        let text = String::from_utf8(buf).map_err(|err| format!("{}", err))?;
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("filename"));
+        assert!(lines[1].contains("a.mp3"));
+        assert!(serialize_records(&mut Vec::new(), &Format::Display, &recs).is_err());
+        Ok(())
+    }
+
+    /// Formats that can't hold raw records should be refused before the table is read
+    #[test]
+    fn bad_formats() {
+        use super::*;
+        for format in [Format::Display, Format::Itunes] {
+            match check_export_raw(format, &ExportOptions::new().raw(true)) {
+                Err(err) => assert!(matches!(err.cause(), Cause::BadFormat(_))),
+                Ok(_) => panic!("{} should have been refused", format),
+            }
+        }
+    }
+}
+
+/// Open `out` for writing; if `append` is true, we'll add to the end of `out` (if it exists),
+/// rather than replacing its contents
+fn create_output(out: &Path, append: bool) -> Result<File> {
+    if append {
+        Ok(std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(out)?)
+    } else {
+        Ok(File::create(out)?)
+    }
+}
+
+/// Serialize `trks` (along with the table's metadata records, if given) to `w`; if `null_as` is
+/// given, every attribute will be written, with those missing from a track represented thereby
+/// (except in an iTunes library, which has no way to represent them)
+fn write_tracks<W: Write>(
+    w: W,
+    format: &Format,
+    meta: Option<&[Vec<Box<dyn NdeField>>]>,
+    trks: &[Track],
+    null_as: Option<&NullAs>,
+) -> Result<()> {
+    if *format == Format::Itunes {
+        return match meta {
+            Some(_) => Err(Error::new(Cause::NoMetaIn(*format))),
+            None => write_itunes(w, trks),
+        };
+    }
+    serialize_to(w, format, meta, trks, null_as)
+}
+
+#[cfg(feature = "serde")]
+fn serialize_to<W: Write>(
+    w: W,
+    format: &Format,
+    meta: Option<&[Vec<Box<dyn NdeField>>]>,
+    trks: &[Track],
+    null_as: Option<&NullAs>,
+) -> Result<()> {
+    match null_as {
+        Some(null_as) => {
+            let trks: Vec<WithNulls> = trks.iter().map(|t| WithNulls::new(t, null_as)).collect();
+            serialize_all(w, format, meta, &trks)
+        }
+        None => serialize_all(w, format, meta, trks),
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+fn serialize_to<W: Write>(
+    _w: W,
+    format: &Format,
+    _meta: Option<&[Vec<Box<dyn NdeField>>]>,
+    _trks: &[Track],
+    _null_as: Option<&NullAs>,
+) -> Result<()> {
+    Err(Error::new(Cause::NoSerde(*format)))
+}
+
+#[cfg(feature = "serde")]
+fn serialize_all<W: Write, T: Serialize>(
+    w: W,
+    format: &Format,
+    meta: Option<&[Vec<Box<dyn NdeField>>]>,
+    trks: &[T],
+) -> Result<()> {
+    match meta {
+        Some(meta) => {
+            let audit = Audit {
+                meta: meta,
+                tracks: trks,
+            };
+            match format {
+                Format::Display => return Err(Error::new(Cause::BadFormat(format.to_string()))),
+                Format::Sexp => serde_lexpr::to_writer(w, &audit)?,
+                Format::Json => serde_json::to_writer(w, &audit)?,
+                Format::Ndjson | Format::Itunes => {
+                    return Err(Error::new(Cause::NoMetaIn(*format)))
+                }
+            }
+        }
+        None => match format {
+            // iTunes libraries are written by `write_itunes`, not serde
+            Format::Display | Format::Itunes => {
+                return Err(Error::new(Cause::BadFormat(format.to_string())))
+            }
+            Format::Sexp => serde_lexpr::to_writer(w, &trks)?,
+            Format::Json => serde_json::to_writer(w, trks)?,
+            Format::Ndjson => {
+                let mut w = std::io::BufWriter::new(w);
+                for t in trks {
+                    serde_json::to_writer(&mut w, t)?;
+                    w.write_all(b"\n")?;
+                }
+                w.flush()?;
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Serialize `trks` to `format`, in memory; with [`parse_bytes`], this takes a library from bytes
+/// to bytes without touching the filesystem
+///
+/// [`parse_bytes`]: fn.parse_bytes.html
+pub fn serialize_tracks(trks: &[Track], format: Format) -> Result<Vec<u8>> {
+    if format.supports_display() {
+        return Err(Error::new(Cause::BadFormat(format.to_string())));
+    }
+    let mut buf: Vec<u8> = Vec::new();
+    write_tracks(&mut buf, &format, None, trks, None)?;
+    Ok(buf)
+}
+
+/// Write `trks` as an iTunes library: a property list whose `Tracks` dictionary maps each track's
+/// ID (its position in `trks`, counting from one) to its attributes
+#[cfg(feature = "itunes")]
+fn write_itunes<W: Write>(w: W, trks: &[Track]) -> Result<()> {
+    use plist::Value;
+    let mut tracks = plist::Dictionary::new();
+    for (i, t) in trks.iter().enumerate() {
+        let id = i as i64 + 1;
+        tracks.insert(format!("{}", id), Value::Dictionary(t.to_itunes_dict(id)));
+    }
+    let mut library = plist::Dictionary::new();
+    library.insert(String::from("Major Version"), Value::Integer(1i64.into()));
+    library.insert(String::from("Minor Version"), Value::Integer(1i64.into()));
+    library.insert(
+        String::from("Application Version"),
+        Value::String(format!("xnde {}", env!("CARGO_PKG_VERSION"))),
+    );
+    library.insert(String::from("Tracks"), Value::Dictionary(tracks));
+    library.insert(String::from("Playlists"), Value::Array(Vec::new()));
+    Value::Dictionary(library).to_writer_xml(w)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "itunes"))]
+fn write_itunes<W: Write>(_w: W, _trks: &[Track]) -> Result<()> {
+    Err(Error::new(Cause::NoItunes))
+}
+
+/// Check that `label` names an encoding to which we can transcode our output
+#[cfg(feature = "encoding")]
+fn check_encoding(label: &str) -> Result<()> {
+    match encoding_rs::Encoding::for_label(label.as_bytes()) {
+        Some(_) => Ok(()),
+        None => Err(Error::new(Cause::BadEncoding(String::from(label)))),
+    }
+}
+
+#[cfg(not(feature = "encoding"))]
+fn check_encoding(label: &str) -> Result<()> {
+    Err(Error::new(Cause::NoEncoding(String::from(label))))
+}
+
+/// Transcode `buf` (UTF-8, as written by serde) to the encoding named by `label`; characters that
+/// can't be represented in that encoding are replaced with HTML numeric character references
+#[cfg(feature = "encoding")]
+fn transcode(buf: Vec<u8>, label: &str) -> Result<Vec<u8>> {
+    let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| Error::new(Cause::BadEncoding(String::from(label))))?;
+    let text = String::from_utf8(buf)?;
+    let (bytes, _, unmappable) = encoding.encode(&text);
+    if unmappable {
+        log::warn!(
+            "Some characters can't be represented in {}; they've been replaced.",
+            encoding.name()
+        );
+    }
+    Ok(bytes.into_owned())
+}
+
+#[cfg(not(feature = "encoding"))]
+fn transcode(_buf: Vec<u8>, label: &str) -> Result<Vec<u8>> {
+    Err(Error::new(Cause::NoEncoding(String::from(label))))
+}
+
+#[cfg(all(test, feature = "encoding"))]
+mod transcode_tests {
+
+    /// Latin-1 should survive, anything else should be replaced
+    #[test]
+    fn latin1() -> Result<(), String> {
+        use super::*;
+        let buf =
+            transcode(Vec::from("Björk ☃"), "windows-1252").map_err(|err| format!("{}", err))?;
+        assert_eq!(buf, b"Bj\xf6rk &#9731;".to_vec());
+        assert!(check_encoding("no-such-encoding").is_err());
+        Ok(())
+    }
+}
+
+/// A writer that discards its input, keeping count of the bytes written; lets us report the size
+/// of the output a dry run would have produced without writing it
+struct ByteCounter {
+    count: usize,
+}
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                           find logic                                           //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Search your Winamp music library for tracks any of whose textual attributes contain `needle`;
+/// print the primary index record number & filename of each to stdout. Return the number of
+/// matching tracks.
+pub fn find(
+    idx: &Path,
+    dat: &Path,
+    needle: &str,
+    case_sensitive: bool,
+    opts: &ParseOptions,
+) -> Result<usize> {
+    let mut nmatch = 0;
+    for t in tracks(idx, dat, opts)? {
+        let (i, t) = t?;
+        if t.contains(needle, case_sensitive) {
+            println!("{}: {}", i, t.filename().display());
+            nmatch += 1;
+        }
+    }
+    debug!("Found {} matching tracks.", nmatch);
+    Ok(nmatch)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                           diff logic                                           //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+// Tracks are compared by way of their JSON representations, so diffs need the `serde` feature.
+
+/// One attribute of a track that differs between two libraries; an attribute present in only one
+/// of them will be `None` in the other
+#[cfg(feature = "serde")]
+#[derive(Debug, Serialize)]
+pub struct AttrChange {
+    pub attr: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+#[cfg(feature = "serde")]
+impl std::fmt::Display for AttrChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let show = |x: &Option<serde_json::Value>| match x {
+            Some(x) => format!("{}", x),
+            None => String::from("(none)"),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.attr,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+/// A track present in both libraries, but with differing attributes
+#[cfg(feature = "serde")]
+#[derive(Debug, Serialize)]
+pub struct TrackChange {
+    /// The track's identity key (its filename, by default)
+    pub key: AttrValue,
+    pub changes: Vec<AttrChange>,
+}
+
+/// The differences between two Winamp music libraries, with tracks matched up by some identity key
+/// (filename, by default)
+#[cfg(feature = "serde")]
+#[derive(Debug, Default, Serialize)]
+pub struct LibraryDiff {
+    /// Keys of tracks in the second library, but not the first
+    pub added: Vec<AttrValue>,
+    /// Keys of tracks in the first library, but not the second
+    pub removed: Vec<AttrValue>,
+    /// Tracks in both libraries whose attributes differ
+    pub changed: Vec<TrackChange>,
+}
+
+#[cfg(feature = "serde")]
+impl LibraryDiff {
+    /// Return true if the two libraries are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::fmt::Display for LibraryDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for key in &self.added {
+            writeln!(f, "+ {}", key)?;
+        }
+        for key in &self.removed {
+            writeln!(f, "- {}", key)?;
+        }
+        for change in &self.changed {
+            writeln!(f, "~ {}", change.key)?;
+            for attr in &change.changes {
+                writeln!(f, "    {}", attr)?;
+            }
+        }
+        write!(
+            f,
+            "{} added, {} removed, {} changed",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )
+    }
+}
+
+/// Read all the [`Track`]s in a library, keyed by attribute `key`; tracks lacking that attribute
+/// are dropped
+///
+/// [`Track`]: tracks/struct.Track.html
+#[cfg(feature = "serde")]
+fn tracks_by_key(
+    idx: &Path,
+    dat: &Path,
+    key: &TrackAttrs,
+    opts: &ParseOptions,
+) -> Result<HashMap<AttrValue, Track>> {
+    let mut trks: HashMap<AttrValue, Track> = HashMap::new();
+    let mut nkeyless: usize = 0;
+    for t in tracks(idx, dat, opts)? {
+        let (i, t) = t?;
+        match t.attr_value(key) {
+            Some(k) => {
+                if trks.insert(k.clone(), t).is_some() {
+                    debug!("Record {} duplicates {}.", i, k);
+                }
+            }
+            None => nkeyless += 1,
+        }
+    }
+    if nkeyless > 0 {
+        log::warn!(
+            "Ignoring {} tracks in {} lacking the key attribute.",
+            nkeyless,
+            dat.display()
+        );
+    }
+    Ok(trks)
+}
+
+/// Compare two versions of a track attribute-by-attribute
+///
+/// [`Track`] only exposes its attributes through serialization, so that's how we get at them: as
+/// entries in a JSON object, from which absent attributes are omitted.
+///
+/// [`Track`]: tracks/struct.Track.html
+#[cfg(feature = "serde")]
+fn diff_tracks(old: &Track, new: &Track) -> Result<Vec<AttrChange>> {
+    let as_map = |t: &Track| -> Result<serde_json::Map<String, serde_json::Value>> {
+        // A struct always serializes to an object
+        match serde_json::to_value(t)? {
+            serde_json::Value::Object(map) => Ok(map),
+            _ => Ok(serde_json::Map::new()),
+        }
+    };
+    let old = as_map(old)?;
+    let mut new = as_map(new)?;
+    let mut changes: Vec<AttrChange> = Vec::new();
+    for (attr, val) in old {
+        let other = new.remove(&attr);
+        if other.as_ref() != Some(&val) {
+            changes.push(AttrChange {
+                attr: attr,
+                old: Some(val),
+                new: other,
+            });
+        }
+    }
+    for (attr, val) in new {
+        changes.push(AttrChange {
+            attr: attr,
+            old: None,
+            new: Some(val),
+        });
+    }
+    changes.sort_by(|lhs, rhs| lhs.attr.cmp(&rhs.attr));
+    Ok(changes)
+}
+
+/// Compare two Winamp music libraries (two backups of the same library, say), matching up their
+/// tracks by attribute `key` (typically [`TrackAttrs::Filename`])
+///
+/// [`TrackAttrs::Filename`]: tracks/enum.TrackAttrs.html#variant.Filename
+#[cfg(feature = "serde")]
+pub fn diff(
+    a_idx: &Path,
+    a_dat: &Path,
+    b_idx: &Path,
+    b_dat: &Path,
+    key: &TrackAttrs,
+    opts: &ParseOptions,
+) -> Result<LibraryDiff> {
+    let old = tracks_by_key(a_idx, a_dat, key, opts)?;
+    let mut new = tracks_by_key(b_idx, b_dat, key, opts)?;
+    let mut diff = LibraryDiff::default();
+    for (k, t) in old {
+        match new.remove(&k) {
+            Some(other) if other == t => (),
+            Some(other) => {
+                let changes = diff_tracks(&t, &other)?;
+                if !changes.is_empty() {
+                    diff.changed.push(TrackChange {
+                        key: k,
+                        changes: changes,
+                    });
+                }
+            }
+            None => diff.removed.push(k),
+        }
+    }
+    diff.added = new.into_keys().collect();
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort_by(|lhs, rhs| lhs.key.cmp(&rhs.key));
+    Ok(diff)
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                          info logic                                            //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Format a count of seconds as "[D days, ]HH:MM:SS"
+fn format_duration(secs: i64) -> String {
+    let days = secs / 86400;
+    let hms = format!(
+        "{:02}:{:02}:{:02}",
+        (secs % 86400) / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    );
+    if days > 0 {
+        format!("{} days, {}", days, hms)
+    } else {
+        hms
+    }
+}
+
+/// Format a count of seconds since the Unix epoch as "YYYY-MM-DD HH:MM:SS UTC"
+fn format_datetime(t: i32) -> String {
+    // Convert days since the epoch to a civil date per Howard Hinnant's `civil_from_days'
+    // <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let t = t as i64;
+    let z = t.div_euclid(86400) + 719468;
+    let secs = t.rem_euclid(86400);
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        y,
+        m,
+        d,
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
+/// Describe the shape of exported tracks as a (pretty-printed) JSON Schema: each attribute's
+/// name, type & whether it may be absent
+#[cfg(feature = "schema")]
+pub fn track_schema() -> Result<String> {
+    Ok(serde_json::to_string_pretty(&schemars::schema_for!(Track))?)
+}
+
+#[cfg(not(feature = "schema"))]
+pub fn track_schema() -> Result<String> {
+    Err(Error::new(Cause::NoSchema))
+}
+
+#[cfg(all(test, feature = "schema"))]
+mod schema_tests {
+
+    /// The schema should name attributes as they're serialized, & require only the filename
+    #[test]
+    fn track() -> Result<(), String> {
+        use super::*;
+        let schema = track_schema().map_err(|err| format!("{}", err))?;
+        let schema: serde_json::Value =
+            serde_json::from_str(&schema).map_err(|err| format!("{}", err))?;
+        let props = schema["properties"]
+            .as_object()
+            .ok_or_else(|| String::from("no properties"))?;
+        assert!(props.contains_key("filename"));
+        assert!(props.contains_key("track_number"));
+        assert!(props.contains_key("last_played"));
+        assert!(!props.contains_key("trackno"));
+        assert_eq!(schema["required"], serde_json::json!(["filename"]));
+        Ok(())
+    }
+}
+
+/// Aggregate statistics describing a Winamp music library
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// Number of tracks
+    pub tracks: usize,
+    /// Number of distinct artists
+    pub artists: usize,
+    /// Number of distinct albums
+    pub albums: usize,
+    /// Number of distinct genres
+    pub genres: usize,
+    /// Names of the indicies defined on the table
+    pub indices: Vec<String>,
+    /// Total play time of all tracks (that have a length), in seconds
+    pub total_length: i64,
+    /// Number of tracks with a length
+    pub tracks_with_length: usize,
+    /// Earliest `dateadded`, if any, in seconds since the Unix epoch
+    pub first_added: Option<i32>,
+    /// Latest `dateadded`, if any, in seconds since the Unix epoch
+    pub last_added: Option<i32>,
+    /// Our best guess at the generation of Winamp that wrote the table
+    pub variant: NdeVariant,
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "format:         {}", self.variant)?;
+        writeln!(f, "tracks:         {}", self.tracks)?;
+        writeln!(f, "artists:        {}", self.artists)?;
+        writeln!(f, "albums:         {}", self.albums)?;
+        writeln!(f, "genres:         {}", self.genres)?;
+        if self.indices.is_empty() {
+            writeln!(f, "indices:        n/a")?;
+        } else {
+            writeln!(f, "indices:        {}", self.indices.join(", "))?;
+        }
+        writeln!(f, "total length:   {}", format_duration(self.total_length))?;
+        if self.tracks_with_length > 0 {
+            writeln!(
+                f,
+                "average length: {}",
+                format_duration(self.total_length / self.tracks_with_length as i64)
+            )?;
+        }
+        match (self.first_added, self.last_added) {
+            (Some(first), Some(last)) => write!(
+                f,
+                "date added:     {} to {}",
+                format_datetime(first),
+                format_datetime(last)
+            ),
+            _ => write!(f, "date added:     n/a"),
+        }
+    }
+}
+
+/// Summarize your Winamp music library: count the tracks, distinct artists, albums & genres, total
+/// up the play time, find the range of dates on which tracks were added & list the table's
+/// indicies.
+pub fn summarize(idx: &Path, dat: &Path, opts: &ParseOptions) -> Result<Summary> {
+    let mut summary = Summary::default();
+    let mut artists: HashSet<String> = HashSet::new();
+    let mut albums: HashSet<String> = HashSet::new();
+    let mut genres: HashSet<String> = HashSet::new();
+
+    let mut trks = tracks(idx, dat, opts)?;
+    summary.indices = trks.index_definitions()?.iter().map(|x| x.name()).collect();
+    summary.variant = trks.variant();
+    for t in trks {
+        let (_, t) = t?;
+        summary.tracks += 1;
+        if let Some(artist) = t.artist() {
+            artists.insert(String::from(artist));
+        }
+        if let Some(album) = t.album() {
+            albums.insert(String::from(album));
+        }
+        if let Some(genre) = t.genre() {
+            genres.insert(String::from(genre));
+        }
+        if let Some(length) = t.length() {
+            summary.total_length += length as i64;
+            summary.tracks_with_length += 1;
+        }
+        if let Some(added) = t.date_added() {
+            summary.first_added = Some(summary.first_added.map_or(added, |x| x.min(added)));
+            summary.last_added = Some(summary.last_added.map_or(added, |x| x.max(added)));
+        }
+    }
+
+    summary.artists = artists.len();
+    summary.albums = albums.len();
+    summary.genres = genres.len();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod info_tests {
+
+    /// Check our hand-rolled date & duration formatting
+    #[test]
+    fn formatting() {
+        use super::*;
+        assert_eq!(format_datetime(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_datetime(951782400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_datetime(1234567890), "2009-02-13 23:31:30 UTC");
+        assert_eq!(format_duration(59), "00:00:59");
+        assert_eq!(format_duration(90061), "1 days, 01:01:01");
+    }
+
+    /// An empty library shouldn't provoke a division by zero
+    #[test]
+    fn empty() {
+        use super::*;
+        let summary = Summary::default();
+        let text = format!("{}", summary);
+        assert!(text.contains("tracks:         0"));
+        assert!(!text.contains("average"));
+    }
+}
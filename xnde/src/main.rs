@@ -24,9 +24,12 @@
 mod vars;
 
 use env_logger::Env;
-use xnde::{dump, export, DumpFormat, ExportFormat};
+use xnde::{
+    diff, dump, dump_indices, error_from, export, export_tables, field_stats, find, summarize,
+    track_schema, verify, DumpOptions, ExportOptions, Format, ParseOptions,
+};
 
-use clap::{value_parser, Arg, Command};
+use clap::{parser::ValueSource, value_parser, Arg, ArgAction, ArgMatches, Command};
 
 // There are many crates for deriving a Display implementation; I tried
 // [withoutboats](https://boats.gitlab.io/blog/)'s
@@ -36,6 +39,7 @@ use clap::{value_parser, Arg, Command};
 use parse_display::Display;
 
 use std::convert::TryFrom;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
@@ -63,6 +67,12 @@ use std::path::{Path, PathBuf};
 // I proceed by hand-coding an Error implementation that satisfies all these conditions, with an
 // eye toward automating the process via Rust macros.
 
+/// The status with which xnde exits when a command ran successfully, but with a negative result:
+/// `diff` found differences, `find` found no matches or `verify` found discrepancies. This is
+/// distinct from the statuses used for errors, so that a script can tell "changed" from
+/// "couldn't read the file".
+const EXIT_NEGATIVE: i32 = 3;
+
 #[derive(Debug, Display)]
 enum Cause {
     /// An error in another crate or module took place during this module's operation
@@ -76,16 +86,20 @@ enum Cause {
     /// No sub-command specified
     #[display("No sub-command given.")]
     NoSubCommand,
+    /// Neither explicit index & data files nor --dir/--table given
+    #[display("No NDE table given; name the index & data files, or use --dir and/or --table.")]
+    NoTable,
+    /// A diff was requested in a format that can't express one (itunes, e.g.)
+    #[display("Can't write a diff in {} format.")]
+    NoDiffIn(Format),
 }
 
-#[derive(Debug, Display)]
-#[display("{cause} Source (if any): {source} Stack trace (if any): {trace}")]
+#[derive(Debug)]
 struct Error {
     /// Enumerated status code-- perhaps this is a holdover from my C++ days, but I've found that
     /// programmatic error-handling is facilitated by status codes, not text. Textual messages
     /// should be synthesized from other information only when it is time to present the error to a
     /// human (in a log file, say).
-    #[display("XNDE error {}.")]
     cause: Cause,
     // This is an Option that may contain a Box containing something that implements
     // std::error::Error.  It is still unclear to me how this satisfies the lifetime bound in
@@ -93,13 +107,9 @@ struct Error {
     // lifetime. There is a discussion of this at
     // <https://users.rust-lang.org/t/what-does-it-mean-to-return-dyn-error-static/37619/6>,
     // but at the time of this writing, i cannot follow it.
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("XNDE error caused by {:#?}.")]
     source: Option<Box<dyn std::error::Error>>,
     /// Optional backtrace
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("backtrace: {:#?}.")]
-    trace: Option<backtrace::Backtrace>,
+    trace: Option<xnde::Backtrace>,
 }
 
 impl Error {
@@ -108,11 +118,53 @@ impl Error {
         Error {
             cause: cause,
             source: None,
-            trace: Some(backtrace::Backtrace::new()),
+            trace: xnde::capture_backtrace(),
+        }
+    }
+    /// The status with which xnde should exit on this error: 2 for usage errors (no sub-command,
+    /// a bad format name &c), 1 for everything else (I/O & parse errors, mostly); cf.
+    /// [`EXIT_NEGATIVE`] for commands that succeed but come up empty-handed
+    fn exit_code(&self) -> i32 {
+        match self.cause {
+            Cause::NoSubCommand | Cause::NoTable | Cause::NoDiffIn(_) => 2,
+            Cause::Other => {
+                let source = self.source.as_ref();
+                if let Some(err) = source.and_then(|err| err.downcast_ref::<xnde::Error>()) {
+                    match err.cause() {
+                        xnde::Cause::BadFormat(_)
+                        | xnde::Cause::BadEncoding(_)
+                        | xnde::Cause::NoEncoding(_)
+                        | xnde::Cause::StdinTwice
+                        | xnde::Cause::CantAppend(_)
+                        | xnde::Cause::NoMetaIn(_) => 2,
+                        _ => 1,
+                    }
+                } else if let Some(err) =
+                    source.and_then(|err| err.downcast_ref::<xnde::tracks::Error>())
+                {
+                    match err.cause() {
+                        xnde::tracks::Cause::BadSortKey(_)
+                        | xnde::tracks::Cause::BadKey(_)
+                        | xnde::tracks::Cause::BadDate(_)
+                        | xnde::tracks::Cause::BadNullAs(_)
+                        | xnde::tracks::Cause::BadColumnAlias(_) => 2,
+                        _ => 1,
+                    }
+                } else {
+                    1
+                }
+            }
+            Cause::Internal => 1,
         }
     }
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        xnde::fmt_error(f, &self.cause, self, self.trace.as_ref())
+    }
+}
+
 impl std::error::Error for Error {
     /// The lower-level source of this error, if any.
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
@@ -128,33 +180,61 @@ impl std::error::Error for Error {
     }
 }
 
-// TODO(sp1ff): there *must* be a way to do this generically, but doing it for all T conflicts
-// with the std implementation of std::convert::From<T> for T. Maybe this could be my first macro?
-impl std::convert::From<xnde::Error> for Error {
-    fn from(err: xnde::Error) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
+// Doing this generically, for all T, would conflict with the std implementation of
+// std::convert::From<T> for T, hence the macro.
+error_from!(xnde::Error);
+error_from!(xnde::tracks::Error);
+error_from!(log::SetLoggerError);
+error_from!(serde_json::error::Error);
+error_from!(serde_lexpr::error::Error);
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                         table location                                         //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Work out the path to one of a table's files: if the sub-command was given it explicitly (as
+/// positional argument `arg`), use that. Otherwise, derive it from the global --dir & --table
+/// options as `<dir>/<table>.<ext>`, defaulting to the current directory & the `main' table,
+/// respectively (but at least one of them must be given).
+fn table_path(
+    matches: &ArgMatches,
+    subm: &ArgMatches,
+    arg: &str,
+    ext: &str,
+) -> Result<PathBuf, Error> {
+    if let Some(path) = subm.get_one::<PathBuf>(arg) {
+        return Ok(path.clone());
     }
+    let dir = matches.get_one::<PathBuf>("dir");
+    let table = matches.get_one::<String>("table");
+    if dir.is_none() && table.is_none() {
+        return Err(Error::new(Cause::NoTable));
+    }
+    let dir = dir.map_or(Path::new("."), |p| p.as_path());
+    let table = table.map_or("main", |t| t.as_str());
+    Ok(dir.join(format!("{}.{}", table, ext)))
 }
 
-impl std::convert::From<log::SetLoggerError> for Error {
-    fn from(err: log::SetLoggerError) -> Self {
-        Error {
-            cause: Cause::Other,
-            source: Some(Box::new(err)),
-            trace: Some(backtrace::Backtrace::new()),
-        }
-    }
+/// Work out the index & data files on which `subm` is to operate
+fn table_paths(matches: &ArgMatches, subm: &ArgMatches) -> Result<(PathBuf, PathBuf), Error> {
+    Ok((
+        table_path(matches, subm, "index", "idx")?,
+        table_path(matches, subm, "data", "dat")?,
+    ))
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 //                                          The Big Tuna                                          //
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
-fn main() -> Result<(), Error> {
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(err.exit_code());
+    }
+}
+
+fn run() -> Result<(), Error> {
     use vars::{AUTHOR, VERSION};
     let matches = Command::new("xnde")
         .version(VERSION)
@@ -173,6 +253,141 @@ against the Win32 API and (seemingly) ported to MacOS, but never Linux.",
                 .required(false)
                 .num_args(0),
         )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Log only warnings & errors (no progress messages); handy in scripts")
+                .required(false)
+                .num_args(0)
+                .conflicts_with("verbose"),
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .help("Format for log messages: `text' or `json' (one object per line)")
+                .required(false)
+                .num_args(1)
+                .value_parser(["text", "json"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("mmap")
+                .long("mmap")
+                .help("Memory-map the data file (needs the `mmap' feature)")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("buffer-size")
+                .long("buffer-size")
+                .help("Read the data file through a buffer of this many bytes (zero for none)")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("columns-only-first-n")
+                .long("columns-only-first-n")
+                .help("Give up on a table whose column definitions run past this many columns")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("guess-utf16")
+                .long("guess-utf16")
+                .help("Decode strings that look like UTF-16LE as such, even without a BOM")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("strict-utf8")
+                .long("strict-utf8")
+                .help("Decode all strings as UTF-8, even those starting with a UTF-16 BOM")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("omit-empty")
+                .long("omit-empty")
+                .help("Treat empty strings as missing attributes, omitting them from exports")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("map")
+                .long("map")
+                .help(
+                    "Map a column to a track attribute, as COLUMN=attribute (`ARTIST_V2=artist',
+e.g.); may be given more than once",
+                )
+                .required(false)
+                .num_args(1)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("check-overruns")
+                .long("check-overruns")
+                .help("Fail on any field that runs into the next field in its record")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("resync")
+                .long("resync")
+                .help("Search near each indexed record offset for the record's true start")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("big-endian")
+                .long("big-endian")
+                .help("Read integers as big-endian (for files from PowerPC-era Macs, e.g.)")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("length-units")
+                .long("length-units")
+                .help("Units of LENGTH fields (`auto' takes implausibly long lengths as millis)")
+                .required(false)
+                .num_args(1)
+                .default_value("auto")
+                .value_parser(["seconds", "millis", "auto"]),
+        )
+        .arg(
+            Arg::new("string-fallback")
+                .long("string-fallback")
+                .help("How to decode strings that aren't valid UTF-8 (`cp1252' needs `encoding')")
+                .required(false)
+                .num_args(1)
+                .default_value("error")
+                .value_parser(["error", "cp1252", "lossy"]),
+        )
+        .arg(
+            Arg::new("max-field-size")
+                .long("max-field-size")
+                .help("Refuse to read any field claiming to be larger than this many bytes")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("dir")
+                .long("dir")
+                .help("Directory holding the NDE table (in lieu of naming its index & data files)")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("table")
+                .long("table")
+                .help("Name of the NDE table (in lieu of naming its files); defaults to `main'")
+                .required(false)
+                .num_args(1),
+        )
         .subcommand(
             Command::new("dump")
                 .about("dump the contents of a Winamp Music Library")
@@ -188,19 +403,82 @@ contents to stdout. Useful for exploring & trouble-shooting.",
                         .num_args(1)
                         .default_value("display"),
                 )
+                .arg(
+                    Arg::new("skip-meta")
+                        .long("skip-meta")
+                        .help("Skip the records defining the table's columns & indicies")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("extract-binary")
+                        .long("extract-binary")
+                        .help("Write binary fields to files in this directory, rather than inline")
+                        .required(false)
+                        .num_args(1)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("tolerate-unknown-types")
+                        .long("tolerate-unknown-types")
+                        .help("On an unknown field type, warn & skip ahead to the next field")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("pretty-bytes")
+                        .long("pretty-bytes")
+                        .help("Follow file sizes with a human-readable size (`4.2 MiB', e.g.)")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("max-records")
+                        .long("max-records")
+                        .visible_alias("head")
+                        .help("Stop after dumping this many records")
+                        .required(false)
+                        .num_args(1)
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("tail")
+                        .long("tail")
+                        .help("Dump only the last this many records")
+                        .required(false)
+                        .num_args(1)
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("record")
+                        .long("record")
+                        .help("Dump only this record (numbered as by `find')")
+                        .required(false)
+                        .num_args(1)
+                        .value_parser(value_parser!(usize))
+                        .conflicts_with_all(["max-records", "tail"]),
+                )
+                .arg(
+                    Arg::new("indices")
+                        .long("indices")
+                        .short('i')
+                        .help("Dump the index file's indicies rather than the data file's records")
+                        .required(false)
+                        .num_args(0),
+                )
                 .arg(
                     Arg::new("index")
-                        .help("NDE index file (`main.idx', e.g.)")
+                        .help("NDE index file (`main.idx', e.g.; `-' for stdin)")
                         .index(1)
                         .requires("data")
-                        .required(true)
+                        .required(false)
                         .value_parser(value_parser!(std::path::PathBuf)),
                 )
                 .arg(
                     Arg::new("data")
-                        .help("corresponding NDE data file (`main.dat', e.g.)")
+                        .help("corresponding NDE data file (`main.dat', e.g.; `-' for stdin)")
                         .index(2)
-                        .required(true)
+                        .required(false)
                         .value_parser(value_parser!(std::path::PathBuf)),
                 ),
         )
@@ -228,55 +506,442 @@ be written",
                     Arg::new("format")
                         .long("format")
                         .short('f')
-                        .help("Format to which your Music Library shall be serialized")
+                        .help(
+                            "Format to which your Music Library shall be serialized; `auto' guesses
+from the output file's extension, falling back to `sexp'",
+                        )
                         .num_args(1)
                         // TODO(sp1ff): add more output formats
-                        .default_value("sexp"), // .value_name("FORMAT")
+                        .default_value("auto"), // .value_name("FORMAT")
+                )
+                .arg(
+                    Arg::new("added-since")
+                        .long("added-since")
+                        .help("Only export tracks added on or after this date (YYYY-MM-DD)")
+                        .required(false)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("added-until")
+                        .long("added-until")
+                        .help("Only export tracks added before this date")
+                        .required(false)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("played-since")
+                        .long("played-since")
+                        .help("Only export tracks last played on or after this date")
+                        .required(false)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("played-until")
+                        .long("played-until")
+                        .help("Only export tracks last played before this date")
+                        .required(false)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("dedup")
+                        .long("dedup")
+                        .short('d')
+                        .help("Keep only the first track for any given key (cf. --key)")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .short('k')
+                        .help("Attribute identifying a track (`filename', `tuid2', e.g.)")
+                        .num_args(1)
+                        .default_value("filename"),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .short('n')
+                        .help("Parse & filter, but write nothing; report what would be written")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("output-encoding")
+                        .long("output-encoding")
+                        .help(
+                            "Transcode output to this encoding (`latin1', e.g.; needs `encoding')",
+                        )
+                        .required(false)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("null-as")
+                        .long("null-as")
+                        .help("Write missing attributes as `null', `empty' or `sentinel:TEXT'")
+                        .required(false)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("skip-errors")
+                        .long("skip-errors")
+                        .help("Log & skip records that can't be parsed, rather than giving up")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("max-bytes")
+                        .long("max-bytes")
+                        .help("Give up after reading more than this many bytes of the data file")
+                        .required(false)
+                        .num_args(1)
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(
+                    Arg::new("max-records")
+                        .long("max-records")
+                        .help("Give up after reading more than this many records")
+                        .required(false)
+                        .num_args(1)
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("append")
+                        .long("append")
+                        .short('a')
+                        .help("Append to the output file rather than replacing it (ndjson only)")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("include-meta")
+                        .long("include-meta")
+                        .help("Also export the records defining the table's columns & indicies")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("raw")
+                        .long("raw")
+                        .help("Export every record as raw fields, rather than as tracks")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("all-tables")
+                        .long("all-tables")
+                        .help(
+                            "Export every table in --dir, each to its own file in the directory
+named by -o (the current directory, by default)",
+                        )
+                        .required(false)
+                        .num_args(0)
+                        .conflicts_with("index"),
+                )
+                .arg(
+                    Arg::new("sort-by")
+                        .long("sort-by")
+                        .short('s')
+                        .help("Sort tracks by these comma-separated attributes (`artist,album')")
+                        .required(false)
+                        .num_args(1),
+                )
+                .arg(
+                    Arg::new("threads")
+                        .long("threads")
+                        .short('t')
+                        .help("Parse records on this many threads (needs the `parallel' feature)")
+                        .num_args(1)
+                        .default_value("1")
+                        .value_parser(value_parser!(usize)),
                 )
                 .arg(
                     Arg::new("index")
-                        .help("NDE index file (`main.idx', e.g.)")
+                        .help("NDE index file (`main.idx', e.g.; `-' for stdin)")
                         .index(1)
                         .requires("data")
-                        .required(true)
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("data")
+                        .help("corresponding NDE data file (`main.dat', e.g.; `-' for stdin)")
+                        .index(2)
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("find")
+                .about("find tracks in a Winamp Music Library")
+                .long_about(
+                    "Walk the contents of the NDE 'main' table, printing the record number &
+filename of each track any of whose textual attributes (filename, artist, title, album &c)
+contain a given substring. The search is case-insensitive unless --case-sensitive is given.
+Exits with status 3 if no tracks match (errors exit with status 1, or 2 for usage errors).",
+                )
+                .arg(
+                    Arg::new("case-sensitive")
+                        .long("case-sensitive")
+                        .short('c')
+                        .help("Match the substring case-sensitively")
+                        .required(false)
+                        .num_args(0),
+                )
+                .arg(
+                    Arg::new("substring")
+                        .help("text for which to search")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("index")
+                        .help("NDE index file (`main.idx', e.g.; `-' for stdin)")
+                        .index(2)
+                        .requires("data")
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("data")
+                        .help("corresponding NDE data file (`main.dat', e.g.; `-' for stdin)")
+                        .index(3)
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("summarize a Winamp Music Library")
+                .long_about(
+                    "Walk the contents of the NDE 'main' table & print a summary: the number of
+tracks, the number of distinct artists, albums & genres, the total play time & the range of dates
+on which tracks were added.",
+                )
+                .arg(
+                    Arg::new("index")
+                        .help("NDE index file (`main.idx', e.g.; `-' for stdin)")
+                        .index(1)
+                        .requires("data")
+                        .required(false)
                         .value_parser(value_parser!(std::path::PathBuf)),
                 )
                 .arg(
                     Arg::new("data")
-                        .help("corresponding NDE data file (`main.dat', e.g.)")
+                        .help("corresponding NDE data file (`main.dat', e.g.; `-' for stdin)")
                         .index(2)
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("cross-check the indicies in a Winamp Music Library")
+                .long_about(
+                    "Read all the indicies in an NDE index file & check that each references
+exactly the same set of records as the primary index (albeit in a different order). Print any
+offsets present in one index but not another & exit with status 3 if any are found (errors exit
+with status 1, or 2 for usage errors).",
+                )
+                .arg(
+                    Arg::new("index")
+                        .help("NDE index file (`main.idx', e.g.; `-' for stdin)")
+                        .index(1)
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("compare two Winamp Music Libraries")
+                .long_about(
+                    "Read the tracks from two NDE 'main' tables (two backups of the same library,
+say), match them up by filename (or whatever attribute is given by --key) & print the tracks that
+were added, removed or changed, along with the attributes that changed. Exits with status 3 if there
+are any differences (errors exit with status 1, or 2 for usage errors).",
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .help("Format in which the differences shall be printed")
+                        .num_args(1)
+                        .default_value("display"),
+                )
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .short('k')
+                        .help("Attribute identifying a track (`filename', `tuid2', e.g.)")
+                        .num_args(1)
+                        .default_value("filename"),
+                )
+                .arg(
+                    Arg::new("old-index")
+                        .help("NDE index file for the first library")
+                        .index(1)
+                        .required(true)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("old-data")
+                        .help("NDE data file for the first library")
+                        .index(2)
+                        .required(true)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("new-index")
+                        .help("NDE index file for the second library")
+                        .index(3)
+                        .required(true)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("new-data")
+                        .help("NDE data file for the second library")
+                        .index(4)
                         .required(true)
                         .value_parser(value_parser!(std::path::PathBuf)),
                 ),
         )
+        .subcommand(
+            Command::new("field-stats")
+                .about("tally the field types in a Winamp Music Library")
+                .long_about(
+                    "Walk every record in an NDE table & count the fields of each type therein,
+printing the results, most common first. Useful for finding out what's in your library that xnde
+can't yet handle.",
+                )
+                .arg(
+                    Arg::new("index")
+                        .help("NDE index file (`main.idx', e.g.; `-' for stdin)")
+                        .index(1)
+                        .requires("data")
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                )
+                .arg(
+                    Arg::new("data")
+                        .help("corresponding NDE data file (`main.dat', e.g.; `-' for stdin)")
+                        .index(2)
+                        .required(false)
+                        .value_parser(value_parser!(std::path::PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("supported")
+                .about("list the field types xnde can parse")
+                .long_about(
+                    "List every NDE field type, noting whether xnde can parse fields of that type
+or will just carry their raw bytes along. Handy for deciding whether xnde can make sense of your
+library (`xnde field-stats' will tell you which types it contains).",
+                ),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("describe the tracks written by export")
+                .long_about(
+                    "Print a JSON Schema describing each track as written by `xnde export': the
+name & type of every attribute, & whether it may be absent. Requires that xnde be built with the
+`schema' feature.",
+                ),
+        )
         .get_matches();
 
-    env_logger::init_from_env(Env::default().filter_or(
+    let mut builder = env_logger::Builder::from_env(Env::default().filter_or(
         "RUST_LOG",
         if matches.get_flag("verbose") {
             "debug"
+        } else if matches.get_flag("quiet") {
+            "warn"
         } else {
             "info"
         },
     ));
+    if matches.get_one::<String>("log-format").map(|s| s.as_str()) == Some("json") {
+        builder.format(|buf, record| {
+            writeln!(
+                buf,
+                "{}",
+                serde_json::json!({
+                    "timestamp": format!("{}", buf.timestamp()),
+                    "level": format!("{}", record.level()),
+                    "message": format!("{}", record.args()),
+                })
+            )
+        });
+    }
+    builder.try_init()?;
+
+    let mut parse_opts = ParseOptions::new()
+        .byte_order(if matches.get_flag("big-endian") {
+            xnde::fields::ByteOrder::Big
+        } else {
+            xnde::fields::ByteOrder::Little
+        })
+        .check_overruns(matches.get_flag("check-overruns"))
+        .guess_utf16(matches.get_flag("guess-utf16"))
+        .strict_utf8(matches.get_flag("strict-utf8"))
+        .resync_records(matches.get_flag("resync"))
+        .omit_empty_strings(matches.get_flag("omit-empty"))
+        .length_units(
+            match matches
+                .get_one::<String>("length-units")
+                .map(|s| s.as_str())
+            {
+                Some("seconds") => xnde::fields::LengthUnits::Seconds,
+                Some("millis") => xnde::fields::LengthUnits::Millis,
+                _ => xnde::fields::LengthUnits::Auto,
+            },
+        )
+        .string_fallback(
+            match matches
+                .get_one::<String>("string-fallback")
+                .map(|s| s.as_str())
+            {
+                Some("cp1252") => xnde::fields::StringFallback::Cp1252,
+                Some("lossy") => xnde::fields::StringFallback::Lossy,
+                _ => xnde::fields::StringFallback::Error,
+            },
+        );
+    if let Some(size) = matches.get_one::<usize>("max-field-size") {
+        parse_opts = parse_opts.max_field_size(*size);
+    }
+    if let Some(size) = matches.get_one::<usize>("buffer-size") {
+        parse_opts = parse_opts.buffer_size(*size);
+    }
+    if let Some(limit) = matches.get_one::<usize>("columns-only-first-n") {
+        parse_opts = parse_opts.max_columns(*limit);
+    }
+    if let Some(specs) = matches.get_many::<String>("map") {
+        parse_opts = parse_opts.column_aliases(
+            specs
+                .map(|spec| xnde::tracks::parse_column_alias(spec))
+                .collect::<Result<Vec<_>, _>>()?,
+        );
+    }
 
     if let Some(subm) = matches.subcommand_matches("dump") {
         let format = subm
             .get_one::<String>("format")
             .ok_or(Error::new(Cause::Internal))?;
-        // We marked both of these arguments as "required" above, so `clap' *should* have checked
-        // for their presence. That said, I can't bring myself to call `unwrap'.
-        let idx = subm
-            .get_one::<PathBuf>("index")
-            .ok_or(Error::new(Cause::Internal))?;
-        let dat = subm
-            .get_one::<PathBuf>("data")
-            .ok_or(Error::new(Cause::Internal))?;
-        return Ok(dump(
-            Path::new(idx),
-            Path::new(dat),
-            DumpFormat::try_from(format.as_str())?,
-        )?);
+        let idx = table_path(&matches, subm, "index", "idx")?;
+        if subm.get_flag("indices") {
+            return Ok(dump_indices(&idx, &parse_opts)?);
+        }
+        let dat = table_path(&matches, subm, "data", "dat")?;
+        let opts = DumpOptions::new()
+            .mmap(matches.get_flag("mmap"))
+            .skip_meta(subm.get_flag("skip-meta"))
+            .max_records(subm.get_one::<usize>("max-records").copied())
+            .tail(subm.get_one::<usize>("tail").copied())
+            .record(subm.get_one::<usize>("record").copied())
+            .tolerate_unknown_types(subm.get_flag("tolerate-unknown-types"))
+            .extract_binary(subm.get_one::<PathBuf>("extract-binary").cloned())
+            .pretty_bytes(subm.get_flag("pretty-bytes"))
+            .parse_options(parse_opts);
+        return Ok(dump(&idx, &dat, Format::try_from(format.as_str())?, &opts)?);
     } else if let Some(subm) = matches.subcommand_matches("export") {
         // We marked both of these as having default values, so `value_of` should never return
         // Err, here. That said, I can't bring myself to call `unwrap'.
@@ -286,21 +951,163 @@ be written",
         let output = subm
             .get_one::<PathBuf>("output")
             .ok_or(Error::new(Cause::Internal))?;
-        // We marked both of these arguments as "required" above... yadayadayada.
-        let idx = subm
-            .get_one::<PathBuf>("index")
-            .ok_or(Error::new(Cause::Internal))?;
-        let dat = subm
-            .get_one::<PathBuf>("data")
+        let format = match format.as_str() {
+            "auto" => Format::from_extension(output).unwrap_or(Format::Sexp),
+            _ => Format::try_from(format.as_str())?,
+        };
+        let threads = subm
+            .get_one::<usize>("threads")
             .ok_or(Error::new(Cause::Internal))?;
+        let sort_by = match subm.get_one::<String>("sort-by") {
+            Some(spec) => xnde::tracks::parse_sort_keys(spec)?,
+            None => Vec::new(),
+        };
+        let null_as = match subm.get_one::<String>("null-as") {
+            Some(spec) => Some(xnde::tracks::NullAs::try_from(spec.as_str())?),
+            None => None,
+        };
+        let key = match subm.get_one::<String>("key") {
+            Some(name) => xnde::tracks::parse_key(name)?,
+            None => xnde::tracks::TrackAttrs::Filename,
+        };
+        let date = |arg: &str| -> Result<Option<i64>, Error> {
+            match subm.get_one::<String>(arg) {
+                Some(text) => Ok(Some(xnde::tracks::parse_date(text)?)),
+                None => Ok(None),
+            }
+        };
+        let filter = xnde::tracks::TrackFilter {
+            added_since: date("added-since")?,
+            added_until: date("added-until")?,
+            played_since: date("played-since")?,
+            played_until: date("played-until")?,
+        };
+        let opts = ExportOptions::new()
+            .filter(filter)
+            .dedup(if subm.get_flag("dedup") {
+                Some(key)
+            } else {
+                None
+            })
+            .threads(*threads)
+            .mmap(matches.get_flag("mmap"))
+            .sort_by(sort_by)
+            .include_meta(subm.get_flag("include-meta"))
+            .encoding(subm.get_one::<String>("output-encoding").cloned())
+            .append(subm.get_flag("append"))
+            .null_as(null_as)
+            .skip_errors(subm.get_flag("skip-errors"))
+            .raw(subm.get_flag("raw"))
+            .max_bytes(subm.get_one::<u64>("max-bytes").copied())
+            .max_records(subm.get_one::<usize>("max-records").copied())
+            .parse_options(parse_opts);
+        if subm.get_flag("all-tables") {
+            let dir = matches
+                .get_one::<PathBuf>("dir")
+                .map_or(Path::new("."), |p| p.as_path());
+            let out_dir = match subm.value_source("output") {
+                Some(ValueSource::DefaultValue) => Path::new("."),
+                _ => output.as_path(),
+            };
+            export_tables(
+                dir,
+                format,
+                if subm.get_flag("dry-run") {
+                    None
+                } else {
+                    Some(out_dir)
+                },
+                &opts,
+            )?;
+            return Ok(());
+        }
+        let (idx, dat) = table_paths(&matches, subm)?;
         return Ok(export(
-            Path::new(idx),
-            Path::new(dat),
-            ExportFormat::try_from(format.as_str())?,
-            Path::new(output),
+            &idx,
+            &dat,
+            format,
+            if subm.get_flag("dry-run") {
+                None
+            } else {
+                Some(output.as_path())
+            },
+            &opts,
         )?);
+    } else if let Some(subm) = matches.subcommand_matches("find") {
+        let needle = subm
+            .get_one::<String>("substring")
+            .ok_or(Error::new(Cause::Internal))?;
+        let (idx, dat) = table_paths(&matches, subm)?;
+        let nmatch = find(
+            &idx,
+            &dat,
+            needle,
+            subm.get_flag("case-sensitive"),
+            &parse_opts,
+        )?;
+        if nmatch == 0 {
+            std::process::exit(EXIT_NEGATIVE);
+        }
+        return Ok(());
+    } else if let Some(subm) = matches.subcommand_matches("info") {
+        let (idx, dat) = table_paths(&matches, subm)?;
+        println!("{}", summarize(&idx, &dat, &parse_opts)?);
+        return Ok(());
+    } else if let Some(subm) = matches.subcommand_matches("verify") {
+        let idx = table_path(&matches, subm, "index", "idx")?;
+        if verify(&idx, &parse_opts)? != 0 {
+            std::process::exit(EXIT_NEGATIVE);
+        }
+        return Ok(());
+    } else if let Some(subm) = matches.subcommand_matches("diff") {
+        let format = subm
+            .get_one::<String>("format")
+            .ok_or(Error::new(Cause::Internal))?;
+        let format = Format::try_from(format.as_str())?;
+        let path = |arg: &str| -> Result<&PathBuf, Error> {
+            subm.get_one::<PathBuf>(arg)
+                .ok_or(Error::new(Cause::Internal))
+        };
+        let diffs = diff(
+            path("old-index")?,
+            path("old-data")?,
+            path("new-index")?,
+            path("new-data")?,
+            &match subm.get_one::<String>("key") {
+                Some(name) => xnde::tracks::parse_key(name)?,
+                None => xnde::tracks::TrackAttrs::Filename,
+            },
+            &parse_opts,
+        )?;
+        match format {
+            Format::Display => println!("{}", diffs),
+            Format::Sexp => println!("{}", serde_lexpr::to_string(&diffs)?),
+            Format::Json | Format::Ndjson => println!("{}", serde_json::to_string(&diffs)?),
+            _ => return Err(Error::new(Cause::NoDiffIn(format))),
+        }
+        if !diffs.is_empty() {
+            std::process::exit(EXIT_NEGATIVE);
+        }
+        return Ok(());
+    } else if let Some(subm) = matches.subcommand_matches("field-stats") {
+        let (idx, dat) = table_paths(&matches, subm)?;
+        for (ftype, count) in field_stats(&idx, &dat, &parse_opts)? {
+            println!("{:>10} {}", format!("{}", ftype), count);
+        }
+        return Ok(());
+    } else if matches.subcommand_matches("supported").is_some() {
+        for (ftype, supported) in xnde::fields::supported_field_types() {
+            println!(
+                "{:>10} {}",
+                format!("{}", ftype),
+                if supported { "parsed" } else { "unsupported" }
+            );
+        }
+        return Ok(());
+    } else if matches.subcommand_matches("schema").is_some() {
+        println!("{}", track_schema()?);
+        return Ok(());
     } else {
-        // TODO(sp1ff): exit with status 2 here
         Err(Error::new(Cause::NoSubCommand))
     }
 }
@@ -0,0 +1,443 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! Winamp playlists
+//!
+//! # Introduction
+//!
+//! Winamp doesn't keep playlists in NDE tables; it keeps each in an M3U (or M3U8) file of its
+//! own, & lists them all in a "playlist directory", `playlists.xml`, alongside the Music Library's
+//! tables:
+//!
+//! ```xml
+//! <?xml version="1.0" encoding="UTF-16"?>
+//! <playlists playlists="2">
+//! <playlist filename="plf1A2B.m3u8" title="Road Trip" id="{...}" songs="12" seconds="2700"/>
+//! ...
+//! </playlists>
+//! ```
+//!
+//! A playlist file is just a list of paths, so on its own it tells you little about its tracks.
+//! This module resolves each entry against the [`Track`]s in your library, by filename, to
+//! recover your playlists with full metadata.
+//!
+//! # Discussion
+//!
+//! Winamp runs on Windows, so entries are compared as Windows paths: without regard to case, &
+//! with forward slashes taken to be backslashes. An absolute entry has to match a track's
+//! filename exactly (in that sense); a relative entry (which Winamp would resolve against the
+//! playlist's own directory, which we can't know in Winamp's terms) matches the one track whose
+//! filename ends with it, if there is exactly one.
+//!
+//! `playlists.xml` is parsed only as far as picking out each `<playlist>` element's `filename` &
+//! `title` attributes; it's not a general-purpose XML parser.
+//!
+//! [`Track`]: ../tracks/struct.Track.html
+
+use crate::tracks::Track;
+
+use log::debug;
+use parse_display::Display;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                           error type                                           //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Display)]
+pub enum Cause {
+    /// An error in another crate or  module-- cf. source.
+    #[display("An error in another crate or  module-- cf. source.")]
+    Other,
+    /// A `<playlist>` element in the playlist directory with no `filename` attribute
+    #[display("The playlist {} in the playlist directory has no filename.")]
+    NoPlaylistFile(String),
+}
+
+#[derive(Debug)]
+pub struct Error {
+    /// Enumerated status code
+    cause: Cause,
+    /// The lower-level source of this error, if any
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    /// Optional backtrace
+    trace: Option<crate::Backtrace>,
+}
+
+impl Error {
+    fn new(cause: Cause) -> Error {
+        Error {
+            cause: cause,
+            source: None,
+            trace: crate::capture_backtrace(),
+        }
+    }
+    /// What went wrong
+    pub fn cause(&self) -> &Cause {
+        &self.cause
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        crate::fmt_error(f, &self.cause, self, self.trace.as_ref())
+    }
+}
+
+impl std::error::Error for Error {
+    /// The lower-level source of this error, if any.
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.source {
+            Some(bx) => Some(bx.as_ref()),
+            None => None,
+        }
+    }
+}
+
+error_from!(std::io::Error);
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                        reading playlists                                       //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Decode the contents of a playlist or playlist directory: UTF-16LE if there's a BOM saying so,
+/// UTF-8 otherwise (legacy M3U files in the system code page will come through with the odd
+/// replacement character, rather than failing outright)
+fn decode(bytes: &[u8]) -> String {
+    if let Some(bytes) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        let bytes = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]).unwrap_or(bytes);
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+/// Return the entries in an M3U playlist: every line that's neither blank nor a comment (which
+/// includes `#EXTM3U` & `#EXTINF` directives)
+fn m3u_entries(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+/// Replace the predefined XML entities in `text`
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Pick the (quoted) attributes out of the text of an XML tag
+fn attributes(tag: &str) -> HashMap<String, String> {
+    let mut attrs: HashMap<String, String> = HashMap::new();
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim().rsplit(char::is_whitespace).next();
+        let value = rest[eq + 1..].trim_start();
+        let quote = match value.chars().next() {
+            Some(q) if q == '"' || q == '\'' => q,
+            _ => break,
+        };
+        let value = &value[1..];
+        let end = match value.find(quote) {
+            Some(end) => end,
+            None => break,
+        };
+        if let Some(name) = name {
+            attrs.insert(String::from(name), unescape(&value[..end]));
+        }
+        rest = &value[end + 1..];
+    }
+    attrs
+}
+
+/// A playlist, as listed in the playlist directory
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlaylistEntry {
+    /// The playlist's title
+    pub title: String,
+    /// The file containing the playlist
+    pub file: PathBuf,
+}
+
+/// Parse the text of a playlist directory; playlist files are taken to be relative to `dir`
+fn playlist_entries(text: &str, dir: &Path) -> Result<Vec<PlaylistEntry>> {
+    let mut entries: Vec<PlaylistEntry> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<playlist") {
+        rest = &rest[start + "<playlist".len()..];
+        // Skip the enclosing `<playlists>` element
+        if !rest.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let end = rest.find('>').unwrap_or(rest.len());
+        let attrs = attributes(&rest[..end]);
+        let title = attrs.get("title").cloned().unwrap_or_default();
+        match attrs.get("filename") {
+            Some(filename) => entries.push(PlaylistEntry {
+                title: title,
+                file: dir.join(filename),
+            }),
+            None => return Err(Error::new(Cause::NoPlaylistFile(title))),
+        }
+        rest = &rest[end..];
+    }
+    Ok(entries)
+}
+
+/// Read the playlist directory (`playlists.xml`) at `path`; playlist files are taken to be in the
+/// same directory
+pub fn read_playlist_directory(path: &Path) -> Result<Vec<PlaylistEntry>> {
+    let text = decode(&std::fs::read(path)?);
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let entries = playlist_entries(&text, dir)?;
+    debug!(
+        "There are {} playlists in {}.",
+        entries.len(),
+        path.display()
+    );
+    Ok(entries)
+}
+
+/// Read the entries in the M3U (or M3U8) playlist at `path`
+pub fn read_m3u(path: &Path) -> Result<Vec<String>> {
+    Ok(m3u_entries(&decode(&std::fs::read(path)?)))
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                       resolving playlists                                      //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Reduce a Windows path to a form in which equivalent paths compare equal
+fn match_key(path: &str) -> String {
+    path.replace('/', "\\").to_lowercase()
+}
+
+/// Return true if `key` (as returned by [`match_key`]) is an absolute path
+///
+/// [`match_key`]: fn.match_key.html
+fn is_absolute(key: &str) -> bool {
+    key.starts_with('\\') || key.as_bytes().get(1) == Some(&b':')
+}
+
+/// A playlist, with its entries resolved against the library
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Playlist {
+    /// The playlist's title
+    pub title: String,
+    /// The tracks in the playlist, in order
+    pub tracks: Vec<Track>,
+    /// Entries that couldn't be matched to any track in the library
+    pub missing: Vec<String>,
+}
+
+/// Resolves playlist entries against the tracks in a library
+pub struct Resolver {
+    by_key: HashMap<String, Track>,
+}
+
+impl Resolver {
+    /// Index `tracks` (typically all the tracks in a library) by filename
+    pub fn new<I: IntoIterator<Item = Track>>(tracks: I) -> Resolver {
+        Resolver {
+            by_key: tracks
+                .into_iter()
+                .map(|t| {
+                    let key = match_key(&t.filename().to_string_lossy());
+                    (key, t)
+                })
+                .collect(),
+        }
+    }
+    /// Look up the track named by playlist entry `entry`, if it's in the library
+    pub fn resolve(&self, entry: &str) -> Option<&Track> {
+        let key = match_key(entry);
+        if let Some(track) = self.by_key.get(&key) {
+            return Some(track);
+        }
+        if is_absolute(&key) {
+            return None;
+        }
+        let suffix = format!("\\{}", key.trim_start_matches(".\\"));
+        let mut candidates = self.by_key.iter().filter(|(k, _)| k.ends_with(&suffix));
+        match (candidates.next(), candidates.next()) {
+            (Some((_, track)), None) => Some(track),
+            _ => None,
+        }
+    }
+    /// Resolve `entries` into a playlist entitled `title`
+    pub fn resolve_all(&self, title: &str, entries: &[String]) -> Playlist {
+        let mut playlist = Playlist {
+            title: String::from(title),
+            tracks: Vec::new(),
+            missing: Vec::new(),
+        };
+        for entry in entries {
+            match self.resolve(entry) {
+                Some(track) => playlist.tracks.push(track.clone()),
+                None => playlist.missing.push(entry.clone()),
+            }
+        }
+        playlist
+    }
+    /// Read & resolve the M3U playlist at `path`, entitling it by its file name
+    pub fn read_playlist(&self, path: &Path) -> Result<Playlist> {
+        let title = path
+            .file_stem()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(self.resolve_all(&title, &read_m3u(path)?))
+    }
+    /// Read & resolve every playlist listed in the playlist directory at `path`
+    pub fn read_playlists(&self, path: &Path) -> Result<Vec<Playlist>> {
+        read_playlist_directory(path)?
+            .into_iter()
+            .map(|entry| Ok(self.resolve_all(&entry.title, &read_m3u(&entry.file)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod playlist_tests {
+
+    /// Build a track with only a filename
+    fn track(filename: &str) -> Result<super::Track, String> {
+        use crate::builder::field_body;
+        use crate::fields::{FieldValue, FilenameField, NdeField};
+        use crate::tracks::{ColumnMap, ColumnTypes, TrackAttrs};
+
+        let mut col_map = ColumnMap::new();
+        col_map.insert(0, TrackAttrs::Filename);
+        let value = FieldValue::Filename(std::path::PathBuf::from(filename));
+        let fields: Vec<Box<dyn NdeField>> = vec![Box::new(
+            FilenameField::new(
+                &mut field_body(&value, 0, 0).as_slice(),
+                0,
+                &crate::ParseOptions::default(),
+            )
+            .map_err(|err| format!("{}", err))?,
+        )];
+        super::Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))
+    }
+
+    /// Playlists should decode with or without a BOM, & skip comments & directives
+    #[test]
+    fn m3u() {
+        use super::*;
+        let text =
+            decode(b"\xef\xbb\xbf#EXTM3U\r\n#EXTINF:305,ABBA - Waterloo\r\nC:\\a.mp3\r\n\r\n");
+        assert_eq!(m3u_entries(&text), vec![String::from("C:\\a.mp3")]);
+        let text = decode(&[0xff, 0xfe, b'x', 0x00, b'\n', 0x00, b'y', 0x00]);
+        assert_eq!(
+            m3u_entries(&text),
+            vec![String::from("x"), String::from("y")]
+        );
+    }
+
+    /// The playlist directory should yield each playlist's title & file
+    #[test]
+    fn directory() -> Result<(), String> {
+        use super::*;
+        let text = r#"<?xml version="1.0" encoding="UTF-16"?>
+<playlists playlists="2">
+<playlist filename="plf1.m3u8" title="Rock &amp; Roll" id="{1}" songs="1" seconds="305"/>
+<playlist title='Empty' filename='plf2.m3u8'/>
+</playlists>"#;
+        let entries = playlist_entries(text, Path::new("plf")).map_err(|err| format!("{}", err))?;
+        assert_eq!(
+            entries,
+            vec![
+                PlaylistEntry {
+                    title: String::from("Rock & Roll"),
+                    file: PathBuf::from("plf").join("plf1.m3u8"),
+                },
+                PlaylistEntry {
+                    title: String::from("Empty"),
+                    file: PathBuf::from("plf").join("plf2.m3u8"),
+                },
+            ]
+        );
+        match playlist_entries(r#"<playlist title="x"/>"#, Path::new("")) {
+            Err(err) => match err.cause() {
+                Cause::NoPlaylistFile(title) => assert_eq!(title, "x"),
+                _ => return Err(format!("unexpected error {}", err)),
+            },
+            Ok(_) => return Err(String::from("a playlist with no file should be an error")),
+        }
+        Ok(())
+    }
+
+    /// Entries should match tracks as Windows paths would
+    #[test]
+    fn resolve() -> Result<(), String> {
+        use super::*;
+        let resolver = Resolver::new(vec![
+            track("C:\\Music\\ABBA\\Waterloo.mp3")?,
+            track("C:\\Music\\Blondie\\Atomic.mp3")?,
+            track("D:\\Other\\Blondie\\Atomic.mp3")?,
+        ]);
+        let playlist = resolver.resolve_all(
+            "mix",
+            &[
+                String::from("c:/music/abba/waterloo.MP3"),
+                String::from("ABBA\\Waterloo.mp3"),
+                String::from("Blondie\\Atomic.mp3"),
+                String::from("C:\\Music\\Heart\\Barracuda.mp3"),
+            ],
+        );
+        assert_eq!(playlist.title, "mix");
+        let names: Vec<&Path> = playlist.tracks.iter().map(|t| t.filename()).collect();
+        assert_eq!(
+            names,
+            vec![
+                Path::new("C:\\Music\\ABBA\\Waterloo.mp3"),
+                Path::new("C:\\Music\\ABBA\\Waterloo.mp3")
+            ]
+        );
+        // The relative entry for "Atomic" is ambiguous
+        assert_eq!(
+            playlist.missing,
+            vec![
+                String::from("Blondie\\Atomic.mp3"),
+                String::from("C:\\Music\\Heart\\Barracuda.mp3")
+            ]
+        );
+        Ok(())
+    }
+}
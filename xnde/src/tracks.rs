@@ -20,7 +20,8 @@
 //!
 //! This module introduces the [`Track`] struct, which represents a single track in your Winamp
 //! Music Library. The idea is to map each record in the NDE "main" table to a [`Track`] instance.
-//! [`Track`] derives the [`Serialize`] [`Serde`] trait, making it easy to write to file.
+//! Given the `serde` feature (on by default), [`Track`] derives the [`Serialize`] [`Serde`]
+//! trait, making it easy to write to file.
 //!
 //! [`Track`]: struct.Track.html
 //! [`Serialize`]: https://docs.serde.rs/serde/trait.Serialize.html
@@ -50,13 +51,17 @@
 //! [`FieldValue`]: enum.FieldValue.html
 //! [`Track`]: struct.Track.html
 
-use crate::fields::{ColumnField, FieldValue, NdeField};
+use crate::{
+    fields::{ColumnField, FieldType, FieldValue, NdeField},
+    ParseOptions,
+};
 
 use log::error;
 use parse_display::Display;
-use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeMap, Serialize, Serializer};
 
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap, convert::TryFrom};
 
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 //                                           error type                                           //
@@ -70,13 +75,26 @@ pub enum Cause {
     /// No filename field found
     #[display("No filename field found.")]
     NoFilename,
+    /// Unknown attribute given as a sort key
+    #[display("Can't sort tracks by {}.")]
+    BadSortKey(String),
+    /// Unknown attribute given as an identity key
+    #[display("Can't identify tracks by {}.")]
+    BadKey(String),
+    /// A date that couldn't be parsed
+    #[display("Couldn't interpret {} as a date (try YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS).")]
+    BadDate(String),
+    /// Bad representation for missing attributes
+    #[display("Couldn't interpret {} as a representation for missing attributes.")]
+    BadNullAs(String),
+    /// A column-name mapping that couldn't be parsed
+    #[display("Couldn't interpret {} as a column mapping (try COLUMN=attribute).")]
+    BadColumnAlias(String),
 }
 
-#[derive(Debug, Display)]
-#[display("{cause} Source (if any): {source} Stack trace (if any): {trace}")]
+#[derive(Debug)]
 pub struct Error {
     /// Enumerated status code
-    #[display("XNDE error {}.")]
     cause: Cause,
     // This is an Option that may contain a Box containing something that implements
     // std::error::Error.  It is still unclear to me how this satisfies the lifetime bound in
@@ -84,13 +102,9 @@ pub struct Error {
     // lifetime. There is a discussion of this at
     // <https://users.rust-lang.org/t/what-does-it-mean-to-return-dyn-error-static/37619/6>,
     // but at the time of this writing, i cannot follow it.
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("fields error caused by {:#?}.")]
-    source: Option<Box<dyn std::error::Error>>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
     /// Optional backtrace
-    // TODO(sp1ff): figure out how to format `source'
-    #[display("backtrace: {:#?}.")]
-    trace: Option<backtrace::Backtrace>,
+    trace: Option<crate::Backtrace>,
 }
 
 impl Error {
@@ -99,9 +113,19 @@ impl Error {
         Error {
             cause: cause,
             source: None,
-            trace: Some(backtrace::Backtrace::new()),
+            trace: crate::capture_backtrace(),
         }
     }
+    /// What went wrong
+    pub fn cause(&self) -> &Cause {
+        &self.cause
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        crate::fmt_error(f, &self.cause, self, self.trace.as_ref())
+    }
 }
 
 impl std::error::Error for Error {
@@ -124,7 +148,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 ////////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Enumerated set of attributes which Track may include
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum TrackAttrs {
     Filename,
     Artist,
@@ -139,6 +163,7 @@ pub enum TrackAttrs {
     LastUpd,
     LastPlay,
     Rating,
+    Tuid,
     Tuid2,
     PlayCount,
     Filetime,
@@ -169,195 +194,354 @@ pub enum TrackAttrs {
     DateAdded,
 }
 
+impl TrackAttrs {
+    /// Map the name of a column in the NDE "main" table to the corresponding attribute, if any
+    pub fn from_column_name(name: &str) -> Option<TrackAttrs> {
+        match name {
+            "filename" => Some(TrackAttrs::Filename),
+            "artist" => Some(TrackAttrs::Artist),
+            "title" => Some(TrackAttrs::Title),
+            "album" => Some(TrackAttrs::Album),
+            "year" => Some(TrackAttrs::Year),
+            "genre" => Some(TrackAttrs::Genre),
+            "comment" => Some(TrackAttrs::Comment),
+            "trackno" => Some(TrackAttrs::TrackNo),
+            "length" => Some(TrackAttrs::Length),
+            "type" => Some(TrackAttrs::Type),
+            "lastupd" => Some(TrackAttrs::LastUpd),
+            "lastplay" => Some(TrackAttrs::LastPlay),
+            "rating" => Some(TrackAttrs::Rating),
+            "tuid" => Some(TrackAttrs::Tuid),
+            "tuid2" => Some(TrackAttrs::Tuid2),
+            "playcount" => Some(TrackAttrs::PlayCount),
+            "filetime" => Some(TrackAttrs::Filetime),
+            "filesize" => Some(TrackAttrs::Filesize),
+            "bitrate" => Some(TrackAttrs::Bitrate),
+            "disc" => Some(TrackAttrs::Disc),
+            "albumartist" => Some(TrackAttrs::Albumartist),
+            "replaygain_album_gain" => Some(TrackAttrs::ReplaygainAlbumGain),
+            "replaygain_track_gain" => Some(TrackAttrs::ReplaygainTrackGain),
+            "publisher" => Some(TrackAttrs::Publisher),
+            "composer" => Some(TrackAttrs::Composer),
+            "bpm" => Some(TrackAttrs::Bpm),
+            "discs" => Some(TrackAttrs::Discs),
+            "tracks" => Some(TrackAttrs::Tracks),
+            "ispodcast" => Some(TrackAttrs::IsPodcast),
+            "podcastchannel" => Some(TrackAttrs::PodcastChannel),
+            "podcastpubdate" => Some(TrackAttrs::PodcastPubdate),
+            "GracenoteFileID" => Some(TrackAttrs::GracenoteFileId),
+            "GracenoteExtData" => Some(TrackAttrs::GracenoteExtData),
+            "lossless" => Some(TrackAttrs::Lossless),
+            "category" => Some(TrackAttrs::Category),
+            "codec" => Some(TrackAttrs::Codec),
+            "director" => Some(TrackAttrs::Director),
+            "producer" => Some(TrackAttrs::Producer),
+            "width" => Some(TrackAttrs::Width),
+            "height" => Some(TrackAttrs::Height),
+            "mimetype" => Some(TrackAttrs::MimeType),
+            "dateadded" => Some(TrackAttrs::DateAdded),
+            _ => None,
+        }
+    }
+}
+
 /// Map NDE table columns (discovered at runtime) to Track attributes (fixed at compile-time)
 pub type ColumnMap = HashMap<i32, TrackAttrs>;
 
 /// Build a ColumnMap from the columns in a table's first record
-pub fn new_column_map<'a, CI>(cols: CI) -> ColumnMap
+///
+/// If two or more columns share a name, each will map to the same attribute (& so whichever is
+/// read last will win); that shouldn't happen, so a warning is logged listing the colliding IDs.
+/// Any column aliases given in `opts` are consulted before the built-in column names.
+pub fn new_column_map<'a, CI>(cols: CI, opts: &ParseOptions) -> ColumnMap
+where
+    CI: Iterator<Item = &'a ColumnField>,
+{
+    let cols: Vec<&ColumnField> = cols.collect();
+    for (name, ids) in duplicate_columns(cols.iter().copied()) {
+        log::warn!(
+            "The column name {} is shared by columns {}.",
+            name,
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+    column_map_with(cols.into_iter(), &opts.column_aliases)
+}
+
+/// Build a ColumnMap, consulting `aliases` before the built-in column names
+fn column_map_with<'a, CI>(cols: CI, aliases: &[(String, TrackAttrs)]) -> ColumnMap
 where
     CI: Iterator<Item = &'a ColumnField>,
 {
     let mut col_map: HashMap<i32, TrackAttrs> = HashMap::new();
     for col in cols {
-        let id = col.id();
-        match col.name().as_str() {
-            "filename" => {
-                col_map.insert(id, TrackAttrs::Filename);
-            }
-            "artist" => {
-                col_map.insert(id, TrackAttrs::Artist);
-            }
-            "title" => {
-                col_map.insert(id, TrackAttrs::Title);
-            }
-            "album" => {
-                col_map.insert(id, TrackAttrs::Album);
-            }
-            "year" => {
-                col_map.insert(id, TrackAttrs::Year);
-            }
-            "genre" => {
-                col_map.insert(id, TrackAttrs::Genre);
-            }
-            "comment" => {
-                col_map.insert(id, TrackAttrs::Comment);
-            }
-            "trackno" => {
-                col_map.insert(id, TrackAttrs::TrackNo);
-            }
-            "length" => {
-                col_map.insert(id, TrackAttrs::Length);
-            }
-            "type" => {
-                col_map.insert(id, TrackAttrs::Type);
-            }
-            "lastupd" => {
-                col_map.insert(id, TrackAttrs::LastUpd);
-            }
-            "lastplay" => {
-                col_map.insert(id, TrackAttrs::LastPlay);
-            }
-            "rating" => {
-                col_map.insert(id, TrackAttrs::Rating);
-            }
-            "tuid2" => {
-                col_map.insert(id, TrackAttrs::Tuid2);
-            }
-            "playcount" => {
-                col_map.insert(id, TrackAttrs::PlayCount);
-            }
-            "filetime" => {
-                col_map.insert(id, TrackAttrs::Filetime);
-            }
-            "filesize" => {
-                col_map.insert(id, TrackAttrs::Filesize);
-            }
-            "bitrate" => {
-                col_map.insert(id, TrackAttrs::Bitrate);
-            }
-            "disc" => {
-                col_map.insert(id, TrackAttrs::Disc);
-            }
-            "albumartist" => {
-                col_map.insert(id, TrackAttrs::Albumartist);
-            }
-            "replaygain_album_gain" => {
-                col_map.insert(id, TrackAttrs::ReplaygainAlbumGain);
-            }
-            "replaygain_track_gain" => {
-                col_map.insert(id, TrackAttrs::ReplaygainTrackGain);
-            }
-            "publisher" => {
-                col_map.insert(id, TrackAttrs::Publisher);
-            }
-            "composer" => {
-                col_map.insert(id, TrackAttrs::Composer);
-            }
-            "bpm" => {
-                col_map.insert(id, TrackAttrs::Bpm);
-            }
-            "discs" => {
-                col_map.insert(id, TrackAttrs::Discs);
-            }
-            "tracks" => {
-                col_map.insert(id, TrackAttrs::Tracks);
-            }
-            "ispodcast" => {
-                col_map.insert(id, TrackAttrs::IsPodcast);
-            }
-            "podcastchannel" => {
-                col_map.insert(id, TrackAttrs::PodcastChannel);
-            }
-            "podcastpubdate" => {
-                col_map.insert(id, TrackAttrs::PodcastPubdate);
-            }
-            "GracenoteFileID" => {
-                col_map.insert(id, TrackAttrs::GracenoteFileId);
-            }
-            "GracenoteExtData" => {
-                col_map.insert(id, TrackAttrs::GracenoteExtData);
-            }
-            "lossless" => {
-                col_map.insert(id, TrackAttrs::Lossless);
-            }
-            "category" => {
-                col_map.insert(id, TrackAttrs::Category);
-            }
-            "codec" => {
-                col_map.insert(id, TrackAttrs::Codec);
-            }
-            "director" => {
-                col_map.insert(id, TrackAttrs::Director);
-            }
-            "producer" => {
-                col_map.insert(id, TrackAttrs::Producer);
-            }
-            "width" => {
-                col_map.insert(id, TrackAttrs::Width);
-            }
-            "height" => {
-                col_map.insert(id, TrackAttrs::Height);
-            }
-            "mimetype" => {
-                col_map.insert(id, TrackAttrs::MimeType);
-            }
-            "dateadded" => {
-                col_map.insert(id, TrackAttrs::DateAdded);
-            }
-            _ => (),
+        let name = col.name();
+        let attr = aliases
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map(|(_, attr)| *attr)
+            .or_else(|| TrackAttrs::from_column_name(&name));
+        if let Some(attr) = attr {
+            col_map.insert(col.id(), attr);
         }
     }
     col_map
 }
 
+/// Parse a column-name mapping of the form `COLUMN=attribute` (`ARTIST_V2=artist`, e.g.), where
+/// `attribute` is named as its built-in column would be
+pub fn parse_column_alias(spec: &str) -> Result<(String, TrackAttrs)> {
+    let bad = || Error::new(Cause::BadColumnAlias(String::from(spec)));
+    let (name, attr) = spec.split_once('=').ok_or_else(bad)?;
+    let attr = TrackAttrs::from_column_name(attr.trim()).ok_or_else(bad)?;
+    if name.is_empty() {
+        return Err(bad());
+    }
+    Ok((String::from(name), attr))
+}
+
+/// Find any names shared by more than one of `cols`, returning each such name along with the IDs
+/// of the columns bearing it (both sorted)
+pub fn duplicate_columns<'a, CI>(cols: CI) -> Vec<(String, Vec<i32>)>
+where
+    CI: Iterator<Item = &'a ColumnField>,
+{
+    let mut by_name: HashMap<String, Vec<i32>> = HashMap::new();
+    for col in cols {
+        by_name.entry(col.name()).or_default().push(col.id());
+    }
+    let mut dups: Vec<(String, Vec<i32>)> = by_name
+        .into_iter()
+        .filter(|(_, ids)| ids.len() > 1)
+        .map(|(name, mut ids)| {
+            ids.sort_unstable();
+            (name, ids)
+        })
+        .collect();
+    dups.sort();
+    dups
+}
+
+/// Map NDE table columns to the types declared for them in the table's first record
+///
+/// The type in each field's header doesn't always agree with that declared for its column;
+/// Winamp will, for instance, sometimes store a timestamp as a bare `INTEGER` in a column declared
+/// `DATETIME`. The column's type is the more reliable guide to the field's meaning.
+pub type ColumnTypes = HashMap<i32, FieldType>;
+
+/// Build a ColumnTypes from the columns in a table's first record
+pub fn new_column_types<'a, CI>(cols: CI) -> ColumnTypes
+where
+    CI: Iterator<Item = &'a ColumnField>,
+{
+    cols.map(|col| (col.id(), col.col_type())).collect()
+}
+
+/// Reinterpret an integral field value according to the type declared for its column, if any;
+/// values of any other sort are returned as-is
+fn coerce(value: FieldValue, col_type: Option<&FieldType>) -> FieldValue {
+    match (value, col_type) {
+        (FieldValue::Integer(x), Some(FieldType::Datetime)) => FieldValue::Datetime(x),
+        (FieldValue::Integer(x), Some(FieldType::Length)) => FieldValue::Length(x),
+        (FieldValue::Datetime(x), Some(FieldType::Integer)) => FieldValue::Integer(x),
+        (FieldValue::Length(x), Some(FieldType::Integer)) => FieldValue::Integer(x),
+        (value, _) => value,
+    }
+}
+
+/// Seconds from the Windows FILETIME epoch (1601-01-01) to the Unix epoch (1970-01-01)
+const FILETIME_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// FILETIME ticks (100ns) per second
+const FILETIME_TICKS_PER_SEC: i64 = 10_000_000;
+
+/// Convert a Windows FILETIME (the number of 100ns intervals since 1601-01-01 UTC) to seconds
+/// since the Unix epoch, discarding any fractional second
+pub fn filetime_to_unix(ticks: i64) -> i64 {
+    ticks.div_euclid(FILETIME_TICKS_PER_SEC) - FILETIME_EPOCH_OFFSET
+}
+
+/// Map the text of a string field to a [`Track`] attribute; if `omit_empty` is true, an empty
+/// string is taken to be no value at all
+///
+/// [`Track`]: struct.Track.html
+fn string_value(x: &str, omit_empty: bool) -> Option<String> {
+    if x.is_empty() && omit_empty {
+        None
+    } else {
+        Some(String::from(x))
+    }
+}
+
+/// Render `path` as a string with forward slashes as separators, regardless of platform
+///
+/// Winamp records Windows paths; read on Windows, they come back as `\`-separated `Path`s, & on
+/// anything else as a single component that happens to contain backslashes. Either way, the
+/// result here is the same.
+#[cfg(any(feature = "serde", feature = "itunes"))]
+fn forward_slashes(path: &std::path::Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(feature = "serde")]
+fn serialize_path<S: Serializer>(
+    path: &std::path::Path,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&forward_slashes(path))
+}
+
 /// Winamp Music Library track
-#[derive(Debug, Serialize)]
+///
+/// Only `filename` is required; any attributes not present in the underlying record are omitted
+/// when serializing.
+///
+/// Attributes are serialized under snake_case names, for the most part those of the corresponding
+/// NDE columns. The exceptions are columns whose names run words together, or which collide with
+/// Rust keywords; these are serialized under the names conventional in music tagging tools:
+///
+/// | NDE column    | serialized as  |
+/// |---------------|----------------|
+/// | `trackno`     | `track_number` |
+/// | `type`        | `type`         |
+/// | `lastupd`     | `last_updated` |
+/// | `lastplay`    | `last_played`  |
+/// | `playcount`   | `play_count`   |
+/// | `albumartist` | `album_artist` |
+/// | `mimetype`    | `mime_type`    |
+///
+/// With the `schema` feature, `Track` also implements `schemars::JsonSchema`, describing this
+/// serialized form (cf. `xnde schema`).
+///
+/// Two `Track`s are equal if & only if every attribute is. That's total equality (`Eq`), since
+/// every attribute is text or an integer; should a floating-point attribute ever be added, it will
+/// have to be compared by its bits to keep it so.
+///
+/// `filename` is always serialized with forward slashes (`C:/My Music/a.mp3`), whatever the
+/// platform on which xnde is running.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Track {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_path"))]
     filename: std::path::PathBuf,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     artist: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     title: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     album: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     year: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     genre: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     comment: Option<String>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "track_number", skip_serializing_if = "Option::is_none")
+    )]
     trackno: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     length: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "type", skip_serializing_if = "Option::is_none")
+    )]
     ttype: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "last_updated", skip_serializing_if = "Option::is_none")
+    )]
     lastupd: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "last_played", skip_serializing_if = "Option::is_none")
+    )]
     lastplay: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     rating: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    tuid: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     tuid2: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     play_count: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     filetime: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     filesize: Option<i64>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     bitrate: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     disc: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "album_artist", skip_serializing_if = "Option::is_none")
+    )]
     albumartist: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     replaygain_album_gain: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     replaygain_track_gain: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     publisher: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     composer: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     bpm: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     discs: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     tracks: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     is_podcast: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     podcast_channel: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     podcast_pubdate: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     gracenote_file_id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     gracenote_ext_data: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     lossless: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     category: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     codec: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     director: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     producer: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     width: Option<i32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     height: Option<i32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "mime_type", skip_serializing_if = "Option::is_none")
+    )]
     mimetype: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     date_added: Option<i32>,
 }
 
 impl Track {
-    pub fn new<'a, FI>(col_map: &ColumnMap, fields: FI) -> Result<Track>
+    /// Build a Track from the fields of a single record; `col_map` maps each field to its
+    /// attribute, & `col_types` gives the declared type of each column (cf. [`ColumnTypes`]);
+    /// lengths & empty strings are interpreted according to `opts`
+    ///
+    /// [`ColumnTypes`]: type.ColumnTypes.html
+    pub fn new<'a, FI>(
+        col_map: &ColumnMap,
+        col_types: &ColumnTypes,
+        fields: FI,
+        opts: &ParseOptions,
+    ) -> Result<Track>
     where
         FI: Iterator<Item = &'a Box<dyn NdeField>>,
     {
@@ -366,7 +550,7 @@ impl Track {
 
         for field in fields {
             match col_map.get(&field.id()) {
-                Some(attr) => match (attr, field.value()) {
+                Some(attr) => match (attr, coerce(field.value(), col_types.get(&field.id()))) {
                     (TrackAttrs::Filename, FieldValue::Filename(x)) => {
                         attrs_map.insert(TrackAttrs::Filename, FieldValue::Filename(x));
                     }
@@ -392,7 +576,8 @@ impl Track {
                         attrs_map.insert(TrackAttrs::TrackNo, FieldValue::Integer(x));
                     }
                     (TrackAttrs::Length, FieldValue::Length(x)) => {
-                        attrs_map.insert(TrackAttrs::Length, FieldValue::Integer(x));
+                        let secs = opts.length_units.seconds(x).round() as i32;
+                        attrs_map.insert(TrackAttrs::Length, FieldValue::Integer(secs));
                     }
                     (TrackAttrs::Type, FieldValue::Integer(x)) => {
                         attrs_map.insert(TrackAttrs::Type, FieldValue::Integer(x));
@@ -406,6 +591,9 @@ impl Track {
                     (TrackAttrs::Rating, FieldValue::Integer(x)) => {
                         attrs_map.insert(TrackAttrs::Rating, FieldValue::Integer(x));
                     }
+                    (TrackAttrs::Tuid, FieldValue::String(x)) => {
+                        attrs_map.insert(TrackAttrs::Tuid, FieldValue::String(x));
+                    }
                     (TrackAttrs::Tuid2, FieldValue::String(x)) => {
                         attrs_map.insert(TrackAttrs::Tuid2, FieldValue::String(x));
                     }
@@ -413,7 +601,16 @@ impl Track {
                         attrs_map.insert(TrackAttrs::PlayCount, FieldValue::Integer(x));
                     }
                     (TrackAttrs::Filetime, FieldValue::Datetime(x)) => {
-                        attrs_map.insert(TrackAttrs::Filetime, FieldValue::Integer(x));
+                        attrs_map.insert(TrackAttrs::Filetime, FieldValue::Datetime(x));
+                    }
+                    // Some libraries keep the file time as a 64-bit Windows FILETIME
+                    (TrackAttrs::Filetime, FieldValue::Int64(x)) => {
+                        match i32::try_from(filetime_to_unix(x)) {
+                            Ok(t) => {
+                                attrs_map.insert(TrackAttrs::Filetime, FieldValue::Datetime(t));
+                            }
+                            Err(_) => log::warn!("The file time {} is out of range.", x),
+                        }
                     }
                     (TrackAttrs::Filesize, FieldValue::Int64(x)) => {
                         attrs_map.insert(TrackAttrs::Filesize, FieldValue::Int64(x));
@@ -454,8 +651,8 @@ impl Track {
                     (TrackAttrs::PodcastChannel, FieldValue::String(x)) => {
                         attrs_map.insert(TrackAttrs::PodcastChannel, FieldValue::String(x));
                     }
-                    (TrackAttrs::PodcastPubdate, FieldValue::Integer(x)) => {
-                        attrs_map.insert(TrackAttrs::PodcastPubdate, FieldValue::Integer(x));
+                    (TrackAttrs::PodcastPubdate, FieldValue::Datetime(x)) => {
+                        attrs_map.insert(TrackAttrs::PodcastPubdate, FieldValue::Datetime(x));
                     }
                     (TrackAttrs::GracenoteFileId, FieldValue::String(x)) => {
                         attrs_map.insert(TrackAttrs::GracenoteFileId, FieldValue::String(x));
@@ -502,8 +699,9 @@ impl Track {
 
         // TODO(sp1ff): This seems awful to me. I don't know if this is Rusty (Rustaceous?)
         // build the track instance thus:
+        // An empty filename is as good as none at all
         let filename = match attrs_map.get(&TrackAttrs::Filename) {
-            Some(FieldValue::Filename(x)) => x.clone(),
+            Some(FieldValue::Filename(x)) if !x.as_os_str().is_empty() => x.clone(),
             _ => {
                 return Err(Error::new(Cause::NoFilename));
             }
@@ -511,15 +709,15 @@ impl Track {
         // TODO(sp1ff): return an error if there is a field with the correct column id, but the
         // wrong type!
         let artist = match attrs_map.get(&TrackAttrs::Artist) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let title = match attrs_map.get(&TrackAttrs::Title) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let album = match attrs_map.get(&TrackAttrs::Album) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let year = match attrs_map.get(&TrackAttrs::Year) {
@@ -527,11 +725,11 @@ impl Track {
             _ => None,
         };
         let genre = match attrs_map.get(&TrackAttrs::Genre) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let comment = match attrs_map.get(&TrackAttrs::Comment) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let trackno = match attrs_map.get(&TrackAttrs::TrackNo) {
@@ -558,8 +756,12 @@ impl Track {
             Some(FieldValue::Integer(x)) => Some(*x),
             _ => None,
         };
+        let tuid = match attrs_map.get(&TrackAttrs::Tuid) {
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
+            _ => None,
+        };
         let tuid2 = match attrs_map.get(&TrackAttrs::Tuid2) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let play_count = match attrs_map.get(&TrackAttrs::PlayCount) {
@@ -568,7 +770,7 @@ impl Track {
         };
 
         let filetime = match attrs_map.get(&TrackAttrs::Filetime) {
-            Some(FieldValue::Integer(x)) => Some(*x),
+            Some(FieldValue::Datetime(x)) => Some(*x),
             _ => None,
         };
         let filesize = match attrs_map.get(&TrackAttrs::Filesize) {
@@ -584,23 +786,23 @@ impl Track {
             _ => None,
         };
         let albumartist = match attrs_map.get(&TrackAttrs::Albumartist) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let replaygain_album_gain = match attrs_map.get(&TrackAttrs::ReplaygainAlbumGain) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let replaygain_track_gain = match attrs_map.get(&TrackAttrs::ReplaygainTrackGain) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let publisher = match attrs_map.get(&TrackAttrs::Publisher) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let composer = match attrs_map.get(&TrackAttrs::Composer) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let bpm = match attrs_map.get(&TrackAttrs::Bpm) {
@@ -620,7 +822,7 @@ impl Track {
             _ => None,
         };
         let podcastchannel = match attrs_map.get(&TrackAttrs::PodcastChannel) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let podcastpubdate = match attrs_map.get(&TrackAttrs::PodcastPubdate) {
@@ -628,11 +830,11 @@ impl Track {
             _ => None,
         };
         let gracenote_file_id = match attrs_map.get(&TrackAttrs::GracenoteFileId) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let gracenote_ext_data = match attrs_map.get(&TrackAttrs::GracenoteExtData) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let lossless = match attrs_map.get(&TrackAttrs::Lossless) {
@@ -640,19 +842,19 @@ impl Track {
             _ => None,
         };
         let category = match attrs_map.get(&TrackAttrs::Category) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let codec = match attrs_map.get(&TrackAttrs::Codec) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let director = match attrs_map.get(&TrackAttrs::Director) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let producer = match attrs_map.get(&TrackAttrs::Producer) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let width = match attrs_map.get(&TrackAttrs::Width) {
@@ -664,7 +866,7 @@ impl Track {
             _ => None,
         };
         let mimetype = match attrs_map.get(&TrackAttrs::MimeType) {
-            Some(FieldValue::String(x)) => Some(x.clone()),
+            Some(FieldValue::String(x)) => string_value(x, opts.omit_empty_strings),
             _ => None,
         };
         let dateadded = match attrs_map.get(&TrackAttrs::DateAdded) {
@@ -686,6 +888,7 @@ impl Track {
             lastupd: lastupd,
             lastplay: lastplay,
             rating: rating,
+            tuid: tuid,
             tuid2: tuid2,
             play_count: play_count,
             filetime: filetime,
@@ -716,4 +919,1115 @@ impl Track {
             date_added: dateadded,
         })
     }
+    /// This track's filename
+    pub fn filename(&self) -> &std::path::Path {
+        &self.filename
+    }
+    /// This track's artist, if known
+    pub fn artist(&self) -> Option<&str> {
+        self.artist.as_deref()
+    }
+    /// This track's album, if known
+    pub fn album(&self) -> Option<&str> {
+        self.album.as_deref()
+    }
+    /// This track's genre, if known
+    pub fn genre(&self) -> Option<&str> {
+        self.genre.as_deref()
+    }
+    /// This track's length, in seconds, if known
+    pub fn length(&self) -> Option<i32> {
+        self.length
+    }
+    /// The time at which this track was added to the library, in seconds since the Unix epoch, if
+    /// known
+    pub fn date_added(&self) -> Option<i32> {
+        self.date_added
+    }
+    /// The modification time of this track's file, in seconds since the Unix epoch, if known
+    pub fn filetime(&self) -> Option<i32> {
+        self.filetime
+    }
+    /// Hash every attribute of this track, for cheaply telling whether it's changed; the hash is
+    /// stable within a single build of xnde, but not across versions
+    pub fn content_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+    /// Return true if any of this track's textual attributes (including its filename) contain
+    /// `needle`
+    pub fn contains(&self, needle: &str, case_sensitive: bool) -> bool {
+        let filename = self.filename.to_string_lossy();
+        let texts = [
+            Some(filename.as_ref()),
+            self.artist.as_deref(),
+            self.title.as_deref(),
+            self.album.as_deref(),
+            self.genre.as_deref(),
+            self.comment.as_deref(),
+            self.tuid.as_deref(),
+            self.tuid2.as_deref(),
+            self.albumartist.as_deref(),
+            self.replaygain_album_gain.as_deref(),
+            self.replaygain_track_gain.as_deref(),
+            self.publisher.as_deref(),
+            self.composer.as_deref(),
+            self.podcast_channel.as_deref(),
+            self.gracenote_file_id.as_deref(),
+            self.gracenote_ext_data.as_deref(),
+            self.category.as_deref(),
+            self.codec.as_deref(),
+            self.director.as_deref(),
+            self.producer.as_deref(),
+            self.mimetype.as_deref(),
+        ];
+        if case_sensitive {
+            texts.iter().flatten().any(|text| text.contains(needle))
+        } else {
+            let needle = needle.to_lowercase();
+            texts
+                .iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&needle))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                            sorting                                             //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Parse a comma-separated list of column names (`artist,album,trackno`, e.g.) into the
+/// attributes by which to sort [`Track`]s
+///
+/// [`Track`]: struct.Track.html
+pub fn parse_sort_keys(spec: &str) -> Result<Vec<TrackAttrs>> {
+    spec.split(',')
+        .map(|name| name.trim())
+        .map(|name| {
+            TrackAttrs::from_column_name(name)
+                .ok_or_else(|| Error::new(Cause::BadSortKey(String::from(name))))
+        })
+        .collect()
+}
+
+/// Parse the name of a column (`filename`, e.g.) into the attribute by which to identify
+/// [`Track`]s when deduplicating or comparing libraries
+///
+/// [`Track`]: struct.Track.html
+pub fn parse_key(name: &str) -> Result<TrackAttrs> {
+    TrackAttrs::from_column_name(name.trim())
+        .ok_or_else(|| Error::new(Cause::BadKey(String::from(name))))
+}
+
+/// A single [`Track`] attribute, reduced to something orderable & hashable
+///
+/// [`Track`]: struct.Track.html
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum AttrValue {
+    Int(i64),
+    Text(String),
+}
+
+impl std::fmt::Display for AttrValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AttrValue::Int(x) => write!(f, "{}", x),
+            AttrValue::Text(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+fn text_value(x: &Option<String>) -> Option<AttrValue> {
+    x.clone().map(AttrValue::Text)
+}
+
+fn int_value(x: Option<i32>) -> Option<AttrValue> {
+    x.map(|x| AttrValue::Int(x as i64))
+}
+
+impl Track {
+    /// Retrieve attribute `attr` of this track (if present) in a form that can be compared &
+    /// hashed, for use as an identity key, e.g.
+    pub fn attr_value(&self, attr: &TrackAttrs) -> Option<AttrValue> {
+        match attr {
+            TrackAttrs::Filename => Some(AttrValue::Text(
+                self.filename.to_string_lossy().into_owned(),
+            )),
+            TrackAttrs::Artist => text_value(&self.artist),
+            TrackAttrs::Title => text_value(&self.title),
+            TrackAttrs::Album => text_value(&self.album),
+            TrackAttrs::Year => int_value(self.year),
+            TrackAttrs::Genre => text_value(&self.genre),
+            TrackAttrs::Comment => text_value(&self.comment),
+            TrackAttrs::TrackNo => int_value(self.trackno),
+            TrackAttrs::Length => int_value(self.length),
+            TrackAttrs::Type => int_value(self.ttype),
+            TrackAttrs::LastUpd => int_value(self.lastupd),
+            TrackAttrs::LastPlay => int_value(self.lastplay),
+            TrackAttrs::Rating => int_value(self.rating),
+            TrackAttrs::Tuid => text_value(&self.tuid),
+            TrackAttrs::Tuid2 => text_value(&self.tuid2),
+            TrackAttrs::PlayCount => int_value(self.play_count),
+            TrackAttrs::Filetime => int_value(self.filetime),
+            TrackAttrs::Filesize => self.filesize.map(AttrValue::Int),
+            TrackAttrs::Bitrate => int_value(self.bitrate),
+            TrackAttrs::Disc => int_value(self.disc),
+            TrackAttrs::Albumartist => text_value(&self.albumartist),
+            TrackAttrs::ReplaygainAlbumGain => text_value(&self.replaygain_album_gain),
+            TrackAttrs::ReplaygainTrackGain => text_value(&self.replaygain_track_gain),
+            TrackAttrs::Publisher => text_value(&self.publisher),
+            TrackAttrs::Composer => text_value(&self.composer),
+            TrackAttrs::Bpm => int_value(self.bpm),
+            TrackAttrs::Discs => int_value(self.discs),
+            TrackAttrs::Tracks => int_value(self.tracks),
+            TrackAttrs::IsPodcast => int_value(self.is_podcast),
+            TrackAttrs::PodcastChannel => text_value(&self.podcast_channel),
+            TrackAttrs::PodcastPubdate => int_value(self.podcast_pubdate),
+            TrackAttrs::GracenoteFileId => text_value(&self.gracenote_file_id),
+            TrackAttrs::GracenoteExtData => text_value(&self.gracenote_ext_data),
+            TrackAttrs::Lossless => int_value(self.lossless),
+            TrackAttrs::Category => text_value(&self.category),
+            TrackAttrs::Codec => text_value(&self.codec),
+            TrackAttrs::Director => text_value(&self.director),
+            TrackAttrs::Producer => text_value(&self.producer),
+            TrackAttrs::Width => int_value(self.width),
+            TrackAttrs::Height => int_value(self.height),
+            TrackAttrs::MimeType => text_value(&self.mimetype),
+            TrackAttrs::DateAdded => int_value(self.date_added),
+        }
+    }
+    /// As [`attr_value`], but with text lower-cased, so as to sort case-insensitively
+    ///
+    /// [`attr_value`]: #method.attr_value
+    fn sort_value(&self, attr: &TrackAttrs) -> Option<AttrValue> {
+        match self.attr_value(attr) {
+            Some(AttrValue::Text(x)) => Some(AttrValue::Text(x.to_lowercase())),
+            x => x,
+        }
+    }
+}
+
+/// Order two [`Track`]s by each of `keys` in turn; text attributes compare case-insensitively, and
+/// a [`Track`] lacking an attribute sorts after one that has it
+///
+/// [`Track`]: struct.Track.html
+pub fn compare_tracks(lhs: &Track, rhs: &Track, keys: &[TrackAttrs]) -> Ordering {
+    for key in keys {
+        let ord = match (lhs.sort_value(key), rhs.sort_value(key)) {
+            (Some(l), Some(r)) => l.cmp(&r),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                           filtering                                            //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Number of days from 1970-01-01 to the given (proleptic Gregorian) date
+///
+/// This is Howard Hinnant's `days_from_civil`; cf.
+/// <http://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Parse an ISO 8601 date (`2023-04-01`) or date & time (`2023-04-01T18:30:00`, optionally
+/// followed by `Z`) into seconds since the Unix epoch; times are taken to be UTC
+pub fn parse_date(text: &str) -> Result<i64> {
+    let bad = || Error::new(Cause::BadDate(String::from(text)));
+    let num = |x: &str, len: usize| -> Result<i64> {
+        if x.len() != len || !x.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(bad());
+        }
+        x.parse::<i64>().map_err(|_| bad())
+    };
+    let text = text.trim();
+    let (date, time) = match text.find('T') {
+        Some(i) => (&text[..i], Some(text[i + 1..].trim_end_matches('Z'))),
+        None => (text, None),
+    };
+    let ymd: Vec<&str> = date.split('-').collect();
+    if ymd.len() != 3 {
+        return Err(bad());
+    }
+    let (y, m, d) = (num(ymd[0], 4)?, num(ymd[1], 2)?, num(ymd[2], 2)?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return Err(bad());
+    }
+    let secs = match time {
+        Some(time) => {
+            let hms: Vec<&str> = time.split(':').collect();
+            if hms.len() != 3 {
+                return Err(bad());
+            }
+            let (h, mi, s) = (num(hms[0], 2)?, num(hms[1], 2)?, num(hms[2], 2)?);
+            if h > 23 || mi > 59 || s > 60 {
+                return Err(bad());
+            }
+            h * 3600 + mi * 60 + s
+        }
+        None => 0,
+    };
+    Ok(days_from_civil(y, m, d) * 86400 + secs)
+}
+
+/// Return true if `t` falls within `[since, until)`; a missing `t` only passes if there are no
+/// bounds
+fn in_window(t: Option<i32>, since: Option<i64>, until: Option<i64>) -> bool {
+    if since.is_none() && until.is_none() {
+        return true;
+    }
+    match t {
+        Some(t) => {
+            let t = t as i64;
+            since.is_none_or(|since| t >= since) && until.is_none_or(|until| t < until)
+        }
+        None => false,
+    }
+}
+
+/// Windows on the times at which [`Track`]s were added & last played; each bound is in seconds
+/// since the Unix epoch, "since" bounds are inclusive & "until" bounds exclusive
+///
+/// A [`Track`] lacking the attribute on which a bound is set is excluded.
+///
+/// [`Track`]: struct.Track.html
+#[derive(Clone, Debug, Default)]
+pub struct TrackFilter {
+    pub added_since: Option<i64>,
+    pub added_until: Option<i64>,
+    pub played_since: Option<i64>,
+    pub played_until: Option<i64>,
+}
+
+impl TrackFilter {
+    /// Return true if this filter admits every [`Track`]
+    ///
+    /// [`Track`]: struct.Track.html
+    pub fn is_empty(&self) -> bool {
+        self.added_since.is_none()
+            && self.added_until.is_none()
+            && self.played_since.is_none()
+            && self.played_until.is_none()
+    }
+    /// Return true if `trk` falls within all of this filter's windows
+    pub fn matches(&self, trk: &Track) -> bool {
+        in_window(trk.date_added, self.added_since, self.added_until)
+            && in_window(trk.lastplay, self.played_since, self.played_until)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                           tag frames                                           //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Format "n" or "n/total", as in the ID3v2 TRCK & TPOS frames
+#[cfg(feature = "tags")]
+fn of_total(n: Option<i32>, total: Option<i32>) -> Option<String> {
+    match (n, total) {
+        (Some(n), Some(total)) => Some(format!("{}/{}", n, total)),
+        (Some(n), None) => Some(format!("{}", n)),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "tags")]
+impl Track {
+    /// Map this track's attributes to ID3v2.4 frame IDs (`TIT2`, `TPE1`, `TALB`, &c), for use
+    /// with a tag-writing crate such as [id3] or [lofty]
+    ///
+    /// Only attributes with a natural home in a text frame are included; the ReplayGain values
+    /// are given as user-defined text frames, `TXXX:REPLAYGAIN_TRACK_GAIN` & so on. `TLEN` is in
+    /// milliseconds, per the spec. Attributes this track lacks are omitted.
+    ///
+    /// [id3]: https://crates.io/crates/id3
+    /// [lofty]: https://crates.io/crates/lofty
+    pub fn to_tag_fields(&self) -> HashMap<&'static str, String> {
+        let frames = [
+            ("TIT2", self.title.clone()),
+            ("TPE1", self.artist.clone()),
+            ("TALB", self.album.clone()),
+            ("TPE2", self.albumartist.clone()),
+            ("TCON", self.genre.clone()),
+            ("COMM", self.comment.clone()),
+            ("TCOM", self.composer.clone()),
+            ("TPUB", self.publisher.clone()),
+            ("TDRC", self.year.map(|x| format!("{}", x))),
+            ("TRCK", of_total(self.trackno, self.tracks)),
+            ("TPOS", of_total(self.disc, self.discs)),
+            ("TBPM", self.bpm.map(|x| format!("{}", x))),
+            (
+                "TLEN",
+                self.length
+                    .filter(|x| *x >= 0)
+                    .map(|x| format!("{}", x as i64 * 1000)),
+            ),
+            (
+                "TXXX:REPLAYGAIN_TRACK_GAIN",
+                self.replaygain_track_gain.clone(),
+            ),
+            (
+                "TXXX:REPLAYGAIN_ALBUM_GAIN",
+                self.replaygain_album_gain.clone(),
+            ),
+        ];
+        frames
+            .iter()
+            .filter_map(|(id, value)| value.clone().map(|value| (*id, value)))
+            .collect()
+    }
+}
+
+/// Render `path` as a `file://` URL, as iTunes writes them: backslashes become forward slashes, a
+/// Windows drive letter gets a leading slash, & anything other than an unreserved character (or a
+/// separator) is percent-encoded
+#[cfg(feature = "itunes")]
+fn file_url(path: &std::path::Path) -> String {
+    let text = forward_slashes(path);
+    let mut url = String::from("file://localhost");
+    if !text.starts_with('/') {
+        url.push('/');
+    }
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~/:".contains(&b) {
+            url.push(b as char);
+        } else {
+            url.push_str(&format!("%{:02X}", b));
+        }
+    }
+    url
+}
+
+#[cfg(feature = "itunes")]
+impl Track {
+    /// Map this track's attributes to the keys of a track in an iTunes library
+    /// (`iTunes Music Library.xml`), under track ID `id`
+    ///
+    /// `Total Time` is in milliseconds & `Rating` runs from 0 to 100 (Winamp's five stars being
+    /// twenty points apiece). `Location` is a `file://` URL made from the filename. Attributes
+    /// this track lacks are omitted.
+    pub fn to_itunes_dict(&self, id: i64) -> plist::Dictionary {
+        use plist::Value;
+        let text = |x: &Option<String>| x.clone().map(Value::String);
+        let int = |x: Option<i32>| x.map(|x| Value::Integer(i64::from(x).into()));
+        let date = |x: Option<i32>| {
+            x.filter(|x| *x >= 0).map(|x| {
+                Value::Date(
+                    (std::time::UNIX_EPOCH + std::time::Duration::from_secs(x as u64)).into(),
+                )
+            })
+        };
+        let keys = vec![
+            ("Track ID", Some(Value::Integer(id.into()))),
+            ("Name", text(&self.title)),
+            ("Artist", text(&self.artist)),
+            ("Album Artist", text(&self.albumartist)),
+            ("Album", text(&self.album)),
+            ("Genre", text(&self.genre)),
+            ("Composer", text(&self.composer)),
+            ("Comments", text(&self.comment)),
+            ("Year", int(self.year)),
+            ("Track Number", int(self.trackno)),
+            ("Track Count", int(self.tracks)),
+            ("Disc Number", int(self.disc)),
+            ("Disc Count", int(self.discs)),
+            (
+                "Total Time",
+                self.length
+                    .filter(|x| *x >= 0)
+                    .map(|x| Value::Integer((i64::from(x) * 1000).into())),
+            ),
+            ("Size", self.filesize.map(|x| Value::Integer(x.into()))),
+            ("Bit Rate", int(self.bitrate)),
+            ("Play Count", int(self.play_count)),
+            ("Rating", int(self.rating.map(|x| x.clamp(0, 5) * 20))),
+            ("Date Added", date(self.date_added)),
+            ("Play Date UTC", date(self.lastplay)),
+            ("Location", Some(Value::String(file_url(&self.filename)))),
+        ];
+        let mut dict = plist::Dictionary::new();
+        for (key, value) in keys {
+            if let Some(value) = value {
+                dict.insert(String::from(key), value);
+            }
+        }
+        dict
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////
+//                                       missing attributes                                       //
+////////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// The names under which [`Track`] attributes are serialized, in the order in which they are
+/// serialized
+///
+/// [`Track`]: struct.Track.html
+pub const ATTRIBUTE_NAMES: [&str; 42] = [
+    "filename",
+    "artist",
+    "title",
+    "album",
+    "year",
+    "genre",
+    "comment",
+    "track_number",
+    "length",
+    "type",
+    "last_updated",
+    "last_played",
+    "rating",
+    "tuid",
+    "tuid2",
+    "play_count",
+    "filetime",
+    "filesize",
+    "bitrate",
+    "disc",
+    "album_artist",
+    "replaygain_album_gain",
+    "replaygain_track_gain",
+    "publisher",
+    "composer",
+    "bpm",
+    "discs",
+    "tracks",
+    "is_podcast",
+    "podcast_channel",
+    "podcast_pubdate",
+    "gracenote_file_id",
+    "gracenote_ext_data",
+    "lossless",
+    "category",
+    "codec",
+    "director",
+    "producer",
+    "width",
+    "height",
+    "mime_type",
+    "date_added",
+];
+
+/// How to represent attributes missing from a [`Track`], when they're to be serialized at all
+///
+/// [`Track`]: struct.Track.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NullAs {
+    /// The serialization format's own null (`null` in JSON, e.g.)
+    Null,
+    /// The empty string
+    Empty,
+    /// A given string (`\N` for Postgres' `COPY`, e.g.)
+    Sentinel(String),
+}
+
+impl std::convert::TryFrom<&str> for NullAs {
+    type Error = Error;
+    /// Parse `null`, `empty` or `sentinel:<text>`
+    fn try_from(x: &str) -> std::result::Result<NullAs, Error> {
+        match x {
+            "null" => Ok(NullAs::Null),
+            "empty" => Ok(NullAs::Empty),
+            _ => match x.strip_prefix("sentinel:") {
+                Some(text) => Ok(NullAs::Sentinel(String::from(text))),
+                None => Err(Error::new(Cause::BadNullAs(String::from(x)))),
+            },
+        }
+    }
+}
+
+/// A [`Track`] that serializes every attribute, representing those it lacks as directed
+///
+/// [`Track`]: struct.Track.html
+#[cfg(feature = "serde")]
+pub struct WithNulls<'a> {
+    track: &'a Track,
+    null_as: &'a NullAs,
+}
+
+#[cfg(feature = "serde")]
+impl<'a> WithNulls<'a> {
+    pub fn new(track: &'a Track, null_as: &'a NullAs) -> WithNulls<'a> {
+        WithNulls {
+            track: track,
+            null_as: null_as,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> Serialize for WithNulls<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // Track only tells us which attributes it has by serializing itself; so do that, then
+        // fill in the gaps
+        let value = serde_json::to_value(self.track).map_err(serde::ser::Error::custom)?;
+        let mut map = serializer.serialize_map(Some(ATTRIBUTE_NAMES.len()))?;
+        for name in ATTRIBUTE_NAMES.iter() {
+            match (value.get(name), self.null_as) {
+                (Some(x), _) => map.serialize_entry(name, x)?,
+                (None, NullAs::Null) => map.serialize_entry(name, &Option::<()>::None)?,
+                (None, NullAs::Empty) => map.serialize_entry(name, "")?,
+                (None, NullAs::Sentinel(text)) => map.serialize_entry(name, text)?,
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod track_tests {
+
+    /// Serialize a string or filename field with the given text
+    fn string_field_bytes(text: &str) -> Vec<u8> {
+        use crate::builder::{body, string};
+        use crate::fields::FieldType;
+        body(&string(0, FieldType::String, text).2, 0, 0)
+    }
+
+    /// Serialize an integer field holding `x`
+    fn int_field_bytes(x: i32) -> Vec<u8> {
+        crate::builder::field_body(&crate::fields::FieldValue::Integer(x), 0, 0)
+    }
+
+    /// Absent attributes shouldn't show up in the serialized Track
+    #[cfg(feature = "serde")]
+    #[test]
+    fn sparse() -> Result<(), String> {
+        use super::*;
+        use crate::fields::{FilenameField, StringField};
+
+        let mut col_map = ColumnMap::new();
+        col_map.insert(0, TrackAttrs::Filename);
+        col_map.insert(1, TrackAttrs::Artist);
+
+        let filename = FilenameField::new(
+            &mut string_field_bytes("a.mp3").as_slice(),
+            0,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let artist = StringField::new(
+            &mut string_field_bytes("ABC").as_slice(),
+            1,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let fields: Vec<Box<dyn NdeField>> = vec![Box::new(filename), Box::new(artist)];
+        let track = Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+
+        let json = serde_json::to_string(&track).map_err(|err| format!("{}", err))?;
+        assert_eq!(json, r#"{"filename":"a.mp3","artist":"ABC"}"#);
+        // with every absent attribute serialized as `null', this would have been 600+ bytes
+        assert!(json.len() < 64);
+        Ok(())
+    }
+
+    /// Older libraries' `tuid` column should be kept alongside `tuid2`
+    #[cfg(feature = "serde")]
+    #[test]
+    fn tuid() -> Result<(), String> {
+        use super::*;
+        use crate::builder::field_body;
+        use crate::fields::{FilenameField, StringField};
+
+        let cols = [(0, "filename"), (1, "tuid"), (2, "tuid2")];
+        let col_map: ColumnMap = cols
+            .iter()
+            .filter_map(|(id, name)| TrackAttrs::from_column_name(name).map(|attr| (*id, attr)))
+            .collect();
+        assert_eq!(col_map.get(&1), Some(&TrackAttrs::Tuid));
+
+        let body = |text: &str| field_body(&FieldValue::String(String::from(text)), 0, 0);
+        let fields: Vec<Box<dyn NdeField>> = vec![
+            Box::new(
+                FilenameField::new(
+                    &mut body("a.mp3").as_slice(),
+                    0,
+                    &crate::ParseOptions::default(),
+                )
+                .map_err(|err| format!("{}", err))?,
+            ),
+            Box::new(
+                StringField::new(
+                    &mut body("0123").as_slice(),
+                    1,
+                    &crate::ParseOptions::default(),
+                )
+                .map_err(|err| format!("{}", err))?,
+            ),
+        ];
+        let track = Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(
+            track.attr_value(&TrackAttrs::Tuid),
+            Some(AttrValue::Text(String::from("0123")))
+        );
+        assert_eq!(track.attr_value(&TrackAttrs::Tuid2), None);
+        let json = serde_json::to_string(&track).map_err(|err| format!("{}", err))?;
+        assert_eq!(json, r#"{"filename":"a.mp3","tuid":"0123"}"#);
+        Ok(())
+    }
+
+    /// Tracks should be equal, & hash alike, exactly when all their attributes are the same
+    #[test]
+    fn equality() -> Result<(), String> {
+        use super::*;
+        let a = track("a.mp3", Some("ABC"))?;
+        let b = track("a.mp3", Some("ABC"))?;
+        let c = track("a.mp3", Some("ABBA"))?;
+        let d = track("a.mp3", None)?;
+        assert_eq!(a, b);
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_ne!(a, c);
+        assert_ne!(a.content_hash(), c.content_hash());
+        assert_ne!(a, d);
+        let set: std::collections::HashSet<Track> = vec![a, b, c, d].into_iter().collect();
+        assert_eq!(set.len(), 3);
+        Ok(())
+    }
+
+    /// File times stored as 64-bit FILETIMEs should come out as Unix timestamps
+    #[test]
+    fn int64_filetime() -> Result<(), String> {
+        use super::*;
+        use crate::builder::field_body;
+        use crate::fields::field_factory;
+
+        assert_eq!(filetime_to_unix(116_444_736_000_000_000), 0);
+        assert_eq!(filetime_to_unix(116_444_736_009_999_999), 0);
+        assert_eq!(filetime_to_unix(116_444_735_990_000_000), -1);
+        assert_eq!(filetime_to_unix(0), -11_644_473_600);
+
+        let mut col_map = ColumnMap::new();
+        col_map.insert(0, TrackAttrs::Filename);
+        col_map.insert(1, TrackAttrs::Filetime);
+        let field =
+            |id: i32, value: FieldValue| -> std::result::Result<Box<dyn NdeField>, String> {
+                let ftype = match value {
+                    FieldValue::Filename(_) => FieldType::Filename,
+                    _ => FieldType::Int64,
+                };
+                field_factory(
+                    &mut field_body(&value, 0, 0).as_slice(),
+                    id,
+                    ftype,
+                    0,
+                    &crate::ParseOptions::default(),
+                )
+                .map_err(|err| format!("{}", err))
+            };
+        let fields = vec![
+            field(0, FieldValue::Filename(std::path::PathBuf::from("a.mp3")))?,
+            // 2023-04-01T18:30:05Z
+            field(1, FieldValue::Int64(133_248_474_050_000_000))?,
+        ];
+        let track = Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(track.filetime(), Some(1680373805));
+
+        // Past 2038, which won't fit
+        let fields = vec![
+            field(0, FieldValue::Filename(std::path::PathBuf::from("a.mp3")))?,
+            field(1, FieldValue::Int64(i64::MAX))?,
+        ];
+        let track = Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        assert_eq!(track.filetime(), None);
+        Ok(())
+    }
+
+    /// A record with an empty filename shouldn't produce a Track
+    #[test]
+    fn empty_filename() -> Result<(), String> {
+        use super::*;
+        use crate::fields::FilenameField;
+
+        let mut col_map = ColumnMap::new();
+        col_map.insert(0, TrackAttrs::Filename);
+        let filename = FilenameField::new(
+            &mut string_field_bytes("").as_slice(),
+            0,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let fields: Vec<Box<dyn NdeField>> = vec![Box::new(filename)];
+        match Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        ) {
+            Ok(_) => Err(String::from("construction should have failed")),
+            Err(err) => match err.cause() {
+                Cause::NoFilename => Ok(()),
+                _ => Err(format!("unexpected error {}", err)),
+            },
+        }
+    }
+
+    /// Attributes should serialize under their canonical names
+    #[cfg(feature = "serde")]
+    #[test]
+    fn renamed() -> Result<(), String> {
+        use super::*;
+        use crate::fields::{FilenameField, IntegerField};
+
+        let mut col_map = ColumnMap::new();
+        col_map.insert(0, TrackAttrs::Filename);
+        col_map.insert(1, TrackAttrs::Type);
+        col_map.insert(2, TrackAttrs::TrackNo);
+
+        let filename = FilenameField::new(
+            &mut string_field_bytes("a.mp3").as_slice(),
+            0,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let ttype = IntegerField::new(
+            &mut int_field_bytes(0).as_slice(),
+            1,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let trackno = IntegerField::new(
+            &mut int_field_bytes(7).as_slice(),
+            2,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let fields: Vec<Box<dyn NdeField>> =
+            vec![Box::new(filename), Box::new(ttype), Box::new(trackno)];
+        let track = Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+
+        let json = serde_json::to_string(&track).map_err(|err| format!("{}", err))?;
+        assert_eq!(json, r#"{"filename":"a.mp3","track_number":7,"type":0}"#);
+        Ok(())
+    }
+
+    /// An integer field in a column declared DATETIME should be taken as a timestamp
+    #[cfg(feature = "serde")]
+    #[test]
+    fn schema() -> Result<(), String> {
+        use super::*;
+        use crate::fields::{FilenameField, IntegerField};
+
+        let mut col_map = ColumnMap::new();
+        col_map.insert(0, TrackAttrs::Filename);
+        col_map.insert(1, TrackAttrs::LastUpd);
+        let mut col_types = ColumnTypes::new();
+        col_types.insert(0, FieldType::Filename);
+        col_types.insert(1, FieldType::Datetime);
+
+        let filename = FilenameField::new(
+            &mut string_field_bytes("a.mp3").as_slice(),
+            0,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let lastupd = IntegerField::new(
+            &mut int_field_bytes(1602547200).as_slice(),
+            1,
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let fields: Vec<Box<dyn NdeField>> = vec![Box::new(filename), Box::new(lastupd)];
+
+        // Without the column's declared type, the field is dropped...
+        let track = Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let json = serde_json::to_string(&track).map_err(|err| format!("{}", err))?;
+        assert_eq!(json, r#"{"filename":"a.mp3"}"#);
+        // but with it, it's recognized for what it is.
+        let track = Track::new(
+            &col_map,
+            &col_types,
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))?;
+        let json = serde_json::to_string(&track).map_err(|err| format!("{}", err))?;
+        assert_eq!(json, r#"{"filename":"a.mp3","last_updated":1602547200}"#);
+        Ok(())
+    }
+
+    /// Build a Track with the given filename & (optionally) artist
+    fn track(filename: &str, artist: Option<&str>) -> Result<super::Track, String> {
+        use super::*;
+        use crate::fields::{FilenameField, StringField};
+
+        let mut col_map = ColumnMap::new();
+        col_map.insert(0, TrackAttrs::Filename);
+        col_map.insert(1, TrackAttrs::Artist);
+
+        let mut fields: Vec<Box<dyn NdeField>> = vec![Box::new(
+            FilenameField::new(
+                &mut string_field_bytes(filename).as_slice(),
+                0,
+                &crate::ParseOptions::default(),
+            )
+            .map_err(|err| format!("{}", err))?,
+        )];
+        if let Some(artist) = artist {
+            fields.push(Box::new(
+                StringField::new(
+                    &mut string_field_bytes(artist).as_slice(),
+                    1,
+                    &crate::ParseOptions::default(),
+                )
+                .map_err(|err| format!("{}", err))?,
+            ));
+        }
+        Track::new(
+            &col_map,
+            &ColumnTypes::new(),
+            fields.iter(),
+            &crate::ParseOptions::default(),
+        )
+        .map_err(|err| format!("{}", err))
+    }
+
+    /// Filenames should be serialized with forward slashes, whatever the platform
+    #[cfg(feature = "serde")]
+    #[test]
+    fn forward_slashes() -> Result<(), String> {
+        let t = track("C:\\My Music\\a.mp3", None)?;
+        let json = serde_json::to_string(&t).map_err(|err| format!("{}", err))?;
+        assert_eq!(json, r#"{"filename":"C:/My Music/a.mp3"}"#);
+        let t = track("/music/a.mp3", None)?;
+        let json = serde_json::to_string(&t).map_err(|err| format!("{}", err))?;
+        assert_eq!(json, r#"{"filename":"/music/a.mp3"}"#);
+        Ok(())
+    }
+
+    /// Attributes should map to the keys of an iTunes library track
+    #[cfg(feature = "itunes")]
+    #[test]
+    fn itunes_dict() -> Result<(), String> {
+        let t = track("C:\\My Music\\a.mp3", Some("ABC"))?;
+        let dict = t.to_itunes_dict(7);
+        assert_eq!(dict.len(), 3);
+        assert_eq!(
+            dict.get("Track ID").and_then(|x| x.as_signed_integer()),
+            Some(7)
+        );
+        assert_eq!(dict.get("Artist").and_then(|x| x.as_string()), Some("ABC"));
+        assert_eq!(
+            dict.get("Location").and_then(|x| x.as_string()),
+            Some("file://localhost/C:/My%20Music/a.mp3")
+        );
+        assert_eq!(
+            super::file_url(std::path::Path::new("/music/Björk/a.mp3")),
+            "file://localhost/music/Bj%C3%B6rk/a.mp3"
+        );
+        Ok(())
+    }
+
+    /// Attributes should map to the corresponding ID3v2 frames
+    #[cfg(feature = "tags")]
+    #[test]
+    fn tag_fields() -> Result<(), String> {
+        let t = track("a.mp3", Some("ABC"))?;
+        let tags = t.to_tag_fields();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags.get("TPE1").map(|x| x.as_str()), Some("ABC"));
+        assert_eq!(
+            super::of_total(Some(3), Some(12)),
+            Some(String::from("3/12"))
+        );
+        assert_eq!(super::of_total(Some(3), None), Some(String::from("3")));
+        assert_eq!(super::of_total(None, Some(12)), None);
+        Ok(())
+    }
+
+    /// Columns sharing a name should be reported, along with all their IDs
+    #[test]
+    fn duplicate_column_names() -> Result<(), String> {
+        use super::*;
+        use crate::builder::field_body;
+        let cols = [
+            (0, FieldType::Filename, "filename"),
+            (1, FieldType::String, "artist"),
+            (2, FieldType::String, "title"),
+            (7, FieldType::String, "artist"),
+        ]
+        .iter()
+        .map(|(id, col_type, name)| {
+            let value = FieldValue::Column((*col_type as i32, String::from(*name)));
+            ColumnField::new(
+                &mut field_body(&value, 0, 0).as_slice(),
+                *id,
+                &crate::ParseOptions::default(),
+            )
+            .map_err(|err| format!("{}", err))
+        })
+        .collect::<std::result::Result<Vec<ColumnField>, String>>()?;
+        assert_eq!(
+            duplicate_columns(cols.iter()),
+            vec![(String::from("artist"), vec![1, 7])]
+        );
+        assert!(duplicate_columns(cols[..3].iter()).is_empty());
+
+        let col_map = new_column_map(cols.iter(), &crate::ParseOptions::default());
+        assert_eq!(col_map.get(&1), Some(&TrackAttrs::Artist));
+        assert_eq!(col_map.get(&7), Some(&TrackAttrs::Artist));
+        Ok(())
+    }
+
+    /// Column aliases should augment, & take precedence over, the built-in column names
+    #[test]
+    fn column_aliases() -> Result<(), String> {
+        use super::*;
+        use crate::builder::field_body;
+        let cols = [
+            (0, FieldType::Filename, "filename"),
+            (1, FieldType::String, "ARTIST_V2"),
+            (2, FieldType::String, "title"),
+        ]
+        .iter()
+        .map(|(id, col_type, name)| {
+            let value = FieldValue::Column((*col_type as i32, String::from(*name)));
+            ColumnField::new(
+                &mut field_body(&value, 0, 0).as_slice(),
+                *id,
+                &crate::ParseOptions::default(),
+            )
+            .map_err(|err| format!("{}", err))
+        })
+        .collect::<std::result::Result<Vec<ColumnField>, String>>()?;
+        assert_eq!(column_map_with(cols.iter(), &[]).get(&1), None);
+
+        let aliases = vec![
+            parse_column_alias("ARTIST_V2=artist").map_err(|err| format!("{}", err))?,
+            parse_column_alias("title=album").map_err(|err| format!("{}", err))?,
+        ];
+        let col_map = column_map_with(cols.iter(), &aliases);
+        assert_eq!(col_map.get(&0), Some(&TrackAttrs::Filename));
+        assert_eq!(col_map.get(&1), Some(&TrackAttrs::Artist));
+        assert_eq!(col_map.get(&2), Some(&TrackAttrs::Album));
+
+        assert!(parse_column_alias("ARTIST_V2").is_err());
+        assert!(parse_column_alias("=artist").is_err());
+        assert!(parse_column_alias("ARTIST_V2=singer").is_err());
+        Ok(())
+    }
+
+    /// Dates should parse as UTC seconds since the epoch, & bound half-open windows
+    #[test]
+    fn date_windows() -> Result<(), String> {
+        use super::*;
+        let date = |x: &str| parse_date(x).map_err(|err| format!("{}", err));
+        assert_eq!(date("1970-01-01")?, 0);
+        assert_eq!(date("2000-03-01")?, 951868800);
+        assert_eq!(date("2023-04-01T18:30:05Z")?, 1680373805);
+        assert_eq!(date("2023-04-01T18:30:05")?, 1680373805);
+        assert!(parse_date("2023-4-1").is_err());
+        assert!(parse_date("2023-13-01").is_err());
+        assert!(parse_date("yesterday").is_err());
+
+        assert!(in_window(None, None, None));
+        assert!(!in_window(None, Some(0), None));
+        assert!(in_window(Some(10), Some(10), Some(11)));
+        assert!(!in_window(Some(11), Some(10), Some(11)));
+        assert!(!in_window(Some(9), Some(10), None));
+        assert!(TrackFilter::default().is_empty());
+        Ok(())
+    }
+
+    /// Given a representation for missing attributes, every attribute should be serialized
+    #[cfg(feature = "serde")]
+    #[test]
+    fn with_nulls() -> Result<(), String> {
+        use super::*;
+        use std::convert::TryFrom;
+
+        let t = track("a.mp3", Some("ABC"))?;
+        let null_as = NullAs::try_from("sentinel:\\N").map_err(|err| format!("{}", err))?;
+        let json = serde_json::to_value(&WithNulls::new(&t, &null_as))
+            .map_err(|err| format!("{}", err))?;
+        assert_eq!(json["filename"], "a.mp3");
+        assert_eq!(json["artist"], "ABC");
+        assert_eq!(json["title"], "\\N");
+        assert_eq!(
+            json.as_object().map(|x| x.len()),
+            Some(ATTRIBUTE_NAMES.len())
+        );
+
+        let json = serde_json::to_value(&WithNulls::new(&t, &NullAs::Null))
+            .map_err(|err| format!("{}", err))?;
+        assert!(json["album"].is_null());
+        assert!(NullAs::try_from("nil").is_err());
+        Ok(())
+    }
+
+    /// Sorting should be case-insensitive, put missing attributes last & fall through to
+    /// subsequent keys on ties
+    #[test]
+    fn sort() -> Result<(), String> {
+        use super::*;
+        let mut trks = vec![
+            track("d.mp3", None)?,
+            track("c.mp3", Some("b"))?,
+            track("b.mp3", Some("A"))?,
+            track("a.mp3", Some("B"))?,
+        ];
+        let keys = parse_sort_keys("artist, filename").map_err(|err| format!("{}", err))?;
+        trks.sort_by(|l, r| compare_tracks(l, r, &keys));
+        let names: Vec<&str> = trks
+            .iter()
+            .map(|t| t.filename().to_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["b.mp3", "a.mp3", "c.mp3", "d.mp3"]);
+
+        assert!(parse_sort_keys("artist,bogus").is_err());
+        Ok(())
+    }
 }
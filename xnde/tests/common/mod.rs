@@ -0,0 +1,55 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! Utilities shared among integration tests & benchmarks: the fixture's schema, atop the crate's
+//! own NDE builder
+
+// Not every test or benchmark uses every helper
+#![allow(dead_code)]
+
+pub use xnde::builder::*;
+use xnde::fields::FieldType;
+
+// NDE field types, for brevity
+pub const STRING: FieldType = FieldType::String;
+pub const INTEGER: FieldType = FieldType::Integer;
+pub const LENGTH: FieldType = FieldType::Length;
+pub const FILENAME: FieldType = FieldType::Filename;
+pub const INT64: FieldType = FieldType::Int64;
+
+// Column IDs in our fixture
+pub const FILENAME_COL: u8 = 0;
+pub const ARTIST_COL: u8 = 1;
+pub const TITLE_COL: u8 = 2;
+pub const LENGTH_COL: u8 = 3;
+pub const YEAR_COL: u8 = 4;
+pub const FILESIZE_COL: u8 = 5;
+
+/// Add the records defining our columns & indicies to `table`
+pub fn add_meta(table: &mut Table) {
+    table.add_record(&[
+        column(FILENAME_COL, FILENAME, "filename"),
+        column(ARTIST_COL, STRING, "artist"),
+        column(TITLE_COL, STRING, "title"),
+        column(LENGTH_COL, LENGTH, "length"),
+        column(YEAR_COL, INTEGER, "year"),
+        column(FILESIZE_COL, INT64, "filesize"),
+    ]);
+    table.add_record(&[
+        index(0, 255, 255, "None"),
+        index(1, 0, FILENAME as i32, "filename"),
+    ]);
+}
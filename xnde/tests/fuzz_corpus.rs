@@ -0,0 +1,87 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! Seed the fuzzers' corpora from the test fixture
+//!
+//! To keep binary files out of the repo, the seed corpus isn't checked in; instead, run
+//!
+//! ```text
+//! cargo test --test fuzz_corpus -- --ignored
+//! ```
+//!
+//! to write it to `fuzz/corpus`, then `cargo fuzz run field_factory` (or `read_indicies`, or
+//! `parse_bytes`) as usual.
+
+mod common;
+
+use common::*;
+
+use std::path::Path;
+
+/// Lay out a field the way the `field_factory` fuzz target expects: its type, followed by
+/// everything that follows the field's ID & type on disk
+fn seed(field: &Field) -> Vec<u8> {
+    let (_, ftype, data) = field;
+    let mut buf: Vec<u8> = vec![*ftype as u8];
+    buf.extend_from_slice(&body(data, 0, 0));
+    buf
+}
+
+#[test]
+#[ignore]
+fn write_seed_corpus() -> Result<(), String> {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz")
+        .join("corpus");
+
+    let fields = [
+        column(FILENAME_COL, FILENAME, "filename"),
+        index(1, 0, FILENAME as i32, "filename"),
+        string(FILENAME_COL, FILENAME, "/music/abba/waterloo.mp3"),
+        string(ARTIST_COL, STRING, "ABBA"),
+        utf16_string(TITLE_COL, "Jóga"),
+        integer(LENGTH_COL, LENGTH, 166),
+        integer(YEAR_COL, INTEGER, 1974),
+        int64(FILESIZE_COL, 2654208),
+    ];
+    let dir = corpus.join("field_factory");
+    std::fs::create_dir_all(&dir).map_err(|err| format!("{}", err))?;
+    for (i, field) in fields.iter().enumerate() {
+        std::fs::write(dir.join(format!("field-{}", i)), seed(field))
+            .map_err(|err| format!("{}", err))?;
+    }
+
+    let mut table = Table::new();
+    add_meta(&mut table);
+    table.add_record(&[
+        string(FILENAME_COL, FILENAME, "/music/abba/waterloo.mp3"),
+        string(ARTIST_COL, STRING, "ABBA"),
+    ]);
+    let dir = corpus.join("read_indicies");
+    std::fs::create_dir_all(&dir).map_err(|err| format!("{}", err))?;
+    std::fs::write(dir.join("main.idx"), table.index()).map_err(|err| format!("{}", err))?;
+
+    // parse_bytes expects the index's length, the index, then the data file
+    let idx = table.index();
+    let mut pair: Vec<u8> = (idx.len() as u32).to_le_bytes().to_vec();
+    pair.extend_from_slice(&idx);
+    pair.extend_from_slice(&table.buf);
+    let dir = corpus.join("parse_bytes");
+    std::fs::create_dir_all(&dir).map_err(|err| format!("{}", err))?;
+    std::fs::write(dir.join("main"), pair).map_err(|err| format!("{}", err))?;
+
+    Ok(())
+}
@@ -0,0 +1,656 @@
+// Copyright (C) 2020-2023 Michael Herstine <sp1ff@pobox.com>
+//
+// This file is part of xnde.
+//
+// xnde is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// xnde is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with xnde.  If not, see <http://www.gnu.org/licenses/>. *
+//! End-to-end tests against a small, hand-built NDE table
+//!
+//! The fixture is built up byte-by-byte below, both to keep binary files out of the repo & to
+//! document the on-disk format by example. It has three tracks, preceded by the usual column &
+//! index records:
+//!
+//! | record | contents                                                      |
+//! |--------|---------------------------------------------------------------|
+//! | 0      | columns: filename, artist, title, length, year, filesize      |
+//! | 1      | indicies: primary & filename                                  |
+//! | 2      | a track with every column set                                 |
+//! | 3      | a sparse track (no year nor filesize)                         |
+//! | 4      | a track whose title is UTF-16 encoded (with a BOM)            |
+
+mod common;
+
+use common::*;
+use xnde::{
+    diff, dump, export, read_index_definitions,
+    tracks::{AttrValue, TrackAttrs},
+    DumpOptions, ExportOptions, Format, ParseOptions,
+};
+
+use std::path::{Path, PathBuf};
+
+/// Build the fixture table
+fn fixture() -> Table {
+    let mut table = Table::new();
+    add_meta(&mut table);
+    table.add_record(&[
+        string(FILENAME_COL, FILENAME, "/music/abba/waterloo.mp3"),
+        string(ARTIST_COL, STRING, "ABBA"),
+        string(TITLE_COL, STRING, "Waterloo"),
+        integer(LENGTH_COL, LENGTH, 166),
+        integer(YEAR_COL, INTEGER, 1974),
+        int64(FILESIZE_COL, 2654208),
+    ]);
+    table.add_record(&[
+        string(FILENAME_COL, FILENAME, "/music/blondie/atomic.mp3"),
+        string(ARTIST_COL, STRING, "Blondie"),
+        string(TITLE_COL, STRING, "Atomic"),
+        integer(LENGTH_COL, LENGTH, 274),
+    ]);
+    table.add_record(&[
+        string(FILENAME_COL, FILENAME, "/music/bjork/joga.mp3"),
+        string(ARTIST_COL, STRING, "Björk"),
+        utf16_string(TITLE_COL, "Jóga"),
+        integer(LENGTH_COL, LENGTH, 305),
+        integer(YEAR_COL, INTEGER, 1997),
+        int64(FILESIZE_COL, 4882432),
+    ]);
+    table
+}
+
+/// Write the fixture to a fresh directory named for `test`; return the paths of the index &
+/// data files
+fn write_fixture(test: &str) -> std::io::Result<(PathBuf, PathBuf)> {
+    write_table(test, &fixture())
+}
+
+/// Write `table` to a fresh directory named for `test`; return the paths of the index & data files
+fn write_table(test: &str, table: &Table) -> std::io::Result<(PathBuf, PathBuf)> {
+    let dir = std::env::temp_dir().join(format!("xnde-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let idx = dir.join("main.idx");
+    let dat = dir.join("main.dat");
+    std::fs::write(&idx, table.index())?;
+    std::fs::write(&dat, &table.buf)?;
+    Ok((idx, dat))
+}
+
+fn golden(name: &str) -> Result<serde_json::Value, String> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data")
+        .join(name);
+    let text = std::fs::read_to_string(&path).map_err(|err| format!("{}", err))?;
+    serde_json::from_str(&text).map_err(|err| format!("{}", err))
+}
+
+/// Exporting the fixture to JSON should produce the golden output
+#[test]
+fn export_json() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-json").map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("main.json");
+    export(&idx, &dat, Format::Json, Some(&out), &ExportOptions::new())
+        .map_err(|err| format!("{}", err))?;
+    let text = std::fs::read_to_string(&out).map_err(|err| format!("{}", err))?;
+    let exported: serde_json::Value =
+        serde_json::from_str(&text).map_err(|err| format!("{}", err))?;
+    assert_eq!(exported, golden("main.json")?);
+    Ok(())
+}
+
+/// Reading on several threads should work against a gzip'd data file, which has to be
+/// decompressed before any worker can seek in it
+#[cfg(all(feature = "parallel", feature = "gzip"))]
+#[test]
+fn export_parallel_gzip() -> Result<(), String> {
+    use std::io::Write;
+    let (idx, dat) = write_fixture("export-parallel-gzip").map_err(|err| format!("{}", err))?;
+    let gz = dat.with_file_name("main.dat.gz");
+    let mut enc = flate2::write::GzEncoder::new(
+        std::fs::File::create(&gz).map_err(|err| format!("{}", err))?,
+        flate2::Compression::default(),
+    );
+    enc.write_all(&std::fs::read(&dat).map_err(|err| format!("{}", err))?)
+        .map_err(|err| format!("{}", err))?;
+    enc.finish().map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("main.json");
+    export(
+        &idx,
+        &gz,
+        Format::Json,
+        Some(&out),
+        &ExportOptions::new().threads(2),
+    )
+    .map_err(|err| format!("{}", err))?;
+    let text = std::fs::read_to_string(&out).map_err(|err| format!("{}", err))?;
+    let exported: serde_json::Value =
+        serde_json::from_str(&text).map_err(|err| format!("{}", err))?;
+    assert_eq!(exported, golden("main.json")?);
+    Ok(())
+}
+
+/// Asking for the metadata records should export them alongside the tracks
+#[test]
+fn export_meta() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-meta").map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("main.json");
+    export(
+        &idx,
+        &dat,
+        Format::Json,
+        Some(&out),
+        &ExportOptions::new().include_meta(true),
+    )
+    .map_err(|err| format!("{}", err))?;
+    let text = std::fs::read_to_string(&out).map_err(|err| format!("{}", err))?;
+    let exported: serde_json::Value =
+        serde_json::from_str(&text).map_err(|err| format!("{}", err))?;
+    // one record of columns, one of indicies
+    assert_eq!(exported["meta"].as_array().map(|x| x.len()), Some(2));
+    assert_eq!(exported["meta"][0].as_array().map(|x| x.len()), Some(6));
+    assert_eq!(exported["tracks"], golden("main.json")?);
+    Ok(())
+}
+
+/// A raw export should write every record, as a list of its fields
+#[test]
+fn export_raw() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-raw").map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("main.json");
+    export(
+        &idx,
+        &dat,
+        Format::Json,
+        Some(&out),
+        &ExportOptions::new().raw(true),
+    )
+    .map_err(|err| format!("{}", err))?;
+    let text = std::fs::read_to_string(&out).map_err(|err| format!("{}", err))?;
+    let exported: serde_json::Value =
+        serde_json::from_str(&text).map_err(|err| format!("{}", err))?;
+    let lens = exported.as_array().map(|recs| {
+        recs.iter()
+            .map(|rec| rec.as_array().map(|x| x.len()))
+            .collect()
+    });
+    assert_eq!(
+        lens,
+        Some(vec![Some(6), Some(2), Some(6), Some(4), Some(6)])
+    );
+    Ok(())
+}
+
+/// Exporting to an iTunes library should produce a property list with a dictionary per track
+#[cfg(feature = "itunes")]
+#[test]
+fn export_itunes() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-itunes").map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("library.xml");
+    export(
+        &idx,
+        &dat,
+        Format::Itunes,
+        Some(&out),
+        &ExportOptions::new(),
+    )
+    .map_err(|err| format!("{}", err))?;
+    let library = plist::Value::from_file(&out).map_err(|err| format!("{}", err))?;
+    let tracks = library
+        .as_dictionary()
+        .and_then(|x| x.get("Tracks"))
+        .and_then(|x| x.as_dictionary())
+        .ok_or_else(|| String::from("no Tracks dictionary"))?;
+    assert_eq!(tracks.len(), 3);
+    let waterloo = tracks
+        .get("1")
+        .and_then(|x| x.as_dictionary())
+        .ok_or_else(|| String::from("no track 1"))?;
+    assert_eq!(
+        waterloo.get("Name").and_then(|x| x.as_string()),
+        Some("Waterloo")
+    );
+    assert_eq!(
+        waterloo
+            .get("Total Time")
+            .and_then(|x| x.as_signed_integer()),
+        Some(166000)
+    );
+    assert_eq!(
+        waterloo.get("Location").and_then(|x| x.as_string()),
+        Some("file://localhost/music/abba/waterloo.mp3")
+    );
+    Ok(())
+}
+
+/// A dry run should parse everything, but write nothing
+#[test]
+fn export_dry_run() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-dry-run").map_err(|err| format!("{}", err))?;
+    export(&idx, &dat, Format::Json, None, &ExportOptions::new())
+        .map_err(|err| format!("{}", err))?;
+    assert!(!idx.with_file_name("main.json").exists());
+    Ok(())
+}
+
+/// The async API should produce the same tracks as the synchronous one
+#[cfg(feature = "async")]
+#[test]
+fn read_async() -> Result<(), String> {
+    let table = fixture();
+    let idx = table.index();
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .map_err(|err| format!("{}", err))?;
+    let trks = rt
+        .block_on(xnde::read_tracks(
+            idx.as_slice(),
+            table.buf.as_slice(),
+            &ParseOptions::default(),
+        ))
+        .map_err(|err| format!("{}", err))?;
+    let trks = trks
+        .map(|t| t.map(|(_, t)| t))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("{}", err))?;
+    let exported = serde_json::to_value(&trks).map_err(|err| format!("{}", err))?;
+    assert_eq!(exported, golden("main.json")?);
+    Ok(())
+}
+
+/// Parsing & serializing in memory should produce the same tracks as exporting from files
+#[test]
+fn parse_in_memory() -> Result<(), String> {
+    let table = fixture();
+    let trks = xnde::parse_bytes(&table.index(), &table.buf, &ParseOptions::default())
+        .map_err(|err| format!("{}", err))?;
+    assert_eq!(trks.len(), 3);
+    let buf = xnde::serialize_tracks(&trks, Format::Json).map_err(|err| format!("{}", err))?;
+    let exported: serde_json::Value =
+        serde_json::from_slice(&buf).map_err(|err| format!("{}", err))?;
+    assert_eq!(exported, golden("main.json")?);
+    assert!(xnde::serialize_tracks(&trks, Format::Display).is_err());
+    Ok(())
+}
+
+/// Dumping the fixture should walk every record, meta-records included, without error
+#[test]
+fn dump_all() -> Result<(), String> {
+    let (idx, dat) = write_fixture("dump-all").map_err(|err| format!("{}", err))?;
+    dump(&idx, &dat, Format::Json, &DumpOptions::new()).map_err(|err| format!("{}", err))?;
+    dump(
+        &idx,
+        &dat,
+        Format::Display,
+        &DumpOptions::new().skip_meta(true),
+    )
+    .map_err(|err| format!("{}", err))?;
+    dump(
+        &idx,
+        &dat,
+        Format::Display,
+        &DumpOptions::new().max_records(Some(1)),
+    )
+    .map_err(|err| format!("{}", err))?;
+    dump(
+        &idx,
+        &dat,
+        Format::Display,
+        &DumpOptions::new().tail(Some(2)),
+    )
+    .map_err(|err| format!("{}", err))?;
+    dump(
+        &idx,
+        &dat,
+        Format::Display,
+        &DumpOptions::new().record(Some(3)),
+    )
+    .map_err(|err| format!("{}", err))?;
+    // There are only five records, meta-records included
+    assert!(dump(
+        &idx,
+        &dat,
+        Format::Display,
+        &DumpOptions::new().record(Some(5))
+    )
+    .is_err());
+    Ok(())
+}
+
+/// Diffing the fixture against a later version of itself should pick up each kind of change
+#[test]
+fn diff_libraries() -> Result<(), String> {
+    let (old_idx, old_dat) = write_fixture("diff").map_err(|err| format!("{}", err))?;
+    // Drop Blondie, add Cher & update ABBA's length & year
+    let mut table = Table::new();
+    add_meta(&mut table);
+    table.add_record(&[
+        string(FILENAME_COL, FILENAME, "/music/abba/waterloo.mp3"),
+        string(ARTIST_COL, STRING, "ABBA"),
+        string(TITLE_COL, STRING, "Waterloo"),
+        integer(LENGTH_COL, LENGTH, 167),
+        int64(FILESIZE_COL, 2654208),
+    ]);
+    table.add_record(&[
+        string(FILENAME_COL, FILENAME, "/music/bjork/joga.mp3"),
+        string(ARTIST_COL, STRING, "Björk"),
+        utf16_string(TITLE_COL, "Jóga"),
+        integer(LENGTH_COL, LENGTH, 305),
+        integer(YEAR_COL, INTEGER, 1997),
+        int64(FILESIZE_COL, 4882432),
+    ]);
+    table.add_record(&[
+        string(FILENAME_COL, FILENAME, "/music/cher/believe.mp3"),
+        string(ARTIST_COL, STRING, "Cher"),
+    ]);
+    let new_idx = old_idx.with_file_name("new.idx");
+    let new_dat = old_idx.with_file_name("new.dat");
+    std::fs::write(&new_idx, table.index()).map_err(|err| format!("{}", err))?;
+    std::fs::write(&new_dat, &table.buf).map_err(|err| format!("{}", err))?;
+
+    let text = |x: &str| AttrValue::Text(String::from(x));
+    let diffs = diff(
+        &old_idx,
+        &old_dat,
+        &new_idx,
+        &new_dat,
+        &TrackAttrs::Filename,
+        &ParseOptions::default(),
+    )
+    .map_err(|err| format!("{}", err))?;
+    assert_eq!(diffs.added, vec![text("/music/cher/believe.mp3")]);
+    assert_eq!(diffs.removed, vec![text("/music/blondie/atomic.mp3")]);
+    assert_eq!(diffs.changed.len(), 1);
+    assert_eq!(diffs.changed[0].key, text("/music/abba/waterloo.mp3"));
+    let changes = diffs.changed[0]
+        .changes
+        .iter()
+        .map(|c| format!("{}", c))
+        .collect::<Vec<String>>();
+    assert_eq!(changes, vec!["length: 166 -> 167", "year: 1974 -> (none)"]);
+
+    // Matching up tracks by artist should give the same result
+    let by_artist = diff(
+        &old_idx,
+        &old_dat,
+        &new_idx,
+        &new_dat,
+        &TrackAttrs::Artist,
+        &ParseOptions::default(),
+    )
+    .map_err(|err| format!("{}", err))?;
+    assert_eq!(by_artist.added, vec![text("Cher")]);
+    assert_eq!(by_artist.removed, vec![text("Blondie")]);
+    assert_eq!(by_artist.changed.len(), 1);
+    assert_eq!(by_artist.changed[0].key, text("ABBA"));
+
+    let same = diff(
+        &old_idx,
+        &old_dat,
+        &old_idx,
+        &old_dat,
+        &TrackAttrs::Filename,
+        &ParseOptions::default(),
+    )
+    .map_err(|err| format!("{}", err))?;
+    assert!(same.is_empty());
+    Ok(())
+}
+
+/// The fixture's index definitions should be readable
+#[test]
+fn index_definitions() -> Result<(), String> {
+    let (idx, dat) = write_fixture("index-definitions").map_err(|err| format!("{}", err))?;
+    let defs = read_index_definitions(&idx, &dat, &ParseOptions::default())
+        .map_err(|err| format!("{}", err))?;
+    let names = defs.iter().map(|x| x.name()).collect::<Vec<String>>();
+    assert_eq!(names, vec!["None", "filename"]);
+    assert_eq!(defs[1].ftype(), FILENAME as i32);
+    Ok(())
+}
+
+/// Exporting to ndjson with `append` should add to, rather than replace, the output
+#[test]
+fn export_append() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-append").map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("main.ndjson");
+    let _ = std::fs::remove_file(&out);
+    for _ in 0..2 {
+        export(
+            &idx,
+            &dat,
+            Format::Ndjson,
+            Some(&out),
+            &ExportOptions::new().append(true),
+        )
+        .map_err(|err| format!("{}", err))?;
+    }
+    let text = std::fs::read_to_string(&out).map_err(|err| format!("{}", err))?;
+    let lines = text
+        .lines()
+        .map(|line| serde_json::from_str(line).map_err(|err| format!("{}", err)))
+        .collect::<Result<Vec<serde_json::Value>, String>>()?;
+    assert_eq!(lines.len(), 6);
+    assert_eq!(
+        serde_json::Value::Array(lines[..3].to_vec()),
+        golden("main.json")?
+    );
+    assert_eq!(lines[..3], lines[3..]);
+
+    // but appending to JSON is ill-defined
+    assert!(export(
+        &idx,
+        &dat,
+        Format::Json,
+        Some(&out),
+        &ExportOptions::new().append(true),
+    )
+    .is_err());
+    Ok(())
+}
+
+/// Raw exports should honour `append` just as track exports do
+#[test]
+fn export_raw_append() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-raw-append").map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("main.ndjson");
+    let _ = std::fs::remove_file(&out);
+    for _ in 0..2 {
+        export(
+            &idx,
+            &dat,
+            Format::Ndjson,
+            Some(&out),
+            &ExportOptions::new().raw(true).append(true),
+        )
+        .map_err(|err| format!("{}", err))?;
+    }
+    let text = std::fs::read_to_string(&out).map_err(|err| format!("{}", err))?;
+    let lines: Vec<&str> = text.lines().collect();
+    // two meta records & three tracks, twice over
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[..5], lines[5..]);
+
+    assert!(export(
+        &idx,
+        &dat,
+        Format::Json,
+        Some(&out),
+        &ExportOptions::new().raw(true).append(true),
+    )
+    .is_err());
+    Ok(())
+}
+
+/// A record that can't be made into a track should be identified in the resulting error
+#[test]
+fn record_errors() -> Result<(), String> {
+    let mut table = fixture();
+    table.add_record(&[string(FILENAME_COL, FILENAME, "")]);
+    let offset = table.offsets[5] as u64;
+    let (idx, dat) = write_table("record-errors", &table).map_err(|err| format!("{}", err))?;
+
+    let err = xnde::tracks(&idx, &dat, &ParseOptions::default())
+        .map_err(|err| format!("{}", err))?
+        .find_map(|t| t.err())
+        .ok_or_else(|| String::from("the empty filename should have been an error"))?;
+    match err.cause() {
+        xnde::Cause::RecordParse { record, offset: at } => {
+            assert_eq!(*record, 5);
+            assert_eq!(*at, offset);
+        }
+        _ => return Err(format!("unexpected error {}", err)),
+    }
+    assert!(std::error::Error::source(&err).is_some());
+    Ok(())
+}
+
+/// Asked to, export should skip records it can't parse, rather than giving up
+#[test]
+fn export_skip_errors() -> Result<(), String> {
+    let mut table = fixture();
+    table.add_record(&[string(FILENAME_COL, FILENAME, "")]);
+    let (idx, dat) = write_table("skip-errors", &table).map_err(|err| format!("{}", err))?;
+    let out = idx.with_file_name("main.json");
+    let export_with = |skip_errors: bool| {
+        export(
+            &idx,
+            &dat,
+            Format::Json,
+            Some(&out),
+            &ExportOptions::new().skip_errors(skip_errors),
+        )
+    };
+    assert!(export_with(false).is_err());
+    export_with(true).map_err(|err| format!("{}", err))?;
+    let text = std::fs::read_to_string(&out).map_err(|err| format!("{}", err))?;
+    let exported: serde_json::Value =
+        serde_json::from_str(&text).map_err(|err| format!("{}", err))?;
+    assert_eq!(exported, golden("main.json")?);
+    Ok(())
+}
+
+/// Export should give up on a table once it has read more than it was permitted to
+#[test]
+fn export_limits() -> Result<(), String> {
+    let (idx, dat) = write_fixture("export-limits").map_err(|err| format!("{}", err))?;
+    let export_with = |opts: ExportOptions| export(&idx, &dat, Format::Json, None, &opts);
+    let limited = |opts: ExportOptions, expected: &str| match export_with(opts) {
+        Ok(_) => Err(format!("expected the {} limit to be exceeded", expected)),
+        Err(err) => match err.cause() {
+            xnde::Cause::LimitExceeded { what, .. } if *what == expected => Ok(()),
+            _ => Err(format!("{}", err)),
+        },
+    };
+    export_with(ExportOptions::new().max_records(Some(3))).map_err(|err| format!("{}", err))?;
+    limited(ExportOptions::new().max_records(Some(2)), "records")?;
+    export_with(ExportOptions::new().max_bytes(Some(1 << 20))).map_err(|err| format!("{}", err))?;
+    limited(ExportOptions::new().max_bytes(Some(1)), "bytes")?;
+    Ok(())
+}
+
+/// A record whose fields loop back on themselves mustn't have the export reading forever, limits
+/// or no
+#[test]
+fn export_cyclic_record() -> Result<(), String> {
+    let mut table = fixture();
+    // Point the first field of the last record back at itself
+    let at = *table.offsets.last().ok_or("no records")? as usize;
+    table.buf[at + 6..at + 10].copy_from_slice(&(at as u32).to_le_bytes());
+    let (idx, dat) = write_table("export-cyclic", &table).map_err(|err| format!("{}", err))?;
+    for opts in [
+        ExportOptions::new(),
+        ExportOptions::new()
+            .max_records(Some(10))
+            .max_bytes(Some(4096)),
+    ] {
+        match export(&idx, &dat, Format::Json, None, &opts) {
+            Ok(_) => return Err(String::from("the export should have failed")),
+            Err(err) => match err.cause() {
+                xnde::Cause::RecordParse { .. } | xnde::Cause::FieldCycle { .. } => (),
+                _ => return Err(format!("unexpected error {}", err)),
+            },
+        }
+    }
+    Ok(())
+}
+
+/// The `Table` facade should expose the columns, & both random & sequential access to tracks
+#[test]
+fn table_facade() -> Result<(), String> {
+    let (idx, dat) = write_fixture("table-facade").map_err(|err| format!("{}", err))?;
+    let mut table = xnde::Table::open(&idx, &dat, &ParseOptions::default())
+        .map_err(|err| format!("{}", err))?;
+    assert_eq!(table.len(), 3);
+    assert_eq!(table.columns().len(), 6);
+    assert_eq!(table.indices().indices().len(), 2);
+    assert_eq!(table.indices().record_order(0), Some(vec![4, 3, 2, 1, 0]));
+
+    let trk = table.track(3).map_err(|err| format!("{}", err))?;
+    assert_eq!(trk.artist(), Some("Blondie"));
+    assert!(table.track(5).is_err());
+
+    let filenames = table
+        .tracks()
+        .map(|t| t.map(|(i, t)| (i, t.filename().to_path_buf())))
+        .collect::<Result<Vec<(usize, PathBuf)>, xnde::Error>>()
+        .map_err(|err| format!("{}", err))?;
+    assert_eq!(
+        filenames,
+        vec![
+            (2, PathBuf::from("/music/abba/waterloo.mp3")),
+            (3, PathBuf::from("/music/blondie/atomic.mp3")),
+            (4, PathBuf::from("/music/bjork/joga.mp3")),
+        ]
+    );
+    Ok(())
+}
+
+/// Exporting a directory should write each table to its own file, falling back to raw records
+/// for tables that don't hold tracks
+#[test]
+fn export_all_tables() -> Result<(), String> {
+    let (idx, _) = write_fixture("export-all-tables").map_err(|err| format!("{}", err))?;
+    let dir = idx.parent().ok_or("no parent")?.to_path_buf();
+    let mut playlists = Table::new();
+    playlists.add_record(&[column(0, STRING, "title"), column(1, INTEGER, "entries")]);
+    playlists.add_record(&[index(0, 255, 255, "None")]);
+    playlists.add_record(&[string(0, STRING, "Favorites"), integer(1, INTEGER, 12)]);
+    std::fs::write(dir.join("playlists.idx"), playlists.index())
+        .map_err(|err| format!("{}", err))?;
+    std::fs::write(dir.join("playlists.dat"), &playlists.buf).map_err(|err| format!("{}", err))?;
+    // An index file without its data file isn't a table
+    std::fs::write(dir.join("orphan.idx"), playlists.index()).map_err(|err| format!("{}", err))?;
+
+    assert_eq!(
+        xnde::find_tables(&dir).map_err(|err| format!("{}", err))?,
+        vec![String::from("main"), String::from("playlists")]
+    );
+
+    let out = dir.join("out");
+    let written = xnde::export_tables(&dir, Format::Json, Some(&out), &ExportOptions::new())
+        .map_err(|err| format!("{}", err))?;
+    assert_eq!(written.len(), 2);
+
+    let text = std::fs::read_to_string(out.join("main.json")).map_err(|err| format!("{}", err))?;
+    let main: serde_json::Value = serde_json::from_str(&text).map_err(|err| format!("{}", err))?;
+    assert_eq!(main, golden("main.json")?);
+
+    let text =
+        std::fs::read_to_string(out.join("playlists.json")).map_err(|err| format!("{}", err))?;
+    let recs: serde_json::Value = serde_json::from_str(&text).map_err(|err| format!("{}", err))?;
+    assert_eq!(recs.as_array().map(|x| x.len()), Some(3));
+    assert!(text.contains("Favorites"));
+
+    assert!(xnde::export_tables(&out, Format::Json, None, &ExportOptions::new()).is_err());
+    Ok(())
+}